@@ -18,6 +18,12 @@ class ConfigInjector {
     return 'proxies: []\nproxy-groups: []\nrules: []';
   }
 
+  // 运行时配置文件（runtime_config.yaml）路径
+  static Future<String> getRuntimeConfigPath() async {
+    final geoDataDir = await GeoService.getGeoDataDir();
+    return path.join(geoDataDir, 'runtime_config.yaml');
+  }
+
   // 注入用户自定义配置参数到配置文件
   //
   // 新架构：所有 YAML 处理在 Rust 端完成，避免 Dart 重复解析
@@ -30,12 +36,15 @@ class ConfigInjector {
   // - configPath: 配置文件路径（可选）
   // - configContent: 配置内容（可选，优先使用）
   // - overrides: 覆写列表
+  // - subscriptionName / subscriptionUrl: 当前订阅，用于判断覆写生效条件
   //
   // 返回值：runtime_config.yaml 的绝对路径
   static Future<String?> injectCustomConfigParams({
     String? configPath,
     String? configContent,
     List<OverrideConfig> overrides = const [],
+    String subscriptionName = '',
+    String subscriptionUrl = '',
     required int httpPort,
     required bool isIpv6Enabled,
     required bool isTunEnabled,
@@ -115,13 +124,39 @@ class ConfigInjector {
         externalControllerSecret: externalControllerSecret,
         isKeepAliveEnabled: isKeepAliveEnabled,
         keepAliveInterval: keepAliveInterval,
+        // 以下参数暂无设置项，使用默认值（保留订阅或覆写中的配置）
+        httpProxyPort: null,
+        socksPort: null,
+        redirPort: null,
+        tproxyPort: null,
+        listeners: const [],
+        isSnifferEnabled: null,
+        snifferHttpPorts: const [],
+        snifferTlsPorts: const [],
+        snifferQuicPorts: const [],
+        snifferForceDomain: const [],
+        snifferSkipDomain: const [],
+        isSnifferOverrideDestinationEnabled: null,
+        dnsProfile: null,
+        authentication: const [],
+        skipAuthPrefixes: const [],
+        isLanAuthRequired: false,
+        hosts: const [],
+        isDnsUseHostsEnabled: null,
+        isDnsUseSystemHostsEnabled: null,
       );
 
       // 3. 调用 Rust 统一处理（覆写 + 参数注入 + YAML 序列化）
       final request = GenerateRuntimeConfigRequest(
         baseConfigContent: content,
         overrides: overrides,
+        subscriptionName: subscriptionName,
+        subscriptionUrl: subscriptionUrl,
+        templateVariables: const {},
+        allowedEnvVariables: const [],
         runtimeParams: params,
+        // 端口被占用时生成失败并给出占用进程，避免核心启动时只报笼统的绑定错误
+        portConflictPolicy: PortConflictPolicy.report,
       );
 
       request.sendSignalToRust();
@@ -142,9 +177,12 @@ class ConfigInjector {
         return null;
       }
 
+      for (final warning in response.message.validationWarnings) {
+        Logger.warning('运行时配置警告 [${warning.path}]：${warning.message}');
+      }
+
       // 4. 写入 runtime_config.yaml
-      final geoDataDir = await GeoService.getGeoDataDir();
-      final runtimeConfigPath = path.join(geoDataDir, 'runtime_config.yaml');
+      final runtimeConfigPath = await getRuntimeConfigPath();
       await File(
         runtimeConfigPath,
       ).writeAsString(response.message.resultConfig);
@@ -47,6 +47,7 @@ class OverrideConfig {
   final String? content; // 缓存的内容
   final DateTime? lastUpdate; // 最后更新时间
   final SubscriptionProxyMode proxyMode; // 代理模式（仅远程覆写）
+  final String? sha256Pin; // 固定的内容 SHA-256（仅远程覆写，为空表示不校验）
 
   const OverrideConfig({
     required this.id,
@@ -58,6 +59,7 @@ class OverrideConfig {
     this.content,
     this.lastUpdate,
     this.proxyMode = SubscriptionProxyMode.direct,
+    this.sha256Pin,
   });
 
   // 创建新覆写
@@ -69,6 +71,7 @@ class OverrideConfig {
     String? localPath,
     String? content,
     SubscriptionProxyMode proxyMode = SubscriptionProxyMode.direct,
+    String? sha256Pin,
   }) {
     return OverrideConfig(
       id: DateTime.now().millisecondsSinceEpoch.toString(),
@@ -80,6 +83,7 @@ class OverrideConfig {
       content: content,
       lastUpdate: DateTime.now(),
       proxyMode: proxyMode,
+      sha256Pin: sha256Pin,
     );
   }
 
@@ -92,6 +96,7 @@ class OverrideConfig {
     String? content,
     DateTime? lastUpdate,
     SubscriptionProxyMode? proxyMode,
+    String? sha256Pin,
   }) {
     return OverrideConfig(
       id: id,
@@ -103,6 +108,7 @@ class OverrideConfig {
       content: content ?? this.content,
       lastUpdate: lastUpdate ?? this.lastUpdate,
       proxyMode: proxyMode ?? this.proxyMode,
+      sha256Pin: sha256Pin ?? this.sha256Pin,
     );
   }
 
@@ -116,6 +122,7 @@ class OverrideConfig {
     // content 不序列化到 JSON，仅用于内存缓存
     'lastUpdate': lastUpdate?.toIso8601String(),
     'proxyMode': proxyMode.value,
+    'sha256Pin': sha256Pin,
   };
 
   factory OverrideConfig.fromJson(Map<String, dynamic> json) {
@@ -133,6 +140,7 @@ class OverrideConfig {
       proxyMode: SubscriptionProxyMode.fromString(
         json['proxyMode'] ?? 'direct',
       ),
+      sha256Pin: json['sha256Pin'],
     );
   }
 
@@ -1,4 +1,5 @@
 import 'dart:async';
+import 'dart:io';
 import 'package:stelliberty/clash/network/api_client.dart';
 import 'package:stelliberty/clash/config/config_injector.dart';
 import 'package:stelliberty/clash/config/clash_defaults.dart';
@@ -148,9 +149,15 @@ class ConfigManager {
   }
 
   // 重载配置文件
+  //
+  // 对比核心当前使用的运行时配置，由 Rust 端选择 PATCH、完整重载或重启；
+  // 仅当需要重启时调用 onRestartRequired
   Future<bool> reloadConfig({
     String? configPath,
     List<OverrideConfig> overrides = const [],
+    String subscriptionName = '',
+    String subscriptionUrl = '',
+    required Future<bool> Function() onRestartRequired,
   }) async {
     try {
       if (!_isCoreRunning()) {
@@ -158,6 +165,14 @@ class ConfigManager {
         return false;
       }
 
+      // 生成新配置前读取核心当前使用的运行时配置
+      final runningConfigFile = File(
+        await ConfigInjector.getRuntimeConfigPath(),
+      );
+      final runningConfig = await runningConfigFile.exists()
+          ? await runningConfigFile.readAsString()
+          : '';
+
       Logger.debug(
         '重载参数：configPath=$configPath, isTunEnabled=$_isTunEnabled, ipv6=$_isIpv6Enabled, allowLan=$_isAllowLanEnabled',
       );
@@ -168,6 +183,8 @@ class ConfigManager {
       final runtimeConfigPath = await ConfigInjector.injectCustomConfigParams(
         configPath: configPath, // 可以为 null，ConfigInjector 会使用默认配置
         overrides: overrides,
+        subscriptionName: subscriptionName,
+        subscriptionUrl: subscriptionUrl,
         httpPort: _mixedPort,
         isIpv6Enabled: _isIpv6Enabled,
         isTunEnabled: _isTunEnabled,
@@ -201,18 +218,53 @@ class ConfigManager {
 
       actualConfigPath = runtimeConfigPath;
 
-      final success = await _apiClient.reloadConfig(
-        configPath: actualConfigPath,
-        force: true,
-      );
+      // 没有可对比的运行时配置时直接完整重载
+      if (runningConfig.isEmpty) {
+        final success = await _apiClient.reloadConfig(
+          configPath: actualConfigPath,
+          force: true,
+        );
 
-      if (success) {
-        _notifyListeners();
-      } else {
-        Logger.error('配置重载失败');
+        if (success) {
+          _notifyListeners();
+        } else {
+          Logger.error('配置重载失败');
+        }
+
+        return success;
       }
 
-      return success;
+      ReloadRuntimeConfigRequest(
+        runningConfig: runningConfig,
+        newConfig: await File(actualConfigPath).readAsString(),
+      ).sendSignalToRust();
+
+      final response = await ReloadRuntimeConfigResponse.rustSignalStream.first
+          .timeout(
+            const Duration(seconds: 10),
+            onTimeout: () {
+              throw Exception('Rust 配置热重载超时（10秒）');
+            },
+          );
+      final result = response.message;
+
+      if (result.strategy == ReloadStrategy.restart) {
+        Logger.info(
+          '配置变化需要重启核心（变化字段：${result.changedKeys.join(', ')}）${result.errorMessage}',
+        );
+        return await onRestartRequired();
+      }
+
+      if (!result.isSuccessful) {
+        Logger.error('配置热重载失败：${result.errorMessage}');
+        return false;
+      }
+
+      Logger.info(
+        '配置热重载完成：${result.strategy.name}（变化字段：${result.changedKeys.join(', ')}）',
+      );
+      _notifyListeners();
+      return true;
     } catch (e) {
       Logger.error('重载配置文件出错：$e');
       return false;
@@ -5,6 +5,7 @@ import 'package:stelliberty/clash/services/process_service.dart';
 import 'package:stelliberty/clash/config/config_injector.dart';
 import 'package:stelliberty/clash/config/clash_defaults.dart';
 import 'package:stelliberty/clash/services/traffic_monitor.dart';
+import 'package:stelliberty/clash/services/traffic_stats_service.dart';
 import 'package:stelliberty/clash/services/log_service.dart';
 import 'package:stelliberty/clash/services/geo_service.dart';
 import 'package:stelliberty/clash/providers/service_provider.dart';
@@ -92,11 +93,14 @@ class LifecycleManager {
   // 参数：
   // - configPath: 配置文件路径（可选，为空时使用保存的原始路径）
   // - overrides: 覆写配置列表（由 ClashManager 通过回调获取）
+  // - subscriptionName / subscriptionUrl: 当前订阅，用于判断覆写生效条件
   // - enableFallback: 是否启用覆写失败回退（默认 true）
   // - onOverridesFailed: 覆写失败时的回调（用于禁用覆写）
   Future<bool> startCore({
     String? configPath,
     List<OverrideConfig> overrides = const [],
+    String subscriptionName = '',
+    String subscriptionUrl = '',
     bool enableFallback = true,
     Future<void> Function()? onOverridesFailed,
     required int mixedPort, // 混合端口
@@ -165,6 +169,8 @@ class LifecycleManager {
       final generatedConfigPath = await ConfigInjector.injectCustomConfigParams(
         configPath: configPath,
         overrides: overrides,
+        subscriptionName: subscriptionName,
+        subscriptionUrl: subscriptionUrl,
         httpPort: mixedPort, // 传递混合端口给配置注入器
         isIpv6Enabled: isIpv6Enabled,
         isTunEnabled: isTunEnabled,
@@ -269,6 +275,8 @@ class LifecycleManager {
         isStartSuccessful = await startCore(
           configPath: configPath,
           overrides: const [], // 不使用覆写
+          subscriptionName: subscriptionName,
+          subscriptionUrl: subscriptionUrl,
           mixedPort: mixedPort, // 混合端口
           isIpv6Enabled: isIpv6Enabled,
           isTunEnabled: isTunEnabled,
@@ -499,6 +507,12 @@ class LifecycleManager {
         Logger.error('启动日志服务失败：$e');
       }
 
+      try {
+        await TrafficStatsService.start();
+      } catch (e) {
+        Logger.error('启动流量统计失败：$e');
+      }
+
       // 标记为运行状态
       _coreStateManager.setRunning(reason: '核心启动成功');
 
@@ -615,6 +629,12 @@ class LifecycleManager {
         Logger.error('停止日志服务失败：$e');
       }
 
+      try {
+        await TrafficStatsService.stop();
+      } catch (e) {
+        Logger.error('停止流量统计失败：$e');
+      }
+
       // 再停止 Clash 核心
       if (_currentStartMode == ClashStartMode.service) {
         Logger.info('使用服务模式停止核心');
@@ -1,6 +1,7 @@
 import 'dart:async';
 import 'package:flutter/foundation.dart';
 import 'package:stelliberty/clash/network/api_client.dart';
+import 'package:stelliberty/clash/network/ipc_request_helper.dart';
 import 'package:stelliberty/clash/services/process_service.dart';
 import 'package:stelliberty/clash/config/clash_defaults.dart';
 import 'package:stelliberty/clash/data/connection_model.dart';
@@ -102,6 +103,9 @@ class ClashManager extends ChangeNotifier {
   // 覆写获取回调（从 SubscriptionProvider 注入）
   List<OverrideConfig> Function()? _getOverridesCallback;
 
+  // 当前订阅获取回调（从 SubscriptionProvider 注入），用于判断覆写生效条件
+  ({String name, String url})? Function()? _getActiveSubscriptionCallback;
+
   // 覆写失败回调（启动失败时禁用当前订阅的所有覆写）
   Future<void> Function()? _onOverridesFailedCallback;
 
@@ -179,6 +183,14 @@ class ClashManager extends ChangeNotifier {
     Logger.debug('已设置覆写获取回调到 ClashManager');
   }
 
+  // 设置当前订阅获取回调（由 SubscriptionProvider 注入）
+  void setActiveSubscriptionGetter(
+    ({String name, String url})? Function() callback,
+  ) {
+    _getActiveSubscriptionCallback = callback;
+    Logger.debug('已设置当前订阅获取回调到 ClashManager');
+  }
+
   // 设置覆写失败回调（由 SubscriptionProvider 注入）
   void setOverridesFailedCallback(Future<void> Function() callback) {
     _onOverridesFailedCallback = callback;
@@ -189,9 +201,12 @@ class ClashManager extends ChangeNotifier {
     String? configPath,
     List<OverrideConfig> overrides = const [],
   }) async {
+    final subscription = _getActiveSubscriptionCallback?.call();
     final success = await _lifecycleManager.startCore(
       configPath: configPath,
       overrides: overrides,
+      subscriptionName: subscription?.name ?? '',
+      subscriptionUrl: subscription?.url ?? '',
       onOverridesFailed: onOverridesFailed,
       mixedPort: _configManager.mixedPort, // 传递混合端口
       isIpv6Enabled: _configManager.isIpv6Enabled,
@@ -286,9 +301,13 @@ class ClashManager extends ChangeNotifier {
     String? configPath,
     List<OverrideConfig> overrides = const [],
   }) async {
+    final subscription = _getActiveSubscriptionCallback?.call();
     final success = await _configManager.reloadConfig(
       configPath: configPath,
       overrides: overrides,
+      subscriptionName: subscription?.name ?? '',
+      subscriptionUrl: subscription?.url ?? '',
+      onRestartRequired: () => restartCore(configPath: configPath),
     );
 
     // 重载成功后，更新 lifecycle_manager 的配置路径缓存
@@ -403,9 +422,13 @@ class ClashManager extends ChangeNotifier {
         .getExternalControllerAddress();
     await _configManager.setExternalController(enabled, defaultAddress);
 
+    // 外部控制器无法热重载，由重载流程判断后重启核心
     if (isCoreRunning) {
-      Logger.info('外部控制器配置已更改，重启核心以应用');
-      return await restartCore();
+      Logger.info('外部控制器配置已更改，重载配置以应用');
+      return await reloadConfig(
+        configPath: currentConfigPath,
+        overrides: getOverrides(),
+      );
     }
 
     return true;
@@ -415,8 +438,11 @@ class ClashManager extends ChangeNotifier {
     await ClashPreferences.instance.setKeepAliveEnabled(enabled);
 
     if (isCoreRunning) {
-      Logger.info('TCP 保持活动配置已更改，重启核心以应用');
-      return await restartCore();
+      Logger.info('TCP 保持活动配置已更改，重载配置以应用');
+      return await reloadConfig(
+        configPath: currentConfigPath,
+        overrides: getOverrides(),
+      );
     }
 
     return true;
@@ -552,6 +578,9 @@ class ClashManager extends ChangeNotifier {
   void dispose() {
     _lifecycleManager.dispose();
 
+    // 取消进行中的 IPC 请求，释放 Rust 端占用的连接
+    IpcRequestHelper.instance.cancelPendingRequests();
+
     Logger.info('应用关闭，检查并清理系统代理…');
     unawaited(disableSystemProxy());
 
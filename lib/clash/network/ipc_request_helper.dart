@@ -15,6 +15,17 @@ class _IpcTimeouts {
 
   // 长操作（PUT 配置更新）：30 秒
   static const Duration long = Duration(seconds: 30);
+
+  // Rust 端超时后仍未收到响应时的额外等待时间
+  static const Duration responseGrace = Duration(seconds: 2);
+}
+
+// IPC 请求被取消（应用关闭时）
+class IpcRequestCancelledException implements Exception {
+  const IpcRequestCancelledException();
+
+  @override
+  String toString() => 'IPC 请求已取消';
 }
 
 // IPC 重试配置
@@ -34,6 +45,11 @@ class _IpcRetryConfig {
       return false;
     }
 
+    // 主动取消不重试
+    if (error is IpcRequestCancelledException) {
+      return false;
+    }
+
     // IPC 未就绪不重试（等待 Clash 启动）
     if (_isIpcNotReadyError(errorMsg)) {
       return false;
@@ -90,7 +106,8 @@ class IpcRequestHelper {
       final completer = _pendingRequests.remove(response.requestId);
       if (completer != null) {
         completer.complete(response);
-      } else {
+      } else if (!response.isCancelled && !response.isTimedOut) {
+        // 已取消或超时的请求在 Dart 端已结束，忽略其响应
         Logger.warning('收到未知请求 ID 的响应：${response.requestId}');
       }
     });
@@ -124,8 +141,16 @@ class IpcRequestHelper {
     }
   }
 
-  // 发送 GET 请求
-  Future<Map<String, dynamic>> get(String path) async {
+  // 发送请求并等待响应
+  //
+  // 超时时间同时传给 Rust，由 Rust 中止超时的请求并返回 isTimedOut；
+  // Dart 端多等待 responseGrace 作为兜底，仍未收到响应时主动发送取消请求
+  Future<Map<String, dynamic>> _request(
+    String method,
+    String path,
+    Duration timeout,
+    void Function(int id, int timeoutMs) send,
+  ) async {
     return _retryRequest(() async {
       final completer = Completer<IpcResponse>();
       final id = _getNextId();
@@ -133,11 +158,22 @@ class IpcRequestHelper {
 
       try {
         // 发送请求（带 request_id）
-        IpcGetRequest(requestId: id, path: path).sendSignalToRust();
-
-        // 等待响应（8秒超时 - 快速查询）
-        final response = await completer.future.timeout(_IpcTimeouts.quick);
+        send(id, timeout.inMilliseconds);
+
+        final response = await completer.future.timeout(
+          timeout + _IpcTimeouts.responseGrace,
+          onTimeout: () {
+            IpcCancelRequest(requestId: id).sendSignalToRust();
+            throw TimeoutException('IPC $method 请求超时：$path', timeout);
+          },
+        );
 
+        if (response.isTimedOut) {
+          throw TimeoutException('IPC $method 请求超时：$path', timeout);
+        }
+        if (response.isCancelled) {
+          throw const IpcRequestCancelledException();
+        }
         if (!response.isSuccessful) {
           throw Exception(response.errorMessage ?? 'IPC 请求失败');
         }
@@ -149,7 +185,10 @@ class IpcRequestHelper {
         return json.decode(response.body) as Map<String, dynamic>;
       } on TimeoutException {
         _pendingRequests.remove(id);
-        Logger.error('IPC GET 请求超时（8秒）：$path');
+        Logger.error('IPC $method 请求超时（${timeout.inSeconds}秒）：$path');
+        rethrow;
+      } on IpcRequestCancelledException {
+        _pendingRequests.remove(id);
         rethrow;
       } catch (e) {
         _pendingRequests.remove(id);
@@ -159,191 +198,98 @@ class IpcRequestHelper {
         if (_isIpcNotReadyError(errorMsg)) {
           // IPC 尚未就绪，静默处理（不打印日志）
         } else {
-          Logger.error('IPC GET 请求失败：$path，error：$e');
+          Logger.error('IPC $method 请求失败：$path，error：$e');
         }
         rethrow;
       }
     });
   }
 
-  // 发送 POST 请求
+  // 发送 GET 请求（8秒超时 - 快速查询）
+  Future<Map<String, dynamic>> get(String path) async {
+    return _request('GET', path, _IpcTimeouts.quick, (id, timeoutMs) {
+      IpcGetRequest(
+        requestId: id,
+        path: path,
+        timeoutMs: timeoutMs,
+      ).sendSignalToRust();
+    });
+  }
+
+  // 发送 POST 请求（15秒超时 - 普通操作）
   Future<Map<String, dynamic>> post(
     String path, {
     Map<String, dynamic>? body,
   }) async {
-    return _retryRequest(() async {
-      final completer = Completer<IpcResponse>();
-      final id = _getNextId();
-      _pendingRequests[id] = completer;
-
-      try {
-        final bodyStr = body != null ? json.encode(body) : null;
-        IpcPostRequest(
-          requestId: id,
-          path: path,
-          body: bodyStr,
-        ).sendSignalToRust();
-
-        // 等待响应（15秒超时 - 普通操作）
-        final response = await completer.future.timeout(_IpcTimeouts.normal);
-
-        if (!response.isSuccessful) {
-          throw Exception(response.errorMessage ?? 'IPC 请求失败');
-        }
-
-        if (response.body.isEmpty) {
-          return {};
-        }
-        return json.decode(response.body) as Map<String, dynamic>;
-      } on TimeoutException {
-        _pendingRequests.remove(id);
-        Logger.error('IPC POST 请求超时（15秒）：$path');
-        rethrow;
-      } catch (e) {
-        _pendingRequests.remove(id);
-
-        final errorMsg = e.toString();
-        if (_isIpcNotReadyError(errorMsg)) {
-          // IPC 尚未就绪，静默处理
-        } else {
-          Logger.error('IPC POST 请求失败：$path，error：$e');
-        }
-        rethrow;
-      }
+    final bodyStr = body != null ? json.encode(body) : null;
+    return _request('POST', path, _IpcTimeouts.normal, (id, timeoutMs) {
+      IpcPostRequest(
+        requestId: id,
+        path: path,
+        body: bodyStr,
+        timeoutMs: timeoutMs,
+      ).sendSignalToRust();
     });
   }
 
-  // 发送 PUT 请求
+  // 发送 PUT 请求（30秒超时 - 长操作，用于配置更新）
   Future<Map<String, dynamic>> put(
     String path, {
     Map<String, dynamic>? body,
   }) async {
-    return _retryRequest(() async {
-      final completer = Completer<IpcResponse>();
-      final id = _getNextId();
-      _pendingRequests[id] = completer;
-
-      try {
-        final bodyStr = body != null ? json.encode(body) : null;
-        IpcPutRequest(
-          requestId: id,
-          path: path,
-          body: bodyStr,
-        ).sendSignalToRust();
-
-        // 等待响应（30秒超时 - 长操作，用于配置更新）
-        final response = await completer.future.timeout(_IpcTimeouts.long);
-
-        if (!response.isSuccessful) {
-          throw Exception(response.errorMessage ?? 'IPC 请求失败');
-        }
-
-        if (response.body.isEmpty) {
-          return {};
-        }
-        return json.decode(response.body) as Map<String, dynamic>;
-      } on TimeoutException {
-        _pendingRequests.remove(id);
-        Logger.error('IPC PUT 请求超时（30秒）：$path');
-        rethrow;
-      } catch (e) {
-        _pendingRequests.remove(id);
-
-        final errorMsg = e.toString();
-        if (_isIpcNotReadyError(errorMsg)) {
-          // IPC 尚未就绪，静默处理
-        } else {
-          Logger.error('IPC PUT 请求失败：$path，error：$e');
-        }
-        rethrow;
-      }
+    final bodyStr = body != null ? json.encode(body) : null;
+    return _request('PUT', path, _IpcTimeouts.long, (id, timeoutMs) {
+      IpcPutRequest(
+        requestId: id,
+        path: path,
+        body: bodyStr,
+        timeoutMs: timeoutMs,
+      ).sendSignalToRust();
     });
   }
 
-  // 发送 PATCH 请求
+  // 发送 PATCH 请求（15秒超时 - 普通操作）
   Future<Map<String, dynamic>> patch(
     String path, {
     Map<String, dynamic>? body,
   }) async {
-    return _retryRequest(() async {
-      final completer = Completer<IpcResponse>();
-      final id = _getNextId();
-      _pendingRequests[id] = completer;
-
-      try {
-        final bodyStr = body != null ? json.encode(body) : null;
-        IpcPatchRequest(
-          requestId: id,
-          path: path,
-          body: bodyStr,
-        ).sendSignalToRust();
-
-        // 等待响应（15秒超时 - 普通操作）
-        final response = await completer.future.timeout(_IpcTimeouts.normal);
-
-        if (!response.isSuccessful) {
-          throw Exception(response.errorMessage ?? 'IPC 请求失败');
-        }
-
-        if (response.body.isEmpty) {
-          return {};
-        }
-        return json.decode(response.body) as Map<String, dynamic>;
-      } on TimeoutException {
-        _pendingRequests.remove(id);
-        Logger.error('IPC PATCH 请求超时（15秒）：$path');
-        rethrow;
-      } catch (e) {
-        _pendingRequests.remove(id);
-
-        final errorMsg = e.toString();
-        if (_isIpcNotReadyError(errorMsg)) {
-          // IPC 尚未就绪，静默处理
-        } else {
-          Logger.error('IPC PATCH 请求失败：$path，error：$e');
-        }
-        rethrow;
-      }
+    final bodyStr = body != null ? json.encode(body) : null;
+    return _request('PATCH', path, _IpcTimeouts.normal, (id, timeoutMs) {
+      IpcPatchRequest(
+        requestId: id,
+        path: path,
+        body: bodyStr,
+        timeoutMs: timeoutMs,
+      ).sendSignalToRust();
     });
   }
 
-  // 发送 DELETE 请求
+  // 发送 DELETE 请求（15秒超时 - 普通操作）
   Future<Map<String, dynamic>> delete(String path) async {
-    return _retryRequest(() async {
-      final completer = Completer<IpcResponse>();
-      final id = _getNextId();
-      _pendingRequests[id] = completer;
-
-      try {
-        IpcDeleteRequest(requestId: id, path: path).sendSignalToRust();
-
-        // 等待响应（15秒超时 - 普通操作）
-        final response = await completer.future.timeout(_IpcTimeouts.normal);
+    return _request('DELETE', path, _IpcTimeouts.normal, (id, timeoutMs) {
+      IpcDeleteRequest(
+        requestId: id,
+        path: path,
+        timeoutMs: timeoutMs,
+      ).sendSignalToRust();
+    });
+  }
 
-        if (!response.isSuccessful) {
-          throw Exception(response.errorMessage ?? 'IPC 请求失败');
-        }
+  // 取消所有进行中的请求（应用关闭时调用）
+  //
+  // Rust 端中止请求并关闭对应的连接，等待中的调用方收到 IpcRequestCancelledException
+  void cancelPendingRequests() {
+    final pending = Map.of(_pendingRequests);
+    _pendingRequests.clear();
 
-        if (response.body.isEmpty) {
-          return {};
-        }
-        return json.decode(response.body) as Map<String, dynamic>;
-      } on TimeoutException {
-        _pendingRequests.remove(id);
-        Logger.error('IPC DELETE 请求超时（15秒）：$path');
-        rethrow;
-      } catch (e) {
-        _pendingRequests.remove(id);
+    for (final entry in pending.entries) {
+      IpcCancelRequest(requestId: entry.key).sendSignalToRust();
+      entry.value.completeError(const IpcRequestCancelledException());
+    }
 
-        final errorMsg = e.toString();
-        if (_isIpcNotReadyError(errorMsg)) {
-          // IPC 尚未就绪，静默处理
-        } else {
-          Logger.error('IPC DELETE 请求失败：$path，error：$e');
-        }
-        rethrow;
-      }
-    });
+    if (pending.isNotEmpty) {
+      Logger.info('已取消 ${pending.length} 个进行中的 IPC 请求');
+    }
   }
 
   // 检查响应状态码是否成功
@@ -1233,6 +1233,8 @@ class SubscriptionProvider extends ChangeNotifier {
                     ? OverrideFormat.yaml
                     : OverrideFormat.javascript,
                 content: appOverride.content!,
+                errorPolicy: OverrideErrorPolicy.abort,
+                matchCondition: null,
               );
             })
             .toList();
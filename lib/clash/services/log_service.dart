@@ -55,7 +55,10 @@ class ClashLogService {
     });
 
     // 发送启动日志监控信号到 Rust
-    const StartLogStream().sendSignalToRust();
+    StartLogStream(
+      level: _currentLogLevel.toApiParam(),
+      keywords: const [],
+    ).sendSignalToRust();
   }
 
   // 停止监控日志
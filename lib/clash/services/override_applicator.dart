@@ -1,5 +1,6 @@
 import 'package:stelliberty/clash/data/override_model.dart' as data;
 import 'package:stelliberty/clash/services/override_service.dart';
+import 'package:stelliberty/clash/storage/preferences.dart';
 import 'package:stelliberty/utils/logger.dart';
 import 'package:stelliberty/src/bindings/signals/signals.dart';
 
@@ -9,6 +10,13 @@ import 'package:stelliberty/src/bindings/signals/signals.dart';
 class OverrideApplicator {
   final OverrideService _service;
 
+  // 临时 Map 覆写不属于任何订阅，匹配上下文留空即可
+  static const _emptyMatchContext = OverrideMatchContext(
+    subscriptionName: '',
+    subscriptionUrl: '',
+    isTunEnabled: false,
+  );
+
   OverrideApplicator(this._service);
 
   // 获取覆写服务
@@ -16,10 +24,14 @@ class OverrideApplicator {
 
   // 应用覆写列表到订阅配置
   // 返回应用覆写后的配置内容
+  //
+  // subscriptionName / subscriptionUrl 为被覆写的订阅，用于判断覆写生效条件
   Future<String> applyOverrides(
     String baseConfigContent,
-    List<data.OverrideConfig> overrides,
-  ) async {
+    List<data.OverrideConfig> overrides, {
+    String subscriptionName = '',
+    String subscriptionUrl = '',
+  }) async {
     Logger.debug('OverrideApplicator.applyOverrides');
     Logger.debug('基础配置长度：${baseConfigContent.length} 字符');
     Logger.debug('覆写数量：${overrides.length}');
@@ -53,6 +65,8 @@ class OverrideApplicator {
             name: override.name,
             format: _convertFormat(override.format),
             content: overrideContent,
+            errorPolicy: OverrideErrorPolicy.abort,
+            matchCondition: null,
           ),
         );
       } catch (e) {
@@ -73,6 +87,15 @@ class OverrideApplicator {
       final request = ApplyOverridesRequest(
         baseConfigContent: baseConfigContent,
         overrides: overrideConfigs,
+        matchContext: OverrideMatchContext(
+          subscriptionName: subscriptionName,
+          subscriptionUrl: subscriptionUrl,
+          isTunEnabled: ClashPreferences.instance.getTunEnable(),
+        ),
+        templateVariables: const {},
+        allowedEnvVariables: const [],
+        runtimeParams: null,
+        isDiffEnabled: false,
       );
 
       // 发送请求到 Rust
@@ -130,6 +153,8 @@ class OverrideApplicator {
       name: 'Map Override',
       format: OverrideFormat.yaml,
       content: yamlContent,
+      errorPolicy: OverrideErrorPolicy.abort,
+      matchCondition: null,
     );
 
     // 调用 Rust 处理
@@ -137,6 +162,11 @@ class OverrideApplicator {
       final request = ApplyOverridesRequest(
         baseConfigContent: baseContent,
         overrides: [tempOverride],
+        matchContext: _emptyMatchContext,
+        templateVariables: const {},
+        allowedEnvVariables: const [],
+        runtimeParams: null,
+        isDiffEnabled: false,
       );
 
       // 发送请求到 Rust
@@ -113,6 +113,8 @@ class OverrideService {
         listener = localListener;
 
         // 发送下载请求到 Rust 层
+        // 固定哈希为空时不校验；缓存用于条件请求和下载失败时回退
+        final sha256Pin = config.sha256Pin?.trim();
         signals.DownloadOverrideRequest(
           requestId: requestId,
           url: config.url!,
@@ -122,6 +124,8 @@ class OverrideService {
             BigInt.from(ClashDefaults.overrideDownloadTimeout),
           ),
           mixedPort: mixedPort,
+          sha256Pin: sha256Pin == null || sha256Pin.isEmpty ? null : sha256Pin,
+          cacheDir: PathService.instance.overrideCacheDir,
         ).sendSignalToRust();
 
         // 等待响应
@@ -136,6 +140,13 @@ class OverrideService {
           throw Exception(response.errorMessage ?? '下载失败');
         }
 
+        if (response.contentSource ==
+            signals.OverrideContentSource.lastKnownGood) {
+          Logger.warning(
+            '远程覆写获取失败，使用缓存版本：${config.name} - ${response.errorMessage}',
+          );
+        }
+
         final content = response.content;
         if (content.isEmpty) {
           throw Exception('下载的内容为空');
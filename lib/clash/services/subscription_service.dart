@@ -557,7 +557,12 @@ class SubscriptionService {
             );
           }
 
-          result = await _overrideApplicator!.applyOverrides(result, overrides);
+          result = await _overrideApplicator!.applyOverrides(
+            result,
+            overrides,
+            subscriptionName: subscription.name,
+            subscriptionUrl: subscription.url,
+          );
           Logger.info('规则覆写应用成功：${overrides.length} 个覆写');
         } else {
           Logger.warning('overrideIds 非空，但未获取到任何覆写配置');
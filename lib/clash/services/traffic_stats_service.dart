@@ -0,0 +1,81 @@
+import 'package:rinf/rinf.dart';
+import 'package:stelliberty/services/path_service.dart';
+import 'package:stelliberty/utils/logger.dart';
+import 'package:stelliberty/src/bindings/signals/signals.dart';
+
+// 流量统计服务
+//
+// 核心运行期间由 Rust 端按小时、日、月累计本机核心的流量并写入应用数据目录，
+// 核心停止后仍可查询已记录的统计
+class TrafficStatsService {
+  TrafficStatsService._();
+
+  static const _responseTimeout = Duration(seconds: 5);
+
+  // 开始流量统计（核心启动后调用）
+  static Future<void> start() async {
+    final result = TrafficAccountingResult.rustSignalStream.first;
+    StartTrafficAccounting(
+      dataDir: PathService.instance.appDataPath,
+    ).sendSignalToRust();
+    await _checkAccountingResult('开始', result);
+  }
+
+  // 停止流量统计并写入账本（核心停止前调用）
+  static Future<void> stop() async {
+    final result = TrafficAccountingResult.rustSignalStream.first;
+    const StopTrafficAccounting().sendSignalToRust();
+    await _checkAccountingResult('停止', result);
+  }
+
+  static Future<void> _checkAccountingResult(
+    String action,
+    Future<RustSignalPack<TrafficAccountingResult>> response,
+  ) async {
+    final result = (await response.timeout(_responseTimeout)).message;
+    if (!result.isSuccessful) {
+      Logger.error('$action流量统计失败：${result.errorMessage}');
+    }
+  }
+
+  // 查询统计，start 与 end 为周期键（含），为空表示不限
+  //
+  // 键格式：小时 "2026-01-31 08"，日 "2026-01-31"，月 "2026-01"
+  static Future<List<TrafficStatsBucket>> query(
+    TrafficPeriod period, {
+    String start = '',
+    String end = '',
+  }) async {
+    final response = GetTrafficStatsResponse.rustSignalStream.firstWhere(
+      (signal) => signal.message.period == period,
+    );
+    GetTrafficStatsRequest(
+      dataDir: PathService.instance.appDataPath,
+      period: period,
+      start: start,
+      end: end,
+    ).sendSignalToRust();
+
+    final result = (await response.timeout(_responseTimeout)).message;
+    if (result.errorMessage != null) {
+      throw Exception(result.errorMessage);
+    }
+    return result.buckets;
+  }
+
+  // 今日与本月的统计（没有记录时为 null）
+  static Future<(TrafficStatsBucket?, TrafficStatsBucket?)>
+  queryTodayAndMonth() async {
+    final now = DateTime.now();
+    final month =
+        '${now.year.toString().padLeft(4, '0')}-${now.month.toString().padLeft(2, '0')}';
+    final day = '$month-${now.day.toString().padLeft(2, '0')}';
+
+    final days = await query(TrafficPeriod.day, start: day, end: day);
+    final months = await query(TrafficPeriod.month, start: month, end: month);
+    return (
+      days.isEmpty ? null : days.first,
+      months.isEmpty ? null : months.first,
+    );
+  }
+}
@@ -132,6 +132,9 @@ class ClashPreferences {
       'clash_external_controller_address';
   static const String _kExternalControllerSecret =
       'clash_external_controller_secret';
+  static const String _kRemoteControllerUrl = 'clash_remote_controller_url';
+  static const String _kRemoteControllerSecret =
+      'clash_remote_controller_secret';
   static const String _kKeepAliveEnabled = 'clash_keep_alive_enabled';
   static const String _kKeepAliveInterval = 'clash_keep_alive_interval';
 
@@ -319,6 +322,23 @@ class ClashPreferences {
   Future<void> setExternalControllerSecret(String secret) =>
       _setString(_kExternalControllerSecret, secret);
 
+  // ==================== 远程核心 ====================
+
+  // 获取远程核心控制器地址
+  String getRemoteControllerUrl() => _getString(_kRemoteControllerUrl, '');
+
+  // 保存远程核心控制器地址
+  Future<void> setRemoteControllerUrl(String url) =>
+      _setString(_kRemoteControllerUrl, url);
+
+  // 获取远程核心控制器密钥
+  String getRemoteControllerSecret() =>
+      _getString(_kRemoteControllerSecret, '');
+
+  // 保存远程核心控制器密钥
+  Future<void> setRemoteControllerSecret(String secret) =>
+      _setString(_kRemoteControllerSecret, secret);
+
   // ==================== TCP 保持活动 ====================
 
   // 获取 TCP 保持活动是否启用
@@ -510,6 +530,8 @@ class ClashPreferences {
       _kExternalControllerEnabled,
       _kExternalControllerAddress,
       _kExternalControllerSecret,
+      _kRemoteControllerUrl,
+      _kRemoteControllerSecret,
       _kKeepAliveEnabled,
       _kKeepAliveInterval,
       _kTunEnable,
@@ -558,6 +580,8 @@ class ClashPreferences {
       _kExternalControllerEnabled,
       _kExternalControllerAddress,
       _kExternalControllerSecret,
+      _kRemoteControllerUrl,
+      _kRemoteControllerSecret,
       _kKeepAliveEnabled,
       _kKeepAliveInterval,
       _kTunEnable,
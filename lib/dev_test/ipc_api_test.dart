@@ -229,7 +229,7 @@ class IpcApiTest {
       });
 
       // 3. 发送启动信号
-      const StartLogStream().sendSignalToRust();
+      const StartLogStream(level: 'info', keywords: []).sendSignalToRust();
       Logger.info('  已发送启动日志监控信号...');
 
       // 4. 等待连接或数据（5秒超时）
@@ -165,6 +165,8 @@ class OverrideTest {
               ? OverrideFormat.javascript
               : OverrideFormat.yaml,
           content: content,
+          errorPolicy: OverrideErrorPolicy.abort,
+          matchCondition: null,
         ),
       );
     }
@@ -175,6 +177,15 @@ class OverrideTest {
     final request = ApplyOverridesRequest(
       baseConfigContent: baseConfig,
       overrides: overrideConfigs,
+      matchContext: const OverrideMatchContext(
+        subscriptionName: '',
+        subscriptionUrl: '',
+        isTunEnabled: false,
+      ),
+      templateVariables: const {},
+      allowedEnvVariables: const [],
+      runtimeParams: null,
+      isDiffEnabled: false,
     );
 
     // 发送请求到 Rust
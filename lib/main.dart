@@ -330,6 +330,8 @@ Future<void> setupProviderDependencies(
                 ? OverrideFormat.yaml
                 : OverrideFormat.javascript,
             content: override.content!,
+            errorPolicy: OverrideErrorPolicy.abort,
+            matchCondition: null,
           ),
         );
       }
@@ -337,6 +339,13 @@ Future<void> setupProviderDependencies(
     return overrides;
   });
 
+  // 设置 ClashManager 的当前订阅获取回调
+  ClashManager.instance.setActiveSubscriptionGetter(() {
+    final currentSub = providers.subscriptionProvider.currentSubscription;
+    if (currentSub == null) return null;
+    return (name: currentSub.name, url: currentSub.url);
+  });
+
   // 设置覆写失败回调
   final currentSub = providers.subscriptionProvider.currentSubscription;
   if (currentSub != null && currentSub.overrideIds.isNotEmpty) {
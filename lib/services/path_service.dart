@@ -64,6 +64,9 @@ class PathService {
     return path.join(overridesDir, '$overrideId.$extension');
   }
 
+  // 远程覆写缓存目录（最后一次可用版本及 ETag，由 Rust 端按需创建）
+  String get overrideCacheDir => path.join(overridesDir, 'cache');
+
   // 初始化服务，应用启动时调用一次，创建必要的数据目录
   Future<void> initialize() async {
     // 获取应用包信息
@@ -5,6 +5,7 @@ import 'package:stelliberty/providers/content_provider.dart';
 import 'package:stelliberty/i18n/i18n.dart';
 import 'package:stelliberty/ui/widgets/setting/port_settings_card.dart';
 import 'package:stelliberty/ui/widgets/setting/external_controller_card.dart';
+import 'package:stelliberty/ui/widgets/setting/remote_controller_card.dart';
 import 'package:stelliberty/utils/logger.dart';
 
 class PortControlPage extends StatefulWidget {
@@ -75,6 +76,8 @@ class _PortControlPageState extends State<PortControlPage> {
                   PortSettingsCard(),
                   SizedBox(height: 16),
                   ExternalControllerCard(),
+                  SizedBox(height: 16),
+                  RemoteControllerCard(),
                 ],
               ),
             ),
@@ -1,13 +1,17 @@
+import 'dart:async';
 import 'package:flutter/material.dart';
 import 'package:provider/provider.dart';
 import 'package:stelliberty/clash/manager/manager.dart';
 import 'package:stelliberty/clash/data/traffic_data_model.dart';
+import 'package:stelliberty/clash/services/traffic_stats_service.dart';
+import 'package:stelliberty/src/bindings/signals/signals.dart';
+import 'package:stelliberty/utils/logger.dart';
 import 'package:stelliberty/ui/widgets/home/base_card.dart';
 import 'package:stelliberty/i18n/i18n.dart';
 
 // 流量统计卡片
 //
-// 显示累计上传/下载流量和实时速度波形图
+// 显示累计上传/下载流量、实时速度波形图和今日/本月用量
 class TrafficStatsCard extends StatefulWidget {
   const TrafficStatsCard({super.key});
 
@@ -19,6 +23,40 @@ class _TrafficStatsCardState extends State<TrafficStatsCard> {
   // 缓存最后一次的流量数据，避免页面切换时显示零值
   TrafficData? _trafficDataCache;
 
+  // 今日与本月用量（Rust 端每分钟写入一次账本，按相同间隔刷新）
+  TrafficStatsBucket? _todayUsage;
+  TrafficStatsBucket? _monthUsage;
+  Timer? _usageRefreshTimer;
+
+  @override
+  void initState() {
+    super.initState();
+    _loadUsage();
+    _usageRefreshTimer = Timer.periodic(
+      const Duration(minutes: 1),
+      (_) => _loadUsage(),
+    );
+  }
+
+  @override
+  void dispose() {
+    _usageRefreshTimer?.cancel();
+    super.dispose();
+  }
+
+  Future<void> _loadUsage() async {
+    try {
+      final (today, month) = await TrafficStatsService.queryTodayAndMonth();
+      if (!mounted) return;
+      setState(() {
+        _todayUsage = today;
+        _monthUsage = month;
+      });
+    } catch (e) {
+      Logger.warning('查询流量用量失败：$e');
+    }
+  }
+
   @override
   void didChangeDependencies() {
     super.didChangeDependencies();
@@ -163,6 +201,7 @@ class _TrafficStatsCardState extends State<TrafficStatsCard> {
   ) {
     // 从 ClashManager 读取全局波形图历史数据
     final manager = context.read<ClashManager>();
+    final trans = context.translate;
 
     return Column(
       children: [
@@ -255,10 +294,36 @@ class _TrafficStatsCardState extends State<TrafficStatsCard> {
             ),
           ],
         ),
+
+        const SizedBox(height: 12),
+
+        // 今日与本月用量
+        Row(
+          children: [
+            _buildUsage(context, trans.home.todayUsage, _todayUsage),
+            const SizedBox(width: 16),
+            _buildUsage(context, trans.home.monthUsage, _monthUsage),
+          ],
+        ),
       ],
     );
   }
 
+  Widget _buildUsage(
+    BuildContext context,
+    String label,
+    TrafficStatsBucket? usage,
+  ) {
+    return Text(
+      '$label：↑${_formatBytes(usage?.upload.toInt() ?? 0)} ↓${_formatBytes(usage?.download.toInt() ?? 0)}',
+      style: Theme.of(context).textTheme.bodySmall?.copyWith(
+        color: Theme.of(context).colorScheme.onSurface.withValues(alpha: 0.6),
+        fontSize: 11,
+        fontFeatures: [const FontFeature.tabularFigures()],
+      ),
+    );
+  }
+
   // 重置流量统计（无需确认对话框）
   void _resetTraffic(BuildContext context) {
     final clashManager = context.read<ClashManager>();
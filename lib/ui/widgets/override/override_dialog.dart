@@ -56,6 +56,7 @@ class OverrideDialog extends StatefulWidget {
 class _OverrideDialogState extends State<OverrideDialog> {
   late final TextEditingController _nameController;
   late final TextEditingController _urlController;
+  late final TextEditingController _sha256PinController;
   late OverrideFormat _format;
   late SubscriptionProxyMode _proxyMode;
 
@@ -81,6 +82,9 @@ class _OverrideDialogState extends State<OverrideDialog> {
     _urlController = TextEditingController(
       text: widget.editingOverride?.url ?? '',
     );
+    _sha256PinController = TextEditingController(
+      text: widget.editingOverride?.sha256Pin ?? '',
+    );
     _format = widget.editingOverride?.format ?? OverrideFormat.yaml;
     _proxyMode =
         widget.editingOverride?.proxyMode ?? SubscriptionProxyMode.direct;
@@ -94,6 +98,7 @@ class _OverrideDialogState extends State<OverrideDialog> {
     // 添加监听器以检测内容变化
     _nameController.addListener(_checkForChanges);
     _urlController.addListener(_checkForChanges);
+    _sha256PinController.addListener(_checkForChanges);
   }
 
   // 检查内容是否发生变化
@@ -111,8 +116,12 @@ class _OverrideDialogState extends State<OverrideDialog> {
         widget.editingOverride?.type == OverrideType.remote &&
         _proxyMode !=
             (widget.editingOverride?.proxyMode ?? SubscriptionProxyMode.direct);
+    final sha256PinChanged =
+        widget.editingOverride?.type == OverrideType.remote &&
+        _sha256PinController.text.trim() !=
+            (widget.editingOverride?.sha256Pin ?? '');
 
-    return nameChanged || urlChanged || proxyModeChanged;
+    return nameChanged || urlChanged || proxyModeChanged || sha256PinChanged;
   }
 
   // 延迟重建，合并同一帧内的多次变更
@@ -134,9 +143,11 @@ class _OverrideDialogState extends State<OverrideDialog> {
     // 移除监听器
     _nameController.removeListener(_checkForChanges);
     _urlController.removeListener(_checkForChanges);
+    _sha256PinController.removeListener(_checkForChanges);
     // 释放控制器
     _nameController.dispose();
     _urlController.dispose();
+    _sha256PinController.dispose();
     super.dispose();
   }
 
@@ -220,6 +231,21 @@ class _OverrideDialogState extends State<OverrideDialog> {
                   return null;
                 },
               ),
+              const SizedBox(height: _dialogItemSpacing),
+              TextInputField(
+                controller: _sha256PinController,
+                label: trans.kOverride.sha256PinLabel,
+                hint: trans.kOverride.sha256PinHint,
+                icon: Icons.verified_outlined,
+                validator: (value) {
+                  final pin = value?.trim() ?? '';
+                  if (pin.isNotEmpty &&
+                      !RegExp(r'^[0-9a-fA-F]{64}$').hasMatch(pin)) {
+                    return trans.kOverride.sha256PinError;
+                  }
+                  return null;
+                },
+              ),
             ],
 
             // 编辑模式不显示格式选择器
@@ -347,7 +373,21 @@ class _OverrideDialogState extends State<OverrideDialog> {
     Logger.info('表单验证通过，继续处理...');
 
     final override = widget.editingOverride != null
-        ? widget.editingOverride!.copyWith(name: _nameController.text.trim())
+        ? OverrideConfig(
+            id: widget.editingOverride!.id,
+            name: _nameController.text.trim(),
+            type: widget.editingOverride!.type,
+            format: widget.editingOverride!.format,
+            url: widget.editingOverride!.url,
+            localPath: widget.editingOverride!.localPath,
+            content: widget.editingOverride!.content,
+            lastUpdate: widget.editingOverride!.lastUpdate,
+            proxyMode: widget.editingOverride!.proxyMode,
+            // copyWith 无法清空字段，直接构造以允许移除固定哈希
+            sha256Pin: widget.editingOverride!.type == OverrideType.remote
+                ? _emptyToNull(_sha256PinController.text.trim())
+                : null,
+          )
         : OverrideConfig(
             id: DateTime.now().millisecondsSinceEpoch.toString(),
             name: _nameController.text.trim(),
@@ -365,6 +405,9 @@ class _OverrideDialogState extends State<OverrideDialog> {
             proxyMode: _addMethod == OverrideAddMethod.remote
                 ? _proxyMode
                 : SubscriptionProxyMode.direct,
+            sha256Pin: _addMethod == OverrideAddMethod.remote
+                ? _emptyToNull(_sha256PinController.text.trim())
+                : null,
           );
 
     Logger.info('创建的覆写对象: ${override.name}, ID: ${override.id}');
@@ -415,4 +458,6 @@ class _OverrideDialogState extends State<OverrideDialog> {
     }
     Logger.info('_handleConfirm 完成');
   }
+
+  static String? _emptyToNull(String value) => value.isEmpty ? null : value;
 }
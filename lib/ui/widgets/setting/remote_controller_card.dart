@@ -0,0 +1,230 @@
+import 'package:flutter/material.dart';
+import 'package:stelliberty/clash/storage/preferences.dart';
+import 'package:stelliberty/src/bindings/signals/signals.dart';
+import 'package:stelliberty/ui/common/modern_feature_card.dart';
+import 'package:stelliberty/ui/common/modern_text_field.dart';
+import 'package:stelliberty/utils/logger.dart';
+import 'package:stelliberty/ui/widgets/modern_toast.dart';
+import 'package:stelliberty/i18n/i18n.dart';
+
+// 远程核心卡片
+//
+// 将面板请求切换到远程 external-controller，远程核心仅支持监控和切换节点
+class RemoteControllerCard extends StatefulWidget {
+  const RemoteControllerCard({super.key});
+
+  @override
+  State<RemoteControllerCard> createState() => _RemoteControllerCardState();
+}
+
+class _RemoteControllerCardState extends State<RemoteControllerCard> {
+  late final TextEditingController _urlController;
+  late final TextEditingController _secretController;
+  bool _isApplying = false;
+  String? _urlError;
+
+  // 传输方式在应用重启后恢复为本机核心，页面重建时沿用本次运行中的状态
+  static bool _isRemote = false;
+  static String _remoteVersion = '';
+
+  @override
+  void initState() {
+    super.initState();
+    final prefs = ClashPreferences.instance;
+    _urlController = TextEditingController(
+      text: prefs.getRemoteControllerUrl(),
+    );
+    _secretController = TextEditingController(
+      text: prefs.getRemoteControllerSecret(),
+    );
+  }
+
+  @override
+  void dispose() {
+    _urlController.dispose();
+    _secretController.dispose();
+    super.dispose();
+  }
+
+  bool _validateUrl(String url) {
+    final uri = Uri.tryParse(url);
+    return uri != null &&
+        (uri.scheme == 'http' || uri.scheme == 'https') &&
+        uri.host.isNotEmpty;
+  }
+
+  // 切换传输方式，remoteUrl 为空时切回本机核心
+  Future<void> _applyTransport(String remoteUrl, String secret) async {
+    final trans = context.translate;
+    if (_isApplying) return;
+
+    setState(() => _isApplying = true);
+
+    try {
+      SetControllerTransport(
+        remoteUrl: remoteUrl,
+        secret: secret,
+      ).sendSignalToRust();
+      final result =
+          (await ControllerTransportResult.rustSignalStream.first).message;
+
+      if (!mounted) return;
+      setState(() {
+        _isRemote = result.isRemote;
+        _remoteVersion = result.version;
+      });
+
+      if (!result.isSuccessful) {
+        Logger.error('切换控制器传输方式失败: ${result.errorMessage}');
+        ModernToast.error(
+          context,
+          trans.remoteController.connectFailed.replaceAll(
+            '{error}',
+            result.errorMessage ?? '',
+          ),
+        );
+        return;
+      }
+
+      if (remoteUrl.isNotEmpty) {
+        final prefs = ClashPreferences.instance;
+        await prefs.setRemoteControllerUrl(remoteUrl);
+        await prefs.setRemoteControllerSecret(secret);
+        if (!mounted) return;
+        ModernToast.success(
+          context,
+          trans.remoteController.connected.replaceAll(
+            '{version}',
+            result.version,
+          ),
+        );
+      } else {
+        ModernToast.success(context, trans.remoteController.switchedToLocal);
+      }
+    } catch (e) {
+      Logger.error('切换控制器传输方式失败: $e');
+      if (mounted) {
+        ModernToast.error(
+          context,
+          trans.remoteController.connectFailed.replaceAll(
+            '{error}',
+            e.toString(),
+          ),
+        );
+      }
+    } finally {
+      if (mounted) {
+        setState(() => _isApplying = false);
+      }
+    }
+  }
+
+  Future<void> _connect() async {
+    final trans = context.translate;
+    final url = _urlController.text.trim();
+
+    if (!_validateUrl(url)) {
+      setState(() => _urlError = trans.remoteController.urlFormatError);
+      return;
+    }
+
+    setState(() => _urlError = null);
+    await _applyTransport(url, _secretController.text.trim());
+  }
+
+  @override
+  Widget build(BuildContext context) {
+    final trans = context.translate;
+
+    return ModernFeatureCard(
+      isSelected: false,
+      onTap: () {},
+      isHoverEnabled: false,
+      isTapEnabled: false,
+      child: Column(
+        crossAxisAlignment: CrossAxisAlignment.start,
+        children: [
+          // 标题区域
+          Row(
+            children: [
+              const Icon(Icons.cloud_sync_rounded),
+              const SizedBox(
+                width: ModernFeatureCardSpacing.featureIconToTextSpacing,
+              ),
+              Expanded(
+                child: Column(
+                  crossAxisAlignment: CrossAxisAlignment.start,
+                  children: [
+                    Text(
+                      trans.remoteController.title,
+                      style: Theme.of(context).textTheme.titleMedium,
+                    ),
+                    Text(
+                      _isRemote
+                          ? trans.remoteController.statusRemote.replaceAll(
+                              '{version}',
+                              _remoteVersion,
+                            )
+                          : trans.remoteController.description,
+                      style: Theme.of(context).textTheme.bodySmall,
+                    ),
+                  ],
+                ),
+              ),
+            ],
+          ),
+          const SizedBox(height: 16),
+          // 远程控制器地址输入框
+          ModernTextField(
+            controller: _urlController,
+            keyboardType: TextInputType.url,
+            labelText: trans.remoteController.urlLabel,
+            hintText: trans.remoteController.urlHint,
+            errorText: _urlError,
+            minLines: 1,
+          ),
+          const SizedBox(height: 12),
+          // Secret 输入框
+          ModernTextField(
+            controller: _secretController,
+            keyboardType: TextInputType.text,
+            labelText: trans.remoteController.secretLabel,
+            hintText: trans.remoteController.secretHint,
+            shouldObscureText: true,
+            minLines: 1,
+          ),
+          const SizedBox(height: 16),
+          // 操作按钮
+          Row(
+            mainAxisAlignment: MainAxisAlignment.end,
+            children: [
+              if (_isRemote) ...[
+                OutlinedButton.icon(
+                  onPressed: _isApplying ? null : () => _applyTransport('', ''),
+                  icon: const Icon(Icons.computer, size: 18),
+                  label: Text(trans.remoteController.useLocal),
+                ),
+                const SizedBox(width: 12),
+              ],
+              FilledButton.icon(
+                onPressed: _isApplying ? null : _connect,
+                icon: _isApplying
+                    ? const SizedBox(
+                        width: 18,
+                        height: 18,
+                        child: CircularProgressIndicator(strokeWidth: 2),
+                      )
+                    : const Icon(Icons.link, size: 18),
+                label: Text(
+                  _isApplying
+                      ? trans.remoteController.connecting
+                      : trans.remoteController.connect,
+                ),
+              ),
+            ],
+          ),
+        ],
+      ),
+    );
+  }
+}
@@ -13,6 +13,7 @@ pub mod delay_test;
 pub mod network;
 pub mod overrides;
 pub mod process;
+pub mod traffic_stats;
 
 #[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
 pub mod service;
@@ -35,6 +36,12 @@ pub fn init() {
     // IPC 网络通信
     network::init_rest_api_listeners();
 
+    // 本地 API 桥接
+    network::bridge::init_message_listeners();
+
+    // 控制器传输方式（本机 IPC / 远程 external-controller）
+    network::transport::init_message_listeners();
+
     // 直接进程管理模式
 
     // 启动 Clash 进程
@@ -164,4 +171,7 @@ pub fn init() {
 
     // 启动延迟测试监听器
     delay_test::init_message_listeners();
+
+    // 启动流量统计监听器
+    traffic_stats::init_message_listeners();
 }
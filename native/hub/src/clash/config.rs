@@ -2,11 +2,17 @@
 //
 // 负责统一生成 Clash 运行时配置
 
+pub mod credentials;
 pub mod generator;
 pub mod injector;
+pub mod port_probe;
+pub mod reload;
 pub mod runtime_params;
+pub mod validator;
 
+use credentials::GenerateInboundCredentialsRequest;
 use generator::GenerateRuntimeConfigRequest;
+use reload::ReloadRuntimeConfigRequest;
 use rinf::{DartSignal, RustSignal};
 use tokio::spawn;
 
@@ -22,4 +28,20 @@ pub fn init_message_listeners() {
             response.send_signal_to_dart();
         }
     });
+
+    spawn(async move {
+        let receiver = GenerateInboundCredentialsRequest::get_dart_signal_receiver();
+        while let Some(dart_signal) = receiver.recv().await {
+            let response = dart_signal.message.handle();
+            response.send_signal_to_dart();
+        }
+    });
+
+    spawn(async move {
+        let receiver = ReloadRuntimeConfigRequest::get_dart_signal_receiver();
+        while let Some(dart_signal) = receiver.recv().await {
+            let response = dart_signal.message.handle().await;
+            response.send_signal_to_dart();
+        }
+    });
 }
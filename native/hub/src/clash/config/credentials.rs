@@ -0,0 +1,45 @@
+// 入站认证凭据生成
+//
+// 目的：为允许局域网访问的场景生成随机的入站认证用户名和密码
+
+use rand::Rng;
+use rand::distr::Alphanumeric;
+use rinf::{DartSignal, RustSignal};
+use serde::{Deserialize, Serialize};
+
+const USERNAME_PREFIX: &str = "stelliberty-";
+const USERNAME_RANDOM_LENGTH: usize = 6;
+const PASSWORD_LENGTH: usize = 24;
+
+// Dart → Rust：生成入站认证凭据请求
+#[derive(Debug, Clone, Serialize, Deserialize, DartSignal)]
+pub struct GenerateInboundCredentialsRequest {}
+
+// Rust → Dart：生成入站认证凭据响应
+#[derive(Debug, Clone, Serialize, Deserialize, RustSignal)]
+pub struct GenerateInboundCredentialsResponse {
+    pub username: String,
+    pub password: String,
+}
+
+impl GenerateInboundCredentialsRequest {
+    pub fn handle(self) -> GenerateInboundCredentialsResponse {
+        log::info!("生成随机入站认证凭据");
+        GenerateInboundCredentialsResponse {
+            username: format!(
+                "{}{}",
+                USERNAME_PREFIX,
+                random_alphanumeric(USERNAME_RANDOM_LENGTH).to_ascii_lowercase()
+            ),
+            password: random_alphanumeric(PASSWORD_LENGTH),
+        }
+    }
+}
+
+pub fn random_alphanumeric(length: usize) -> String {
+    rand::rng()
+        .sample_iter(&Alphanumeric)
+        .take(length)
+        .map(char::from)
+        .collect()
+}
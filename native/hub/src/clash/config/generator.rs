@@ -5,8 +5,13 @@
 use rinf::{DartSignal, RustSignal};
 use serde::{Deserialize, Serialize};
 
+use super::port_probe::{self, PortConflict, PortConflictPolicy};
 use super::runtime_params::RuntimeConfigParams;
+use super::validator::{self, ConfigIssue, ConfigIssueKind};
 use crate::clash::overrides::processor::OverrideConfig;
+use crate::clash::overrides::scope::OverrideMatchContext;
+use crate::clash::overrides::template::TemplateVariables;
+use std::collections::HashMap;
 
 // Dart → Rust：生成运行时配置请求
 #[derive(Debug, Clone, Serialize, Deserialize, DartSignal)]
@@ -17,8 +22,21 @@ pub struct GenerateRuntimeConfigRequest {
     // 覆写列表
     pub overrides: Vec<OverrideConfig>,
 
+    // 当前订阅信息（用于匹配作用域覆写）
+    pub subscription_name: String,
+    pub subscription_url: String,
+
+    // 用户自定义模板变量（用于 YAML 覆写中的 `${name}`）
+    pub template_variables: HashMap<String, String>,
+
+    // 允许 YAML 覆写通过 `${env.NAME}` 读取的环境变量
+    pub allowed_env_variables: Vec<String>,
+
     // 运行时参数
     pub runtime_params: RuntimeConfigParams,
+
+    // 端口被占用时的处理策略
+    pub port_conflict_policy: PortConflictPolicy,
 }
 
 // Rust → Dart：生成运行时配置响应
@@ -27,6 +45,9 @@ pub struct GenerateRuntimeConfigResponse {
     pub is_successful: bool,
     pub result_config: String,
     pub error_message: String,
+    pub validation_errors: Vec<ConfigIssue>,
+    pub validation_warnings: Vec<ConfigIssue>,
+    pub port_conflicts: Vec<PortConflict>,
 }
 
 impl GenerateRuntimeConfigRequest {
@@ -35,22 +56,68 @@ impl GenerateRuntimeConfigRequest {
         log::debug!("覆写数量：{}", self.overrides.len());
         log::debug!("运行时参数：{:?}", self.runtime_params);
 
-        match generate_runtime_config_internal(
+        let match_context = OverrideMatchContext {
+            subscription_name: self.subscription_name.clone(),
+            subscription_url: self.subscription_url.clone(),
+            is_tun_enabled: self.runtime_params.is_tun_enabled,
+        };
+
+        let template_variables = TemplateVariables::new()
+            .with_user_variables(&self.template_variables)
+            .with_allowed_env_variables(&self.allowed_env_variables)
+            .with_runtime_params(&self.runtime_params);
+
+        let result = generate_runtime_config_internal(
             &self.base_config_content,
             &self.overrides,
+            &match_context,
+            template_variables,
             &self.runtime_params,
-        ) {
-            Ok(config) => GenerateRuntimeConfigResponse {
-                is_successful: true,
-                result_config: config,
-                error_message: String::new(),
-            },
+        )
+        .and_then(|(config, warnings)| {
+            resolve_runtime_ports(config, self.port_conflict_policy)
+                .map(|(config, port_conflicts)| (config, port_conflicts, warnings))
+        });
+
+        match result {
+            Ok((config, port_conflicts, generation_warnings)) => {
+                if self.port_conflict_policy == PortConflictPolicy::Report
+                    && let Some(conflict) = port_conflicts.first()
+                {
+                    let message = format!(
+                        "端口已被占用：{}（{}），占用进程：{}",
+                        conflict.key,
+                        conflict.port,
+                        conflict.owner_process.as_deref().unwrap_or("未知")
+                    );
+                    log::error!("生成运行时配置失败：{}", message);
+                    return GenerateRuntimeConfigResponse {
+                        is_successful: false,
+                        result_config: String::new(),
+                        error_message: message,
+                        validation_errors: Vec::new(),
+                        validation_warnings: Vec::new(),
+                        port_conflicts,
+                    };
+                }
+
+                let mut response = validate_runtime_config(config);
+                response.port_conflicts = port_conflicts;
+                // 覆写和注入阶段的警告（如局域网访问未设置认证）排在校验警告之前
+                response
+                    .validation_warnings
+                    .splice(0..0, generation_warnings);
+                response
+            }
             Err(e) => {
                 log::error!("生成运行时配置失败：{}", e);
                 GenerateRuntimeConfigResponse {
                     is_successful: false,
                     result_config: String::new(),
                     error_message: e,
+                    validation_errors: Vec::new(),
+                    validation_warnings: Vec::new(),
+                    port_conflicts: Vec::new(),
                 }
             }
         }
@@ -58,12 +125,17 @@ impl GenerateRuntimeConfigRequest {
 }
 
 // 内部处理函数：应用覆写 + 注入运行时参数
+//
+// 返回生成的配置和覆写、注入阶段的警告
 fn generate_runtime_config_internal(
     base_content: &str,
     overrides: &[OverrideConfig],
+    match_context: &OverrideMatchContext,
+    template_variables: TemplateVariables,
     params: &RuntimeConfigParams,
-) -> Result<String, String> {
+) -> Result<(String, Vec<ConfigIssue>), String> {
     // 1. 应用覆写
+    let mut override_warnings = Vec::new();
     let config_after_override = if overrides.is_empty() {
         base_content.to_string()
     } else {
@@ -72,17 +144,107 @@ fn generate_runtime_config_internal(
         // 创建覆写处理器
         let mut processor = crate::clash::overrides::processor::OverrideProcessor::new()
             .map_err(|e| format!("初始化覆写处理器失败：{}", e))?;
+        processor.set_template_variables(template_variables);
 
-        processor.apply_overrides(base_content, overrides.to_vec())?
+        let result = processor
+            .apply_overrides(base_content, overrides.to_vec(), match_context, false)
+            .map_err(|e| e.message)?;
+        override_warnings = result
+            .warnings
+            .into_iter()
+            .map(|message| ConfigIssue {
+                kind: ConfigIssueKind::OverrideWarning,
+                path: String::new(),
+                message,
+            })
+            .collect();
+        result.config
     };
 
     // 2. 注入运行时参数
-    let final_config = super::injector::inject_runtime_params(&config_after_override, params)?;
+    let (final_config, injection_warnings) =
+        super::injector::inject_runtime_params(&config_after_override, params)?;
+    let mut warnings = override_warnings;
+    warnings.extend(injection_warnings);
 
     // 3. 输出配置摘要（调试用）
     log_config_summary(&final_config);
 
-    Ok(final_config)
+    Ok((final_config, warnings))
+}
+
+// 探测配置中的端口占用情况
+//
+// 仅在自动回退改写了端口时重新序列化配置
+fn resolve_runtime_ports(
+    config: String,
+    policy: PortConflictPolicy,
+) -> Result<(String, Vec<PortConflict>), String> {
+    if policy == PortConflictPolicy::Ignore {
+        return Ok((config, Vec::new()));
+    }
+
+    let mut value: serde_yaml_ng::Value =
+        serde_yaml_ng::from_str(&config).map_err(|e| format!("解析生成的配置失败：{}", e))?;
+    let conflicts = port_probe::resolve_port_conflicts(&mut value, policy)?;
+
+    if !conflicts.iter().any(|c| c.resolved_port.is_some()) {
+        return Ok((config, conflicts));
+    }
+
+    let config = serde_yaml_ng::to_string(&value).map_err(|e| format!("序列化配置失败：{}", e))?;
+    Ok((config, conflicts))
+}
+
+// 校验生成的配置
+//
+// 存在错误时视为生成失败，避免写入会导致核心启动崩溃的配置
+fn validate_runtime_config(config: String) -> GenerateRuntimeConfigResponse {
+    let report = match serde_yaml_ng::from_str::<serde_yaml_ng::Value>(&config) {
+        Ok(value) => validator::validate_config(&value),
+        Err(e) => {
+            return GenerateRuntimeConfigResponse {
+                is_successful: false,
+                result_config: String::new(),
+                error_message: format!("解析生成的配置失败：{}", e),
+                validation_errors: Vec::new(),
+                validation_warnings: Vec::new(),
+                port_conflicts: Vec::new(),
+            };
+        }
+    };
+
+    for warning in &report.warnings {
+        log::warn!("配置校验警告 [{}]：{}", warning.path, warning.message);
+    }
+
+    if report.errors.is_empty() {
+        return GenerateRuntimeConfigResponse {
+            is_successful: true,
+            result_config: config,
+            error_message: String::new(),
+            validation_errors: Vec::new(),
+            validation_warnings: report.warnings,
+            port_conflicts: Vec::new(),
+        };
+    }
+
+    for error in &report.errors {
+        log::error!("配置校验错误 [{}]：{}", error.path, error.message);
+    }
+
+    GenerateRuntimeConfigResponse {
+        is_successful: false,
+        result_config: String::new(),
+        error_message: format!(
+            "配置校验失败，共 {} 个错误：{}",
+            report.errors.len(),
+            report.errors[0].message
+        ),
+        validation_errors: report.errors,
+        validation_warnings: report.warnings,
+        port_conflicts: Vec::new(),
+    }
 }
 
 // 输出配置摘要到日志
@@ -4,16 +4,41 @@
 
 use serde_yaml_ng::{Mapping, Value as YamlValue};
 
-use super::runtime_params::RuntimeConfigParams;
+use super::runtime_params::{
+    DnsPrecedence, DnsProfile, HostMapping, InboundListener, InboundUser, RuntimeConfigParams,
+};
+use super::validator::{ConfigIssue, ConfigIssueKind};
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+
+// 未配置 DNS 档案且启用 TUN 时使用的默认值
+const DEFAULT_FAKE_IP_RANGE: &str = "198.18.0.1/16";
+const DEFAULT_NAMESERVERS: &[&str] = &[
+    "8.8.8.8",
+    "https://doh.pub/dns-query",
+    "https://dns.alidns.com/dns-query",
+];
+const DEFAULT_BOOTSTRAP_NAMESERVERS: &[&str] = &["system", "223.6.6.6", "8.8.8.8"];
+
+// DNS 服务器地址允许的协议
+const DNS_SERVER_SCHEMES: &[&str] = &["udp", "tcp", "tls", "https", "quic", "h3", "dhcp", "rcode"];
+const DNS_ENHANCED_MODES: &[&str] = &["fake-ip", "redir-host", "normal"];
+
+// 支持注入的监听器类型
+const LISTENER_TYPES: &[&str] = &["mixed", "http", "socks", "redir", "tproxy"];
 
 // 注入运行时参数到 Clash 配置
 //
 // 将所有运行时参数（端口、TUN、DNS 等）注入到配置中
 // 并修复可能出现的 YAML 解析问题（如科学计数法字符串）
+//
+// 返回注入后的配置和注入过程中发现的警告
 pub fn inject_runtime_params(
     yaml_content: &str,
     params: &RuntimeConfigParams,
-) -> Result<String, String> {
+) -> Result<(String, Vec<ConfigIssue>), String> {
+    let mut warnings = Vec::new();
+
     // 1. 解析 YAML
     let mut config: YamlValue = serde_yaml_ng::from_str(yaml_content).map_err(|e| {
         log::error!("解析配置失败：{}", e);
@@ -116,9 +141,11 @@ pub fn inject_runtime_params(
         params.is_allow_lan_enabled
     );
 
-    // 移除单独的 port 和 socks-port，避免端口冲突
-    config_map.remove(YamlValue::String("port".to_string()));
-    config_map.remove(YamlValue::String("socks-port".to_string()));
+    // 注入独立监听端口及 listeners，并检查端口冲突
+    warnings.extend(inject_listeners(config_map, params)?);
+
+    // 注入入站认证，并检查局域网访问是否缺少认证
+    warnings.extend(inject_authentication(config_map, params)?);
 
     // 6. 注入出站模式
     config_map.insert(
@@ -232,10 +259,14 @@ pub fn inject_runtime_params(
 
     log::info!("TUN 配置已注入（enabled={}）", params.is_tun_enabled);
 
-    // TUN 启用时，注入基本的 DNS 配置
-    if params.is_tun_enabled {
-        inject_dns_config(config_map, params)?;
-    }
+    // 注入域名嗅探配置
+    inject_sniffer_config(config_map, params)?;
+
+    // 注入 DNS 配置
+    inject_dns_config(config_map, params)?;
+
+    // 注入自定义 hosts
+    inject_hosts(config_map, params)?;
 
     // 9. 序列化为 YAML
     let yaml_string = serde_yaml_ng::to_string(&config).map_err(|e| {
@@ -243,11 +274,733 @@ pub fn inject_runtime_params(
         format!("序列化配置失败：{}", e)
     })?;
 
-    Ok(yaml_string)
+    Ok((yaml_string, warnings))
+}
+
+// 注入独立监听端口和额外监听器
+//
+// - port / socks-port：未设置时移除订阅中的值，避免与混合端口冲突
+// - redir-port / tproxy-port / listeners：未设置时保留订阅或覆写中的值
+//
+// 返回因端口冲突而移除的继承端口的警告
+fn inject_listeners(
+    config_map: &mut Mapping,
+    params: &RuntimeConfigParams,
+) -> Result<Vec<ConfigIssue>, String> {
+    let optional_ports = [
+        ("port", params.http_proxy_port, true),
+        ("socks-port", params.socks_port, true),
+        ("redir-port", params.redir_port, false),
+        ("tproxy-port", params.tproxy_port, false),
+    ];
+
+    for (key, port, should_remove_when_unset) in optional_ports {
+        let yaml_key = YamlValue::String(key.to_string());
+        match port {
+            Some(port) => {
+                validate_port(key, port)?;
+                config_map.insert(yaml_key, YamlValue::Number(port.into()));
+                log::info!("注入 {}：{}", key, port);
+            }
+            None if should_remove_when_unset => {
+                config_map.remove(yaml_key);
+            }
+            None => {}
+        }
+    }
+
+    if cfg!(not(target_os = "linux")) && params.tproxy_port.is_some() {
+        log::warn!("tproxy-port 仅在 Linux 上可用");
+    }
+    if cfg!(windows) && params.redir_port.is_some() {
+        log::warn!("redir-port 在 Windows 上不可用");
+    }
+
+    if !params.listeners.is_empty() {
+        let mut listeners = Vec::with_capacity(params.listeners.len());
+        for listener in &params.listeners {
+            listeners.push(build_listener(listener)?);
+        }
+        config_map.insert(
+            YamlValue::String("listeners".to_string()),
+            YamlValue::Sequence(listeners),
+        );
+        log::info!("注入 {} 个额外监听器", params.listeners.len());
+    }
+
+    check_port_conflicts(config_map, params)
+}
+
+fn build_listener(listener: &InboundListener) -> Result<YamlValue, String> {
+    if listener.name.trim().is_empty() {
+        return Err("监听器名称不能为空".to_string());
+    }
+    if !LISTENER_TYPES.contains(&listener.listener_type.as_str()) {
+        return Err(format!(
+            "监听器 {} 的类型无效：{}",
+            listener.name, listener.listener_type
+        ));
+    }
+    validate_port(&format!("listeners.{}", listener.name), listener.port)?;
+
+    let mut map = Mapping::new();
+    map.insert(
+        YamlValue::String("name".to_string()),
+        YamlValue::String(listener.name.clone()),
+    );
+    map.insert(
+        YamlValue::String("type".to_string()),
+        YamlValue::String(listener.listener_type.clone()),
+    );
+    map.insert(
+        YamlValue::String("port".to_string()),
+        YamlValue::Number(listener.port.into()),
+    );
+    if let Some(listen) = listener.listen.as_ref().filter(|l| !l.is_empty()) {
+        map.insert(
+            YamlValue::String("listen".to_string()),
+            YamlValue::String(listen.clone()),
+        );
+    }
+    if let Some(is_udp_enabled) = listener.is_udp_enabled {
+        map.insert(
+            YamlValue::String("udp".to_string()),
+            YamlValue::Bool(is_udp_enabled),
+        );
+    }
+    if let Some(proxy) = listener.proxy.as_ref().filter(|p| !p.is_empty()) {
+        map.insert(
+            YamlValue::String("proxy".to_string()),
+            YamlValue::String(proxy.clone()),
+        );
+    }
+    if let Some(rule) = listener.rule.as_ref().filter(|r| !r.is_empty()) {
+        map.insert(
+            YamlValue::String("rule".to_string()),
+            YamlValue::String(rule.clone()),
+        );
+    }
+
+    Ok(YamlValue::Mapping(map))
+}
+
+fn validate_port(name: &str, port: i32) -> Result<(), String> {
+    if (1..=65535).contains(&port) {
+        Ok(())
+    } else {
+        Err(format!("{} 端口无效：{}", name, port))
+    }
+}
+
+// 检查最终配置中所有入站端口是否重复，监听器名称是否重复
+//
+// 用户设置的端口之间冲突时报错；从订阅或覆写继承的 redir-port、tproxy-port
+// 和监听器与已占用的端口冲突时移除，并返回警告
+fn check_port_conflicts(
+    config_map: &mut Mapping,
+    params: &RuntimeConfigParams,
+) -> Result<Vec<ConfigIssue>, String> {
+    let mut used: HashMap<i64, String> = HashMap::new();
+    let mut warnings = Vec::new();
+
+    // mixed-port、port、socks-port 只会来自用户设置
+    let ports = [
+        ("mixed-port", true),
+        ("port", true),
+        ("socks-port", true),
+        ("redir-port", params.redir_port.is_some()),
+        ("tproxy-port", params.tproxy_port.is_some()),
+    ];
+    let (user_ports, inherited_ports): (Vec<_>, Vec<_>) =
+        ports.into_iter().partition(|(_, is_user_set)| *is_user_set);
+
+    for (key, _) in user_ports {
+        if let Some(port) = port_of(config_map, key) {
+            claim_port(&mut used, port, key.to_string())?;
+        }
+    }
+
+    let is_user_listeners = !params.listeners.is_empty();
+    if is_user_listeners {
+        let mut names = HashSet::new();
+        for (name, port) in listener_ports(config_map) {
+            if !names.insert(name.clone()) {
+                return Err(format!("监听器名称重复：{}", name));
+            }
+            if let Some(port) = port {
+                claim_port(&mut used, port, format!("listeners.{}", name))?;
+            }
+        }
+    }
+
+    for (key, _) in inherited_ports {
+        let Some(port) = port_of(config_map, key) else {
+            continue;
+        };
+        if let Err(message) = claim_port(&mut used, port, key.to_string()) {
+            config_map.remove(YamlValue::String(key.to_string()));
+            warnings.push(inherited_port_warning(key.to_string(), &message));
+        }
+    }
+
+    if !is_user_listeners
+        && let Some(listeners) = config_map
+            .get_mut(YamlValue::String("listeners".to_string()))
+            .and_then(|v| v.as_sequence_mut())
+    {
+        let mut names = HashSet::new();
+        let mut index = 0;
+        listeners.retain(|listener| {
+            let name = listener
+                .get("name")
+                .and_then(|n| n.as_str())
+                .unwrap_or_default()
+                .to_string();
+            let path = format!("listeners[{}]", index);
+            index += 1;
+
+            if !names.insert(name.clone()) {
+                let message = format!("监听器名称重复：{}", name);
+                warnings.push(inherited_port_warning(path, &message));
+                return false;
+            }
+            let Some(port) = listener.get("port").and_then(|p| p.as_i64()) else {
+                return true;
+            };
+            match claim_port(&mut used, port, format!("listeners.{}", name)) {
+                Ok(()) => true,
+                Err(message) => {
+                    warnings.push(inherited_port_warning(path, &message));
+                    false
+                }
+            }
+        });
+    }
+
+    Ok(warnings)
+}
+
+fn port_of(config_map: &Mapping, key: &str) -> Option<i64> {
+    config_map
+        .get(YamlValue::String(key.to_string()))
+        .and_then(|v| v.as_i64())
+        .filter(|p| *p > 0)
+}
+
+fn listener_ports(config_map: &Mapping) -> Vec<(String, Option<i64>)> {
+    config_map
+        .get(YamlValue::String("listeners".to_string()))
+        .and_then(|v| v.as_sequence())
+        .map(|listeners| {
+            listeners
+                .iter()
+                .map(|listener| {
+                    let name = listener
+                        .get("name")
+                        .and_then(|n| n.as_str())
+                        .unwrap_or_default()
+                        .to_string();
+                    (name, listener.get("port").and_then(|p| p.as_i64()))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn claim_port(used: &mut HashMap<i64, String>, port: i64, owner: String) -> Result<(), String> {
+    match used.get(&port) {
+        Some(existing) => Err(format!(
+            "端口冲突：{} 与 {} 均使用端口 {}",
+            existing, owner, port
+        )),
+        None => {
+            used.insert(port, owner);
+            Ok(())
+        }
+    }
+}
+
+fn inherited_port_warning(path: String, reason: &str) -> ConfigIssue {
+    let message = format!("{}，已移除订阅中的 {}", reason, path);
+    log::warn!("{}", message);
+    ConfigIssue {
+        kind: ConfigIssueKind::InheritedPortConflict,
+        path,
+        message,
+    }
+}
+
+// 注入域名嗅探配置
+//
+// 未设置 is_sniffer_enabled 时保留订阅中的 sniffer；设置后与订阅中的 sniffer 合并，
+// 只覆盖用户设置的项（端口按协议整体替换）
+//
+// parse-pure-ip 让仅有 IP 的连接也进行嗅探，fake-ip 下域名规则才能匹配这类流量，
+// 订阅未设置时默认启用
+fn inject_sniffer_config(
+    config_map: &mut Mapping,
+    params: &RuntimeConfigParams,
+) -> Result<(), String> {
+    let Some(is_sniffer_enabled) = params.is_sniffer_enabled else {
+        return Ok(());
+    };
+
+    let sniffer_key = YamlValue::String("sniffer".to_string());
+    let mut sniffer_config = config_map
+        .get(&sniffer_key)
+        .and_then(|v| v.as_mapping())
+        .cloned()
+        .unwrap_or_default();
+
+    let sniff_key = YamlValue::String("sniff".to_string());
+    let mut sniff = sniffer_config
+        .get(&sniff_key)
+        .and_then(|v| v.as_mapping())
+        .cloned()
+        .unwrap_or_default();
+    for (protocol, ports) in [
+        ("HTTP", &params.sniffer_http_ports),
+        ("TLS", &params.sniffer_tls_ports),
+        ("QUIC", &params.sniffer_quic_ports),
+    ] {
+        if ports.is_empty() {
+            continue;
+        }
+
+        let mut port_values = Vec::with_capacity(ports.len());
+        for port in ports {
+            port_values.push(sniffer_port_value(protocol, port)?);
+        }
+
+        let mut protocol_config = Mapping::new();
+        protocol_config.insert(
+            YamlValue::String("ports".to_string()),
+            YamlValue::Sequence(port_values),
+        );
+        sniff.insert(
+            YamlValue::String(protocol.to_string()),
+            YamlValue::Mapping(protocol_config),
+        );
+    }
+
+    if is_sniffer_enabled && sniff.is_empty() {
+        return Err("已启用域名嗅探但未设置任何嗅探端口".to_string());
+    }
+
+    sniffer_config.insert(
+        YamlValue::String("enable".to_string()),
+        YamlValue::Bool(is_sniffer_enabled),
+    );
+    for key in ["force-dns-mapping", "parse-pure-ip"] {
+        let key = YamlValue::String(key.to_string());
+        if !sniffer_config.contains_key(&key) {
+            sniffer_config.insert(key, YamlValue::Bool(true));
+        }
+    }
+    if let Some(is_override_enabled) = params.is_sniffer_override_destination_enabled {
+        sniffer_config.insert(
+            YamlValue::String("override-destination".to_string()),
+            YamlValue::Bool(is_override_enabled),
+        );
+    }
+    if !sniff.is_empty() {
+        sniffer_config.insert(sniff_key, YamlValue::Mapping(sniff));
+    }
+    if !params.sniffer_force_domain.is_empty() {
+        sniffer_config.insert(
+            YamlValue::String("force-domain".to_string()),
+            string_sequence(&params.sniffer_force_domain),
+        );
+    }
+    if !params.sniffer_skip_domain.is_empty() {
+        sniffer_config.insert(
+            YamlValue::String("skip-domain".to_string()),
+            string_sequence(&params.sniffer_skip_domain),
+        );
+    }
+
+    config_map.insert(sniffer_key, YamlValue::Mapping(sniffer_config));
+
+    log::info!("域名嗅探配置已注入（enabled={}）", is_sniffer_enabled);
+
+    Ok(())
+}
+
+// 单个端口写为数字，端口范围（如 8080-8880）写为字符串
+fn sniffer_port_value(protocol: &str, port: &str) -> Result<YamlValue, String> {
+    let invalid = || format!("{} 嗅探端口无效：{}", protocol, port);
+    let parse = |value: &str| {
+        value
+            .trim()
+            .parse::<u16>()
+            .ok()
+            .filter(|p| *p > 0)
+            .ok_or_else(invalid)
+    };
+
+    match port.split_once('-') {
+        Some((start, end)) => {
+            if parse(start)? > parse(end)? {
+                return Err(invalid());
+            }
+            Ok(YamlValue::String(port.trim().to_string()))
+        }
+        None => Ok(YamlValue::Number(parse(port)?.into())),
+    }
+}
+
+// 注入自定义 hosts 及 DNS 的 hosts 开关
+//
+// 与订阅或覆写中已有的 hosts 合并，同名域名以用户设置为准
+fn inject_hosts(config_map: &mut Mapping, params: &RuntimeConfigParams) -> Result<(), String> {
+    if !params.hosts.is_empty() {
+        let hosts_key = YamlValue::String("hosts".to_string());
+        let mut hosts = config_map
+            .get(&hosts_key)
+            .and_then(|v| v.as_mapping())
+            .cloned()
+            .unwrap_or_default();
+
+        for mapping in &params.hosts {
+            validate_host_mapping(mapping)?;
+            let value = match mapping.addresses.as_slice() {
+                [address] => YamlValue::String(address.clone()),
+                addresses => string_sequence(addresses),
+            };
+            hosts.insert(YamlValue::String(mapping.domain.clone()), value);
+        }
+
+        config_map.insert(hosts_key, YamlValue::Mapping(hosts));
+        log::info!("注入 {} 条自定义 hosts", params.hosts.len());
+    }
+
+    let toggles = [
+        ("use-hosts", params.is_dns_use_hosts_enabled),
+        ("use-system-hosts", params.is_dns_use_system_hosts_enabled),
+    ];
+    if toggles.iter().all(|(_, value)| value.is_none()) {
+        return Ok(());
+    }
+
+    let dns_key = YamlValue::String("dns".to_string());
+    let mut dns_config = config_map
+        .get(&dns_key)
+        .and_then(|v| v.as_mapping())
+        .cloned()
+        .unwrap_or_default();
+    for (key, value) in toggles {
+        if let Some(is_enabled) = value {
+            dns_config.insert(
+                YamlValue::String(key.to_string()),
+                YamlValue::Bool(is_enabled),
+            );
+        }
+    }
+    config_map.insert(dns_key, YamlValue::Mapping(dns_config));
+
+    Ok(())
+}
+
+fn validate_host_mapping(mapping: &HostMapping) -> Result<(), String> {
+    let domain = mapping.domain.trim();
+    if domain.is_empty() || domain.contains(char::is_whitespace) {
+        return Err(format!("hosts 域名无效：{}", mapping.domain));
+    }
+
+    match mapping.addresses.as_slice() {
+        [] => Err(format!("hosts 域名 {} 未设置地址", domain)),
+        // 单个地址可以是 IP 或别名域名
+        [address] if !address.trim().is_empty() => Ok(()),
+        addresses => {
+            for address in addresses {
+                if address.parse::<IpAddr>().is_err() {
+                    return Err(format!("hosts 域名 {} 的地址无效：{}", domain, address));
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+// 注入入站认证
+//
+// 用户未设置时保留订阅或覆写中的值；
+// 允许局域网访问但最终配置没有任何认证用户时，按 is_lan_auth_required 拒绝或警告
+//
+// 允许局域网访问但未设置认证时返回警告（要求认证时直接报错）
+fn inject_authentication(
+    config_map: &mut Mapping,
+    params: &RuntimeConfigParams,
+) -> Result<Option<ConfigIssue>, String> {
+    if !params.authentication.is_empty() {
+        let mut users = Vec::with_capacity(params.authentication.len());
+        for user in &params.authentication {
+            validate_inbound_user(user)?;
+            users.push(format!("{}:{}", user.username, user.password));
+        }
+        config_map.insert(
+            YamlValue::String("authentication".to_string()),
+            string_sequence(&users),
+        );
+        log::info!("注入 {} 个入站认证用户", users.len());
+    }
+
+    if !params.skip_auth_prefixes.is_empty() {
+        for prefix in &params.skip_auth_prefixes {
+            validate_ip_prefix(prefix)?;
+        }
+        config_map.insert(
+            YamlValue::String("skip-auth-prefixes".to_string()),
+            string_sequence(&params.skip_auth_prefixes),
+        );
+        log::info!("注入免认证网段：{:?}", params.skip_auth_prefixes);
+    }
+
+    if !params.is_allow_lan_enabled {
+        return Ok(None);
+    }
+
+    let has_authentication = config_map
+        .get(YamlValue::String("authentication".to_string()))
+        .and_then(|v| v.as_sequence())
+        .is_some_and(|users| !users.is_empty());
+
+    if has_authentication {
+        return Ok(None);
+    }
+    if params.is_lan_auth_required {
+        return Err("已允许局域网访问但未设置入站认证，拒绝生成配置".to_string());
+    }
+
+    let message = "已允许局域网访问但未设置入站认证，局域网内任何设备均可使用代理";
+    log::warn!("{}", message);
+    Ok(Some(ConfigIssue {
+        kind: ConfigIssueKind::LanWithoutAuthentication,
+        path: "authentication".to_string(),
+        message: message.to_string(),
+    }))
+}
+
+fn validate_inbound_user(user: &InboundUser) -> Result<(), String> {
+    if user.username.is_empty() || user.username.contains(':') {
+        return Err(format!("认证用户名无效：{}", user.username));
+    }
+    if user.password.is_empty() {
+        return Err(format!("认证用户 {} 的密码为空", user.username));
+    }
+    Ok(())
+}
+
+// 校验 CIDR 网段，如 192.168.0.0/16 或 fe80::/10
+fn validate_ip_prefix(prefix: &str) -> Result<(), String> {
+    let invalid = || format!("免认证网段无效：{}", prefix);
+    let (address, length) = prefix.split_once('/').ok_or_else(invalid)?;
+    let address: IpAddr = address.parse().map_err(|_| invalid())?;
+    let length: u8 = length.parse().map_err(|_| invalid())?;
+    let max_length = if address.is_ipv4() { 32 } else { 128 };
+    if length > max_length {
+        return Err(invalid());
+    }
+    Ok(())
 }
 
-// 注入 DNS 配置（TUN 模式需要）
+// 注入 DNS 配置
+//
+// 用户设置了 DNS 档案时按其 precedence 与订阅 DNS 合并；
+// TUN 启用时补全合并后仍为空的基本 DNS 配置
 fn inject_dns_config(config_map: &mut Mapping, params: &RuntimeConfigParams) -> Result<(), String> {
+    match &params.dns_profile {
+        Some(profile) => inject_dns_profile(
+            config_map,
+            profile,
+            params.is_ipv6_enabled,
+            params.is_tun_enabled,
+        ),
+        None if params.is_tun_enabled => inject_default_dns_config(config_map, params),
+        None => Ok(()),
+    }
+}
+
+// 注入用户 DNS 档案
+fn inject_dns_profile(
+    config_map: &mut Mapping,
+    profile: &DnsProfile,
+    is_ipv6_enabled: bool,
+    is_tun_enabled: bool,
+) -> Result<(), String> {
+    validate_dns_profile(profile)?;
+
+    let mut dns_config = config_map
+        .get(YamlValue::String("dns".to_string()))
+        .and_then(|v| v.as_mapping())
+        .cloned()
+        .unwrap_or_default();
+
+    let is_override = profile.precedence == DnsPrecedence::Override;
+    let mut set = |key: &str, value: YamlValue| {
+        let key = YamlValue::String(key.to_string());
+        if is_override || dns_config.get(&key).is_none_or(is_unset) {
+            dns_config.insert(key, value);
+        }
+    };
+
+    set("enable", YamlValue::Bool(true));
+    set("ipv6", YamlValue::Bool(is_ipv6_enabled));
+
+    if let Some(mode) = &profile.enhanced_mode {
+        set("enhanced-mode", YamlValue::String(mode.clone()));
+    }
+    if let Some(range) = &profile.fake_ip_range {
+        set("fake-ip-range", YamlValue::String(range.clone()));
+    }
+
+    let lists = [
+        ("fake-ip-filter", &profile.fake_ip_filter),
+        ("default-nameserver", &profile.default_nameserver),
+        ("nameserver", &profile.nameserver),
+        ("fallback", &profile.fallback),
+        ("proxy-server-nameserver", &profile.proxy_server_nameserver),
+    ];
+    for (key, values) in lists {
+        if !values.is_empty() {
+            set(key, string_sequence(values));
+        }
+    }
+
+    if let Some(filter) = &profile.fallback_filter {
+        let mut filter_map = Mapping::new();
+        filter_map.insert(
+            YamlValue::String("geoip".to_string()),
+            YamlValue::Bool(filter.is_geoip_enabled),
+        );
+        if !filter.geoip_code.is_empty() {
+            filter_map.insert(
+                YamlValue::String("geoip-code".to_string()),
+                YamlValue::String(filter.geoip_code.clone()),
+            );
+        }
+        if !filter.ipcidr.is_empty() {
+            filter_map.insert(
+                YamlValue::String("ipcidr".to_string()),
+                string_sequence(&filter.ipcidr),
+            );
+        }
+        if !filter.domain.is_empty() {
+            filter_map.insert(
+                YamlValue::String("domain".to_string()),
+                string_sequence(&filter.domain),
+            );
+        }
+        set("fallback-filter", YamlValue::Mapping(filter_map));
+    }
+
+    if !profile.nameserver_policy.is_empty() {
+        let mut policy_map = Mapping::new();
+        for policy in &profile.nameserver_policy {
+            policy_map.insert(
+                YamlValue::String(policy.pattern.clone()),
+                string_sequence(&policy.servers),
+            );
+        }
+        set("nameserver-policy", YamlValue::Mapping(policy_map));
+    }
+
+    if let Some(is_respect_rules_enabled) = profile.is_respect_rules_enabled {
+        set("respect-rules", YamlValue::Bool(is_respect_rules_enabled));
+    }
+
+    // respect-rules 依赖 proxy-server-nameserver 解析代理服务器地址
+    let is_respect_rules = dns_config
+        .get(YamlValue::String("respect-rules".to_string()))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let has_proxy_server_nameserver = dns_config
+        .get(YamlValue::String("proxy-server-nameserver".to_string()))
+        .is_some_and(|v| !is_unset(v));
+    if is_respect_rules && !has_proxy_server_nameserver {
+        return Err("启用 respect-rules 时必须设置 proxy-server-nameserver".to_string());
+    }
+
+    // TUN 依赖 DNS 劫持，档案与订阅均未设置的基本项使用默认值
+    if is_tun_enabled {
+        fill_default_dns_fields(&mut dns_config);
+    }
+
+    log::info!(
+        "DNS 档案已注入（precedence={:?}，enhanced-mode={:?}）",
+        profile.precedence,
+        dns_config
+            .get(YamlValue::String("enhanced-mode".to_string()))
+            .and_then(|v| v.as_str())
+    );
+
+    config_map.insert(
+        YamlValue::String("dns".to_string()),
+        YamlValue::Mapping(dns_config),
+    );
+
+    Ok(())
+}
+
+fn validate_dns_profile(profile: &DnsProfile) -> Result<(), String> {
+    if let Some(mode) = &profile.enhanced_mode
+        && !DNS_ENHANCED_MODES.contains(&mode.as_str())
+    {
+        return Err(format!("无效的 DNS enhanced-mode：{}", mode));
+    }
+
+    let servers = profile
+        .default_nameserver
+        .iter()
+        .chain(&profile.nameserver)
+        .chain(&profile.fallback)
+        .chain(&profile.proxy_server_nameserver)
+        .chain(profile.nameserver_policy.iter().flat_map(|p| &p.servers));
+
+    for server in servers {
+        if let Some((scheme, _)) = server.split_once("://")
+            && !DNS_SERVER_SCHEMES.contains(&scheme)
+        {
+            return Err(format!("不支持的 DNS 服务器协议：{}", server));
+        }
+    }
+
+    if profile
+        .nameserver_policy
+        .iter()
+        .any(|p| p.pattern.is_empty() || p.servers.is_empty())
+    {
+        return Err("nameserver-policy 的匹配项和服务器不能为空".to_string());
+    }
+
+    Ok(())
+}
+
+// 判断订阅中的字段是否视为未设置
+fn is_unset(value: &YamlValue) -> bool {
+    match value {
+        YamlValue::Null => true,
+        YamlValue::String(s) => s.is_empty(),
+        YamlValue::Sequence(seq) => seq.is_empty(),
+        YamlValue::Mapping(map) => map.is_empty(),
+        _ => false,
+    }
+}
+
+fn string_sequence<S: AsRef<str>>(values: &[S]) -> YamlValue {
+    YamlValue::Sequence(
+        values
+            .iter()
+            .map(|v| YamlValue::String(v.as_ref().to_string()))
+            .collect(),
+    )
+}
+
+// 注入默认 DNS 配置（TUN 模式需要）
+fn inject_default_dns_config(
+    config_map: &mut Mapping,
+    params: &RuntimeConfigParams,
+) -> Result<(), String> {
     // 获取现有 DNS 配置（如果有）
     let existing_dns = config_map
         .get(YamlValue::String("dns".to_string()))
@@ -279,60 +1032,303 @@ fn inject_dns_config(config_map: &mut Mapping, params: &RuntimeConfigParams) ->
         YamlValue::Bool(params.is_ipv6_enabled),
     );
 
-    if !dns_config.contains_key(YamlValue::String("enhanced-mode".to_string())) {
-        dns_config.insert(
-            YamlValue::String("enhanced-mode".to_string()),
-            YamlValue::String("fake-ip".to_string()),
-        );
+    fill_default_dns_fields(&mut dns_config);
+
+    config_map.insert(
+        YamlValue::String("dns".to_string()),
+        YamlValue::Mapping(dns_config),
+    );
+
+    Ok(())
+}
+
+// 补全为空的 enhanced-mode、fake-ip-range、nameserver 和 default-nameserver
+//
+// fake-ip-range 仅在 fake-ip 模式下补全
+fn fill_default_dns_fields(dns_config: &mut Mapping) {
+    let mut fill = |key: &str, value: YamlValue| {
+        let key = YamlValue::String(key.to_string());
+        if dns_config.get(&key).is_none_or(is_unset) {
+            dns_config.insert(key, value);
+        }
+    };
+
+    fill("enhanced-mode", YamlValue::String("fake-ip".to_string()));
+    fill("nameserver", string_sequence(DEFAULT_NAMESERVERS));
+    fill(
+        "default-nameserver",
+        string_sequence(DEFAULT_BOOTSTRAP_NAMESERVERS),
+    );
+
+    let is_fake_ip = dns_config
+        .get(YamlValue::String("enhanced-mode".to_string()))
+        .and_then(|v| v.as_str())
+        == Some("fake-ip");
+    if is_fake_ip {
+        let key = YamlValue::String("fake-ip-range".to_string());
+        if dns_config.get(&key).is_none_or(is_unset) {
+            dns_config.insert(key, YamlValue::String(DEFAULT_FAKE_IP_RANGE.to_string()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_params() -> RuntimeConfigParams {
+        RuntimeConfigParams {
+            http_port: 7890,
+            is_ipv6_enabled: false,
+            is_allow_lan_enabled: false,
+            is_tcp_concurrent_enabled: false,
+            is_unified_delay_enabled: true,
+            outbound_mode: "rule".to_string(),
+            is_tun_enabled: false,
+            tun_stack: "mixed".to_string(),
+            tun_device: "Mihomo".to_string(),
+            is_tun_auto_route_enabled: true,
+            is_tun_auto_redirect_enabled: false,
+            is_tun_auto_detect_interface_enabled: true,
+            tun_dns_hijack: vec!["any:53".to_string()],
+            is_tun_strict_route_enabled: false,
+            tun_route_exclude_address: vec![],
+            is_tun_icmp_forwarding_disabled: false,
+            tun_mtu: 9000,
+            geodata_loader: "memconservative".to_string(),
+            find_process_mode: "off".to_string(),
+            clash_core_log_level: "info".to_string(),
+            external_controller: None,
+            external_controller_secret: None,
+            is_keep_alive_enabled: false,
+            keep_alive_interval: None,
+            http_proxy_port: None,
+            socks_port: None,
+            redir_port: None,
+            tproxy_port: None,
+            listeners: vec![],
+            dns_profile: None,
+            authentication: vec![],
+            skip_auth_prefixes: vec![],
+            is_lan_auth_required: false,
+            is_sniffer_enabled: None,
+            sniffer_http_ports: vec![],
+            sniffer_tls_ports: vec![],
+            sniffer_quic_ports: vec![],
+            sniffer_force_domain: vec![],
+            sniffer_skip_domain: vec![],
+            is_sniffer_override_destination_enabled: None,
+            hosts: vec![],
+            is_dns_use_hosts_enabled: None,
+            is_dns_use_system_hosts_enabled: None,
+        }
     }
 
-    if !dns_config.contains_key(YamlValue::String("fake-ip-range".to_string())) {
-        dns_config.insert(
-            YamlValue::String("fake-ip-range".to_string()),
-            YamlValue::String("198.18.0.1/16".to_string()),
+    fn inject(yaml: &str, params: &RuntimeConfigParams) -> Result<YamlValue, String> {
+        let (output, _) = inject_runtime_params(yaml, params)?;
+        serde_yaml_ng::from_str(&output).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn test_listener_ports_and_conflicts() {
+        let mut params = test_params();
+        params.socks_port = Some(7891);
+        params.listeners = vec![InboundListener {
+            name: "gateway".to_string(),
+            listener_type: "tproxy".to_string(),
+            port: 7895,
+            listen: None,
+            is_udp_enabled: Some(true),
+            proxy: None,
+            rule: None,
+        }];
+
+        let config = inject("port: 8080\n", &params).unwrap_or_default();
+        assert!(config.get("port").is_none());
+        assert_eq!(config["socks-port"].as_i64(), Some(7891));
+        assert_eq!(config["listeners"][0]["type"].as_str(), Some("tproxy"));
+
+        params.redir_port = Some(7890);
+        let result = inject("mode: rule\n", &params);
+        assert!(result.is_err_and(|e| e.contains("端口冲突")));
+
+        // 订阅中与用户端口冲突的 redir-port、tproxy-port 和监听器被移除并给出警告
+        let mut params = test_params();
+        params.socks_port = Some(7891);
+        let Ok((output, warnings)) = inject_runtime_params(
+            "redir-port: 7890\ntproxy-port: 7892\nlisteners:\n  - name: a\n    type: socks\n    port: 7891\n  - name: b\n    type: http\n    port: 7893\n",
+            &params,
+        ) else {
+            panic!("继承的端口冲突不应导致生成失败");
+        };
+        let config: YamlValue = serde_yaml_ng::from_str(&output).unwrap_or_default();
+        assert!(config.get("redir-port").is_none());
+        assert_eq!(config["tproxy-port"].as_i64(), Some(7892));
+        assert_eq!(config["listeners"].as_sequence().map(Vec::len), Some(1));
+        assert_eq!(config["listeners"][0]["name"].as_str(), Some("b"));
+        let paths: Vec<_> = warnings
+            .iter()
+            .filter(|w| w.kind == ConfigIssueKind::InheritedPortConflict)
+            .map(|w| w.path.as_str())
+            .collect();
+        assert_eq!(paths, vec!["redir-port", "listeners[0]"]);
+    }
+
+    #[test]
+    fn test_dns_profile_precedence() {
+        let base = "dns:\n  enhanced-mode: redir-host\n  nameserver: [1.1.1.1]\n";
+        let mut params = test_params();
+        params.dns_profile = Some(DnsProfile {
+            precedence: DnsPrecedence::Fallback,
+            enhanced_mode: Some("fake-ip".to_string()),
+            fake_ip_range: None,
+            fake_ip_filter: vec!["+.lan".to_string()],
+            default_nameserver: vec![],
+            nameserver: vec!["tls://8.8.8.8".to_string()],
+            fallback: vec![],
+            fallback_filter: None,
+            nameserver_policy: vec![],
+            proxy_server_nameserver: vec![],
+            is_respect_rules_enabled: None,
+        });
+
+        let config = inject(base, &params).unwrap_or_default();
+        assert_eq!(config["dns"]["enhanced-mode"].as_str(), Some("redir-host"));
+        assert_eq!(config["dns"]["nameserver"][0].as_str(), Some("1.1.1.1"));
+        assert_eq!(config["dns"]["fake-ip-filter"][0].as_str(), Some("+.lan"));
+
+        if let Some(profile) = params.dns_profile.as_mut() {
+            profile.precedence = DnsPrecedence::Override;
+        }
+        let config = inject(base, &params).unwrap_or_default();
+        assert_eq!(config["dns"]["enhanced-mode"].as_str(), Some("fake-ip"));
+        assert_eq!(
+            config["dns"]["nameserver"][0].as_str(),
+            Some("tls://8.8.8.8")
+        );
+
+        // TUN 启用时补全档案与订阅均未设置的基本项
+        params.is_tun_enabled = true;
+        let config = inject("dns:\n  nameserver: []\n", &params).unwrap_or_default();
+        assert_eq!(
+            config["dns"]["nameserver"][0].as_str(),
+            Some("tls://8.8.8.8")
+        );
+        assert_eq!(
+            config["dns"]["fake-ip-range"].as_str(),
+            Some(DEFAULT_FAKE_IP_RANGE)
+        );
+        assert_eq!(
+            config["dns"]["default-nameserver"][0].as_str(),
+            Some(DEFAULT_BOOTSTRAP_NAMESERVERS[0])
         );
+        if let Some(profile) = params.dns_profile.as_mut() {
+            profile.nameserver = vec![];
+        }
+        let config = inject("dns:\n  nameserver: []\n", &params).unwrap_or_default();
+        assert_eq!(
+            config["dns"]["nameserver"][0].as_str(),
+            Some(DEFAULT_NAMESERVERS[0])
+        );
+
+        if let Some(profile) = params.dns_profile.as_mut() {
+            profile.nameserver = vec!["ftp://example.com".to_string()];
+        }
+        assert!(inject(base, &params).is_err());
     }
 
-    // 如果用户配置没有 nameserver，添加默认值
-    if !dns_config.contains_key(YamlValue::String("nameserver".to_string()))
-        || dns_config
-            .get(YamlValue::String("nameserver".to_string()))
-            .and_then(|v| v.as_sequence())
-            .is_none_or(|s| s.is_empty())
-    {
-        let nameservers = vec![
-            YamlValue::String("8.8.8.8".to_string()),
-            YamlValue::String("https://doh.pub/dns-query".to_string()),
-            YamlValue::String("https://dns.alidns.com/dns-query".to_string()),
-        ];
-        dns_config.insert(
-            YamlValue::String("nameserver".to_string()),
-            YamlValue::Sequence(nameservers),
+    #[test]
+    fn test_lan_authentication() {
+        let mut params = test_params();
+        params.is_allow_lan_enabled = true;
+        let Ok((_, warnings)) = inject_runtime_params("mode: rule\n", &params) else {
+            panic!("未要求认证时应生成配置");
+        };
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].kind, ConfigIssueKind::LanWithoutAuthentication);
+
+        params.is_lan_auth_required = true;
+        assert!(inject("mode: rule\n", &params).is_err());
+
+        params.authentication = vec![InboundUser {
+            username: "user".to_string(),
+            password: "secret".to_string(),
+        }];
+        params.skip_auth_prefixes = vec!["127.0.0.1/32".to_string()];
+        let config = inject("mode: rule\n", &params).unwrap_or_default();
+        assert_eq!(config["authentication"][0].as_str(), Some("user:secret"));
+        assert_eq!(
+            config["skip-auth-prefixes"][0].as_str(),
+            Some("127.0.0.1/32")
         );
+
+        params.skip_auth_prefixes = vec!["10.0.0.0/33".to_string()];
+        assert!(inject("mode: rule\n", &params).is_err());
     }
 
-    // 如果用户配置没有 default-nameserver，添加默认值
-    if !dns_config.contains_key(YamlValue::String("default-nameserver".to_string()))
-        || dns_config
-            .get(YamlValue::String("default-nameserver".to_string()))
-            .and_then(|v| v.as_sequence())
-            .is_none_or(|s| s.is_empty())
-    {
-        let default_nameservers = vec![
-            YamlValue::String("system".to_string()),
-            YamlValue::String("223.6.6.6".to_string()),
-            YamlValue::String("8.8.8.8".to_string()),
-        ];
-        dns_config.insert(
-            YamlValue::String("default-nameserver".to_string()),
-            YamlValue::Sequence(default_nameservers),
+    #[test]
+    fn test_sniffer_config() {
+        // 未设置时保留订阅中的 sniffer
+        let base = "sniffer:\n  enable: true\n  sniff:\n    TLS:\n      ports: [443, 8443]\n";
+        let mut params = test_params();
+        let config = inject(base, &params).unwrap_or_default();
+        assert_eq!(config["sniffer"]["enable"].as_bool(), Some(true));
+        assert!(config["sniffer"].get("parse-pure-ip").is_none());
+
+        // 只切换开关时保留订阅中的端口
+        params.is_sniffer_enabled = Some(false);
+        let config = inject(base, &params).unwrap_or_default();
+        assert_eq!(config["sniffer"]["enable"].as_bool(), Some(false));
+        assert_eq!(
+            config["sniffer"]["sniff"]["TLS"]["ports"][1].as_i64(),
+            Some(8443)
+        );
+
+        params.is_sniffer_enabled = Some(true);
+        assert!(inject("mode: rule\n", &params).is_err());
+
+        params.sniffer_http_ports = vec!["80".to_string(), "8080-8880".to_string()];
+        params.sniffer_tls_ports = vec!["443".to_string()];
+        params.sniffer_skip_domain = vec!["Mijia Cloud".to_string()];
+        let config = inject("mode: rule\n", &params).unwrap_or_default();
+        let sniffer = &config["sniffer"];
+        assert_eq!(sniffer["enable"].as_bool(), Some(true));
+        assert_eq!(sniffer["sniff"]["HTTP"]["ports"][0].as_i64(), Some(80));
+        assert_eq!(
+            sniffer["sniff"]["HTTP"]["ports"][1].as_str(),
+            Some("8080-8880")
         );
+        assert!(sniffer["sniff"].get("QUIC").is_none());
+        assert_eq!(sniffer["skip-domain"][0].as_str(), Some("Mijia Cloud"));
+
+        params.sniffer_quic_ports = vec!["443-80".to_string()];
+        assert!(inject("mode: rule\n", &params).is_err());
     }
 
-    config_map.insert(
-        YamlValue::String("dns".to_string()),
-        YamlValue::Mapping(dns_config),
-    );
+    #[test]
+    fn test_hosts_merge() {
+        let base = "hosts:\n  router.lan: 192.168.1.1\n  git.corp: 10.0.0.1\n";
+        let mut params = test_params();
+        params.hosts = vec![
+            HostMapping {
+                domain: "git.corp".to_string(),
+                addresses: vec!["10.0.0.2".to_string()],
+            },
+            HostMapping {
+                domain: "*.svc.corp".to_string(),
+                addresses: vec!["10.0.1.1".to_string(), "10.0.1.2".to_string()],
+            },
+        ];
+        params.is_dns_use_hosts_enabled = Some(true);
 
-    Ok(())
+        let config = inject(base, &params).unwrap_or_default();
+        assert_eq!(config["hosts"]["router.lan"].as_str(), Some("192.168.1.1"));
+        assert_eq!(config["hosts"]["git.corp"].as_str(), Some("10.0.0.2"));
+        assert_eq!(config["hosts"]["*.svc.corp"][1].as_str(), Some("10.0.1.2"));
+        assert_eq!(config["dns"]["use-hosts"].as_bool(), Some(true));
+        assert!(config["dns"].get("use-system-hosts").is_none());
+
+        params.hosts[1].addresses.push("not-an-ip".to_string());
+        assert!(inject(base, &params).is_err());
+    }
 }
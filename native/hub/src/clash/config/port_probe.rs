@@ -0,0 +1,465 @@
+// 端口占用探测
+//
+// 目的：在写入运行时配置前检查所有入站端口和外部控制器端口是否已被占用，
+// 报告占用端口的进程，并可选择自动改用下一个空闲端口，
+// 避免核心因绑定失败而启动失败却只给出笼统的错误
+
+use once_cell::sync::Lazy;
+use rinf::SignalPiece;
+use serde::{Deserialize, Serialize};
+use serde_yaml_ng::Value as YamlValue;
+use std::collections::HashSet;
+use std::net::TcpListener;
+use std::sync::{Mutex, PoisonError};
+
+// 核心进程名前缀，被自身核心占用的端口不视为冲突（例如重启前尚未退出）
+const CORE_PROCESS_PREFIX: &str = "clash-core";
+
+// 自动回退时最多尝试的端口数量
+const MAX_FALLBACK_ATTEMPTS: u16 = 100;
+
+// 当前运行的核心所用配置中的端口
+//
+// 核心由特权服务（root）运行时无法读取其监听端口的所属进程，需按运行配置识别，
+// 否则自身核心会被视为冲突，Fallback 策略下每次重新生成都会改用新端口
+static RUNNING_CORE_PORTS: Lazy<Mutex<HashSet<u16>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+// 端口冲突处理策略
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, SignalPiece, PartialEq, Eq)]
+pub enum PortConflictPolicy {
+    Ignore = 0,   // 不探测
+    Report = 1,   // 探测到冲突时生成失败
+    Fallback = 2, // 自动改用下一个空闲端口
+}
+
+// 端口冲突信息
+#[derive(Debug, Clone, Serialize, Deserialize, SignalPiece)]
+pub struct PortConflict {
+    pub key: String, // mixed-port、socks-port、listeners.名称、external-controller 等
+    pub port: i32,
+    pub owner_process: Option<String>, // 占用端口的进程名（无法识别时为 None）
+    pub resolved_port: Option<i32>,    // 自动回退后使用的端口
+}
+
+// 端口在配置中的位置
+enum PortTarget {
+    TopLevel(&'static str),
+    Listener(usize),
+    ExternalController,
+}
+
+// 待探测的端口
+struct PortBinding {
+    key: String,
+    target: PortTarget,
+    host: String,
+    port: u16,
+}
+
+// 记录核心启动时使用的配置中的端口（核心启动成功后调用）
+pub fn record_running_core_config(config_path: &str) {
+    let ports = std::fs::read_to_string(config_path)
+        .ok()
+        .and_then(|content| serde_yaml_ng::from_str::<YamlValue>(&content).ok())
+        .map(|config| collect_bindings(&config).iter().map(|b| b.port).collect())
+        .unwrap_or_default();
+    log::debug!("核心运行配置端口：{:?}", ports);
+    *RUNNING_CORE_PORTS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner) = ports;
+}
+
+// 核心停止后清除记录的端口
+pub fn clear_running_core_ports() {
+    RUNNING_CORE_PORTS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clear();
+}
+
+// 探测配置中的所有端口并按策略处理冲突
+//
+// Fallback 策略下会直接修改配置；返回检测到的全部冲突
+pub fn resolve_port_conflicts(
+    config: &mut YamlValue,
+    policy: PortConflictPolicy,
+) -> Result<Vec<PortConflict>, String> {
+    let core_ports = RUNNING_CORE_PORTS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone();
+    resolve_port_conflicts_excluding(config, policy, &core_ports)
+}
+
+// core_ports 中的端口由当前运行的核心占用，即使无法识别占用进程也不视为冲突
+fn resolve_port_conflicts_excluding(
+    config: &mut YamlValue,
+    policy: PortConflictPolicy,
+    core_ports: &HashSet<u16>,
+) -> Result<Vec<PortConflict>, String> {
+    if policy == PortConflictPolicy::Ignore {
+        return Ok(Vec::new());
+    }
+
+    let bindings = collect_bindings(config);
+    let mut reserved: HashSet<u16> = bindings.iter().map(|b| b.port).collect();
+    let mut conflicts = Vec::new();
+
+    for binding in bindings {
+        if is_port_available(&binding.host, binding.port) {
+            continue;
+        }
+
+        if core_ports.contains(&binding.port) {
+            log::debug!("端口 {} 由当前运行的核心占用，跳过", binding.port);
+            continue;
+        }
+
+        let owner_process = find_port_owner(binding.port);
+        if let Some(owner) = &owner_process
+            && owner.starts_with(CORE_PROCESS_PREFIX)
+        {
+            log::debug!("端口 {} 由核心进程占用，跳过：{}", binding.port, owner);
+            continue;
+        }
+
+        log::warn!(
+            "端口冲突：{}（{}）已被占用，占用进程：{}",
+            binding.key,
+            binding.port,
+            owner_process.as_deref().unwrap_or("未知")
+        );
+
+        let resolved_port = if policy == PortConflictPolicy::Fallback {
+            let port = find_free_port(&binding.host, binding.port, &reserved).ok_or_else(|| {
+                format!(
+                    "{} 端口 {} 已被占用，且未找到可用的替代端口",
+                    binding.key, binding.port
+                )
+            })?;
+            reserved.insert(port);
+            rewrite_port(config, &binding, port);
+            log::info!("{} 改用端口 {}", binding.key, port);
+            Some(i32::from(port))
+        } else {
+            None
+        };
+
+        conflicts.push(PortConflict {
+            key: binding.key,
+            port: i32::from(binding.port),
+            owner_process,
+            resolved_port,
+        });
+    }
+
+    Ok(conflicts)
+}
+
+// 收集配置中所有需要绑定的端口
+fn collect_bindings(config: &YamlValue) -> Vec<PortBinding> {
+    let bind_address = config
+        .get("bind-address")
+        .and_then(|v| v.as_str())
+        .map(normalize_host)
+        .unwrap_or_else(|| "0.0.0.0".to_string());
+
+    let mut bindings = Vec::new();
+
+    for key in [
+        "mixed-port",
+        "port",
+        "socks-port",
+        "redir-port",
+        "tproxy-port",
+    ] {
+        if let Some(port) = config.get(key).and_then(as_port) {
+            bindings.push(PortBinding {
+                key: key.to_string(),
+                target: PortTarget::TopLevel(key),
+                host: bind_address.clone(),
+                port,
+            });
+        }
+    }
+
+    if let Some(listeners) = config.get("listeners").and_then(|v| v.as_sequence()) {
+        for (index, listener) in listeners.iter().enumerate() {
+            let Some(port) = listener.get("port").and_then(as_port) else {
+                continue;
+            };
+            let name = listener
+                .get("name")
+                .and_then(|n| n.as_str())
+                .unwrap_or_default();
+            let host = listener
+                .get("listen")
+                .and_then(|v| v.as_str())
+                .map(normalize_host)
+                .unwrap_or_else(|| bind_address.clone());
+
+            bindings.push(PortBinding {
+                key: format!("listeners.{}", name),
+                target: PortTarget::Listener(index),
+                host,
+                port,
+            });
+        }
+    }
+
+    if let Some((host, port)) = config
+        .get("external-controller")
+        .and_then(|v| v.as_str())
+        .and_then(split_host_port)
+    {
+        bindings.push(PortBinding {
+            key: "external-controller".to_string(),
+            target: PortTarget::ExternalController,
+            host: normalize_host(&host),
+            port,
+        });
+    }
+
+    bindings
+}
+
+fn as_port(value: &YamlValue) -> Option<u16> {
+    value
+        .as_u64()
+        .and_then(|p| u16::try_from(p).ok())
+        .filter(|p| *p > 0)
+}
+
+// 拆分 "host:port"，支持 "[::1]:9090" 和 ":9090"
+fn split_host_port(address: &str) -> Option<(String, u16)> {
+    let (host, port) = address.rsplit_once(':')?;
+    let port = port.parse::<u16>().ok().filter(|p| *p > 0)?;
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    Some((host.to_string(), port))
+}
+
+fn normalize_host(host: &str) -> String {
+    match host.trim() {
+        "" | "*" => "0.0.0.0".to_string(),
+        host => host.to_string(),
+    }
+}
+
+fn is_port_available(host: &str, port: u16) -> bool {
+    TcpListener::bind((host, port)).is_ok()
+}
+
+// 从冲突端口向上查找空闲端口，跳过配置中已使用的端口
+fn find_free_port(host: &str, start: u16, reserved: &HashSet<u16>) -> Option<u16> {
+    (1..=MAX_FALLBACK_ATTEMPTS)
+        .filter_map(|offset| start.checked_add(offset))
+        .find(|port| !reserved.contains(port) && is_port_available(host, *port))
+}
+
+fn rewrite_port(config: &mut YamlValue, binding: &PortBinding, port: u16) {
+    let Some(config_map) = config.as_mapping_mut() else {
+        return;
+    };
+
+    match binding.target {
+        PortTarget::TopLevel(key) => {
+            config_map.insert(
+                YamlValue::String(key.to_string()),
+                YamlValue::Number(port.into()),
+            );
+        }
+        PortTarget::Listener(index) => {
+            if let Some(listener) = config_map
+                .get_mut(YamlValue::String("listeners".to_string()))
+                .and_then(|v| v.as_sequence_mut())
+                .and_then(|listeners| listeners.get_mut(index))
+                .and_then(|v| v.as_mapping_mut())
+            {
+                listener.insert(
+                    YamlValue::String("port".to_string()),
+                    YamlValue::Number(port.into()),
+                );
+            }
+        }
+        PortTarget::ExternalController => {
+            let key = YamlValue::String("external-controller".to_string());
+            let Some(host) = config_map
+                .get(&key)
+                .and_then(|v| v.as_str())
+                .and_then(|address| address.rsplit_once(':'))
+                .map(|(host, _)| host.to_string())
+            else {
+                return;
+            };
+            config_map.insert(key, YamlValue::String(format!("{}:{}", host, port)));
+        }
+    }
+}
+
+// 查找监听指定 TCP 端口的进程名
+//
+// 通过 /proc 查找 socket inode 对应的进程；无权限读取的进程会被跳过
+#[cfg(target_os = "linux")]
+fn find_port_owner(port: u16) -> Option<String> {
+    use std::fs;
+
+    let mut inodes = HashSet::new();
+    for table in ["/proc/net/tcp", "/proc/net/tcp6"] {
+        let Ok(content) = fs::read_to_string(table) else {
+            continue;
+        };
+        for line in content.lines().skip(1) {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            // local_address 形如 0100007F:1EDC，状态 0A 表示 LISTEN
+            if fields.len() < 10 || fields[3] != "0A" {
+                continue;
+            }
+            let local_port = fields[1]
+                .rsplit_once(':')
+                .and_then(|(_, p)| u16::from_str_radix(p, 16).ok());
+            if local_port == Some(port) {
+                inodes.insert(format!("socket:[{}]", fields[9]));
+            }
+        }
+    }
+
+    if inodes.is_empty() {
+        return None;
+    }
+
+    for entry in fs::read_dir("/proc").ok()?.flatten() {
+        let Ok(fds) = fs::read_dir(entry.path().join("fd")) else {
+            continue;
+        };
+        for fd in fds.flatten() {
+            let Ok(link) = fs::read_link(fd.path()) else {
+                continue;
+            };
+            if inodes.contains(link.to_string_lossy().as_ref()) {
+                return fs::read_to_string(entry.path().join("comm"))
+                    .ok()
+                    .map(|name| name.trim().to_string());
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(target_os = "macos")]
+fn find_port_owner(port: u16) -> Option<String> {
+    use std::process::Command;
+
+    let output = Command::new("lsof")
+        .args(["-nP", &format!("-iTCP:{}", port), "-sTCP:LISTEN", "-Fc"])
+        .output()
+        .ok()?;
+
+    // -F 输出格式：每行以字段标识开头，c 为进程名
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| line.strip_prefix('c'))
+        .map(str::to_string)
+}
+
+#[cfg(target_os = "windows")]
+fn find_port_owner(port: u16) -> Option<String> {
+    use std::os::windows::process::CommandExt;
+    use std::process::Command;
+    const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+    let output = Command::new("netstat")
+        .args(["-ano"])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .ok()?;
+
+    // 行格式：TCP    127.0.0.1:7890    0.0.0.0:0    LISTENING    1234
+    let suffix = format!(":{}", port);
+    let pid = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>())
+        .find(|fields| {
+            fields.len() == 5
+                && fields[0].eq_ignore_ascii_case("TCP")
+                && fields[1].ends_with(&suffix)
+                && fields[3].eq_ignore_ascii_case("LISTENING")
+        })
+        .map(|fields| fields[4].to_string())?;
+
+    let output = Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {}", pid), "/FO", "CSV", "/NH"])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .ok()?;
+
+    // 输出格式："clash-core.exe","1234",...
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .next()
+        .and_then(|line| line.split(',').next())
+        .map(|name| name.trim_matches('"').to_string())
+        .filter(|name| !name.is_empty() && !name.starts_with("INFO:"))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn find_port_owner(_port: u16) -> Option<String> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_occupied_port_fallback() {
+        let Ok(occupied) = TcpListener::bind(("127.0.0.1", 0)) else {
+            panic!("无法绑定测试端口");
+        };
+        let port = occupied.local_addr().map(|a| a.port()).unwrap_or_default();
+
+        let yaml = format!(
+            "bind-address: 127.0.0.1\nmixed-port: {}\nexternal-controller: 127.0.0.1:{}\n",
+            port, port
+        );
+        let mut config: YamlValue = serde_yaml_ng::from_str(&yaml).unwrap_or_default();
+
+        let conflicts = resolve_port_conflicts(&mut config, PortConflictPolicy::Report)
+            .unwrap_or_else(|e| panic!("{}", e));
+        assert_eq!(conflicts.len(), 2);
+        assert!(conflicts.iter().all(|c| c.resolved_port.is_none()));
+
+        let conflicts = resolve_port_conflicts(&mut config, PortConflictPolicy::Fallback)
+            .unwrap_or_else(|e| panic!("{}", e));
+        assert_eq!(conflicts.len(), 2);
+
+        let mixed_port = config["mixed-port"].as_i64().unwrap_or_default();
+        let controller = config["external-controller"].as_str().unwrap_or_default();
+        assert_ne!(mixed_port, i64::from(port));
+        assert_eq!(conflicts[0].resolved_port.map(i64::from), Some(mixed_port));
+        assert!(!controller.ends_with(&format!(":{}", port)));
+        assert!(!controller.ends_with(&format!(":{}", mixed_port)));
+    }
+
+    #[test]
+    fn test_running_core_port_not_conflict() {
+        let Ok(occupied) = TcpListener::bind(("127.0.0.1", 0)) else {
+            panic!("无法绑定测试端口");
+        };
+        let port = occupied.local_addr().map(|a| a.port()).unwrap_or_default();
+
+        // 占用进程无法识别（特权服务运行的核心），但端口属于当前运行的核心
+        let yaml = format!("bind-address: 127.0.0.1\nmixed-port: {}\n", port);
+        let mut config: YamlValue = serde_yaml_ng::from_str(&yaml).unwrap_or_default();
+        let core_ports = HashSet::from([port]);
+
+        let conflicts = resolve_port_conflicts_excluding(
+            &mut config,
+            PortConflictPolicy::Fallback,
+            &core_ports,
+        )
+        .unwrap_or_else(|e| panic!("{}", e));
+        assert!(conflicts.is_empty());
+        assert_eq!(config["mixed-port"].as_u64(), Some(u64::from(port)));
+    }
+}
@@ -0,0 +1,312 @@
+// 配置热重载
+//
+// 目的：设置变更时对比新旧运行时配置，尽量在不重启核心的情况下生效，
+// 避免重启进程导致所有连接中断
+//
+// - 仅运行时可变字段变化：通过 PATCH /configs 修改
+// - 其他字段变化：通过 PUT /configs?force=true 完整重载
+// - 外部控制器等核心无法重载的字段变化：需要重启核心（由 Dart 层执行）
+// - 已切换到远程核心时拒绝应用，避免本机配置覆盖路由器、服务器上的配置
+
+use rinf::{DartSignal, RustSignal, SignalPiece};
+use serde::{Deserialize, Serialize};
+use serde_yaml_ng::{Mapping, Value as YamlValue};
+
+use crate::clash::network::ClashApiClient;
+use crate::clash::network::transport;
+
+// 可通过 PATCH /configs 在运行时修改的字段
+const PATCHABLE_KEYS: &[&str] = &[
+    "mode",
+    "log-level",
+    "allow-lan",
+    "bind-address",
+    "ipv6",
+    "mixed-port",
+    "port",
+    "socks-port",
+    "redir-port",
+    "tproxy-port",
+    "tun",
+    "tcp-concurrent",
+    "find-process-mode",
+    "interface-name",
+    "skip-auth-prefixes",
+];
+
+// 变化后必须重启核心的字段（控制器在核心启动时创建，重载不会重新监听）
+const RESTART_KEYS: &[&str] = &[
+    "external-controller",
+    "external-controller-unix",
+    "external-controller-pipe",
+    "external-controller-tls",
+    "secret",
+];
+
+// 重载方式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, SignalPiece, PartialEq, Eq)]
+pub enum ReloadStrategy {
+    Unchanged = 0, // 配置无变化
+    Patch = 1,     // PATCH /configs
+    Reload = 2,    // PUT /configs?force=true
+    Restart = 3,   // 需要重启核心
+}
+
+// Dart → Rust：热重载运行时配置请求
+#[derive(Debug, Clone, Serialize, Deserialize, DartSignal)]
+pub struct ReloadRuntimeConfigRequest {
+    pub running_config: String, // 核心当前使用的配置
+    pub new_config: String,     // 新生成的运行时配置
+}
+
+// Rust → Dart：热重载运行时配置响应
+//
+// strategy 为 Restart 时，Dart 层应重启核心；
+// 当前连接远程核心时 is_successful 为 false 且 strategy 为 Unchanged，不应重启本机核心
+#[derive(Debug, Clone, Serialize, Deserialize, RustSignal)]
+pub struct ReloadRuntimeConfigResponse {
+    pub is_successful: bool,
+    pub strategy: ReloadStrategy,
+    pub changed_keys: Vec<String>,
+    pub error_message: String,
+}
+
+// 重载计划
+struct ReloadPlan {
+    strategy: ReloadStrategy,
+    changed_keys: Vec<String>,
+    patch: Mapping, // 仅 Patch 策略使用
+}
+
+impl ReloadRuntimeConfigRequest {
+    pub async fn handle(self) -> ReloadRuntimeConfigResponse {
+        if transport::remote_controller().is_some() {
+            let message = "当前连接的是远程核心，无法应用本机运行时配置".to_string();
+            log::warn!("{}", message);
+            return ReloadRuntimeConfigResponse {
+                is_successful: false,
+                strategy: ReloadStrategy::Unchanged,
+                changed_keys: Vec::new(),
+                error_message: message,
+            };
+        }
+
+        let plan = match parse_config(&self.running_config)
+            .and_then(|running| Ok((running, parse_config(&self.new_config)?)))
+        {
+            Ok((running, new)) => plan_reload(&running, &new),
+            Err(e) => {
+                log::error!("热重载失败：{}", e);
+                return ReloadRuntimeConfigResponse {
+                    is_successful: false,
+                    strategy: ReloadStrategy::Restart,
+                    changed_keys: Vec::new(),
+                    error_message: e,
+                };
+            }
+        };
+
+        log::info!(
+            "配置热重载：{:?}，变化字段：{:?}",
+            plan.strategy,
+            plan.changed_keys
+        );
+
+        let result = match plan.strategy {
+            ReloadStrategy::Unchanged | ReloadStrategy::Restart => Ok(plan.strategy),
+            ReloadStrategy::Patch => match patch_config(&plan.patch).await {
+                Ok(()) => Ok(ReloadStrategy::Patch),
+                Err(e) => {
+                    log::warn!("PATCH 配置失败，改为完整重载：{}", e);
+                    reload_config(&self.new_config)
+                        .await
+                        .map(|()| ReloadStrategy::Reload)
+                }
+            },
+            ReloadStrategy::Reload => reload_config(&self.new_config)
+                .await
+                .map(|()| ReloadStrategy::Reload),
+        };
+
+        match result {
+            Ok(strategy) => ReloadRuntimeConfigResponse {
+                is_successful: true,
+                strategy,
+                changed_keys: plan.changed_keys,
+                error_message: String::new(),
+            },
+            Err(e) => {
+                log::error!("热重载失败，需要重启核心：{}", e);
+                ReloadRuntimeConfigResponse {
+                    is_successful: false,
+                    strategy: ReloadStrategy::Restart,
+                    changed_keys: plan.changed_keys,
+                    error_message: e,
+                }
+            }
+        }
+    }
+}
+
+fn parse_config(content: &str) -> Result<Mapping, String> {
+    match serde_yaml_ng::from_str::<YamlValue>(content) {
+        Ok(YamlValue::Mapping(map)) => Ok(map),
+        Ok(_) => Err("配置根节点必须是 Map".to_string()),
+        Err(e) => Err(format!("解析配置失败：{}", e)),
+    }
+}
+
+// 对比顶层字段，决定重载方式
+fn plan_reload(running: &Mapping, new: &Mapping) -> ReloadPlan {
+    let mut changed_keys = Vec::new();
+    let mut patch = Mapping::new();
+    let mut needs_reload = false;
+    let mut needs_restart = false;
+
+    let all_keys = running
+        .keys()
+        .chain(new.keys().filter(|key| !running.contains_key(*key)));
+
+    for key in all_keys {
+        let new_value = new.get(key);
+        if running.get(key) == new_value {
+            continue;
+        }
+
+        let name = key.as_str().unwrap_or_default();
+        changed_keys.push(name.to_string());
+
+        if RESTART_KEYS.contains(&name) {
+            needs_restart = true;
+        } else if let Some(value) = new_value
+            && PATCHABLE_KEYS.contains(&name)
+        {
+            patch.insert(key.clone(), value.clone());
+        } else {
+            // 被删除的字段无法通过 PATCH 恢复默认值
+            needs_reload = true;
+        }
+    }
+
+    let strategy = if changed_keys.is_empty() {
+        ReloadStrategy::Unchanged
+    } else if needs_restart {
+        ReloadStrategy::Restart
+    } else if needs_reload {
+        ReloadStrategy::Reload
+    } else {
+        ReloadStrategy::Patch
+    };
+
+    ReloadPlan {
+        strategy,
+        changed_keys,
+        patch,
+    }
+}
+
+async fn patch_config(patch: &Mapping) -> Result<(), String> {
+    ClashApiClient::new().patch_configs(patch).await?;
+    Ok(())
+}
+
+// 通过 payload 直接提交完整配置，无需核心读取配置文件
+async fn reload_config(config: &str) -> Result<(), String> {
+    ClashApiClient::new().reload_configs(config, true).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan(running: &str, new: &str) -> ReloadPlan {
+        let (Ok(running), Ok(new)) = (parse_config(running), parse_config(new)) else {
+            panic!("测试配置无效");
+        };
+        plan_reload(&running, &new)
+    }
+
+    #[test]
+    fn test_plan_reload() {
+        let base = "mode: rule\nlog-level: info\nproxies: []\n";
+
+        assert_eq!(plan(base, base).strategy, ReloadStrategy::Unchanged);
+
+        let result = plan(base, "mode: global\nlog-level: debug\nproxies: []\n");
+        assert_eq!(result.strategy, ReloadStrategy::Patch);
+        assert_eq!(result.changed_keys, vec!["mode", "log-level"]);
+        assert_eq!(result.patch.len(), 2);
+
+        let result = plan(
+            base,
+            "mode: global\nlog-level: info\nproxies: [{name: a}]\n",
+        );
+        assert_eq!(result.strategy, ReloadStrategy::Reload);
+
+        let result = plan(base, "mode: rule\nproxies: []\n");
+        assert_eq!(result.strategy, ReloadStrategy::Reload);
+
+        let result = plan(base, &format!("{}external-controller: :9090\n", base));
+        assert_eq!(result.strategy, ReloadStrategy::Restart);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_reload_refused_on_remote_core() {
+        use crate::clash::network::handlers::internal_ipc_send;
+        use crate::clash::network::mock_core;
+        use crate::clash::network::transport::SetControllerTransport;
+
+        let _guard = mock_core::exclusive().await;
+        let mock = mock_core::shared();
+        let remote_core = mock_core::MockCore::start();
+
+        let result = SetControllerTransport {
+            remote_url: remote_core.http_url(),
+            secret: "secret".to_string(),
+        }
+        .handle()
+        .await;
+        assert!(result.is_successful && result.is_remote);
+
+        let response = ReloadRuntimeConfigRequest {
+            running_config: "mode: rule\nproxies: []\n".to_string(),
+            new_config: "mode: global\nproxies: [{name: a}]\n".to_string(),
+        }
+        .handle()
+        .await;
+        assert!(!response.is_successful);
+        assert_eq!(response.strategy, ReloadStrategy::Unchanged);
+
+        // 绕过热重载直接发送的配置请求同样被拒绝，节点切换仍可用
+        assert!(
+            internal_ipc_send("PUT", "/configs?force=true", Some("{}"))
+                .await
+                .is_err()
+        );
+        assert!(
+            internal_ipc_send("PATCH", "/configs", Some("{}"))
+                .await
+                .is_err()
+        );
+        let Ok(response) =
+            internal_ipc_send("PUT", "/proxies/GLOBAL", Some(r#"{"name":"DIRECT"}"#)).await
+        else {
+            panic!("切换节点应转发到远程核心");
+        };
+        assert_eq!(response.status_code, 204);
+
+        SetControllerTransport {
+            remote_url: String::new(),
+            secret: String::new(),
+        }
+        .handle()
+        .await;
+
+        let requests = remote_core.requests();
+        assert!(requests.contains(&"PUT /proxies/GLOBAL".to_string()));
+        assert!(!requests.iter().any(|r| r.contains("/configs")));
+        assert!(mock.requests().is_empty());
+    }
+}
@@ -9,7 +9,14 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Clone, Serialize, Deserialize, DartSignal, SignalPiece)]
 pub struct RuntimeConfigParams {
     // 端口配置
-    pub http_port: i32,
+    pub http_port: i32, // 混合端口（mixed-port）
+
+    // 独立监听端口（为空时不启用）
+    pub http_proxy_port: Option<i32>, // port
+    pub socks_port: Option<i32>,      // socks-port
+    pub redir_port: Option<i32>,      // redir-port（Linux/macOS）
+    pub tproxy_port: Option<i32>,     // tproxy-port（仅 Linux）
+    pub listeners: Vec<InboundListener>,
 
     // 全局配置
     pub is_ipv6_enabled: bool,
@@ -31,6 +38,15 @@ pub struct RuntimeConfigParams {
     pub is_tun_icmp_forwarding_disabled: bool,
     pub tun_mtu: i32,
 
+    // 域名嗅探配置（与订阅中的 sniffer 合并，为空的项保留订阅中的值）
+    pub is_sniffer_enabled: Option<bool>, // 为空时不修改订阅中的 sniffer
+    pub sniffer_http_ports: Vec<String>,  // 端口或端口范围，如 "80"、"8080-8880"
+    pub sniffer_tls_ports: Vec<String>,
+    pub sniffer_quic_ports: Vec<String>,
+    pub sniffer_force_domain: Vec<String>,
+    pub sniffer_skip_domain: Vec<String>,
+    pub is_sniffer_override_destination_enabled: Option<bool>,
+
     // 核心配置
     pub geodata_loader: String,
     pub find_process_mode: String,
@@ -41,4 +57,89 @@ pub struct RuntimeConfigParams {
     // Keep-Alive 配置
     pub is_keep_alive_enabled: bool,
     pub keep_alive_interval: Option<i32>,
+
+    // DNS 配置（为空时仅在 TUN 启用时注入默认 DNS）
+    pub dns_profile: Option<DnsProfile>,
+
+    // 入站认证（为空时保留订阅或覆写中的值）
+    pub authentication: Vec<InboundUser>,
+    pub skip_auth_prefixes: Vec<String>, // 免认证的来源网段，如 127.0.0.1/32
+    pub is_lan_auth_required: bool,      // 允许局域网但未设置认证时拒绝生成配置（否则仅警告）
+
+    // 自定义 hosts（与订阅中的 hosts 合并，同名域名以此处为准）
+    pub hosts: Vec<HostMapping>,
+    pub is_dns_use_hosts_enabled: Option<bool>, // dns.use-hosts
+    pub is_dns_use_system_hosts_enabled: Option<bool>, // dns.use-system-hosts
+}
+
+// 域名到地址的映射
+//
+// 域名支持通配符（如 *.example.com、+.example.com）；
+// 地址为一个或多个 IP，只有一个地址时也可以是另一个域名（别名）
+#[derive(Debug, Clone, Serialize, Deserialize, SignalPiece)]
+pub struct HostMapping {
+    pub domain: String,
+    pub addresses: Vec<String>,
+}
+
+// 入站认证用户（对应 mihomo 的 authentication 配置）
+#[derive(Debug, Clone, Serialize, Deserialize, SignalPiece)]
+pub struct InboundUser {
+    pub username: String,
+    pub password: String,
+}
+
+// 额外入站监听器（对应 mihomo 的 listeners 配置）
+#[derive(Debug, Clone, Serialize, Deserialize, SignalPiece)]
+pub struct InboundListener {
+    pub name: String,
+    pub listener_type: String, // "mixed" | "http" | "socks" | "redir" | "tproxy"
+    pub port: i32,
+    pub listen: Option<String>, // 为空时跟随 bind-address
+    pub is_udp_enabled: Option<bool>,
+    pub proxy: Option<String>, // 直接交给指定代理或代理组，跳过规则
+    pub rule: Option<String>,  // 使用指定的子规则
+}
+
+// DNS 配置与订阅自带 DNS 的合并方式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, SignalPiece, PartialEq, Eq)]
+pub enum DnsPrecedence {
+    Override = 0, // 已设置的字段覆盖订阅中的同名字段
+    Fallback = 1, // 仅填充订阅中缺失的字段
+}
+
+// 用户 DNS 配置
+//
+// 列表为空、Option 为 None 的字段视为未设置，不会写入配置。
+// nameserver 等列表支持 udp/tcp 地址以及 DoH（https://）、DoT（tls://）、
+// DoQ（quic://）、DoH3（h3://）、dhcp:// 和 system
+#[derive(Debug, Clone, Serialize, Deserialize, SignalPiece)]
+pub struct DnsProfile {
+    pub precedence: DnsPrecedence,
+    pub enhanced_mode: Option<String>, // "fake-ip" | "redir-host" | "normal"
+    pub fake_ip_range: Option<String>,
+    pub fake_ip_filter: Vec<String>,
+    pub default_nameserver: Vec<String>,
+    pub nameserver: Vec<String>,
+    pub fallback: Vec<String>,
+    pub fallback_filter: Option<DnsFallbackFilter>,
+    pub nameserver_policy: Vec<DnsNameserverPolicy>,
+    pub proxy_server_nameserver: Vec<String>,
+    pub is_respect_rules_enabled: Option<bool>,
+}
+
+// fallback 过滤条件
+#[derive(Debug, Clone, Serialize, Deserialize, SignalPiece)]
+pub struct DnsFallbackFilter {
+    pub is_geoip_enabled: bool,
+    pub geoip_code: String,
+    pub ipcidr: Vec<String>,
+    pub domain: Vec<String>,
+}
+
+// 按域名指定 DNS 服务器（按列表顺序匹配）
+#[derive(Debug, Clone, Serialize, Deserialize, SignalPiece)]
+pub struct DnsNameserverPolicy {
+    pub pattern: String, // 域名、通配符或 geosite:/rule-set: 匹配
+    pub servers: Vec<String>,
 }
@@ -0,0 +1,483 @@
+// Clash 运行时配置校验
+//
+// 目的：在写入配置前发现会导致核心启动失败的引用错误，
+// 以结构化的错误和警告返回给 Dart
+
+use rinf::SignalPiece;
+use serde::{Deserialize, Serialize};
+use serde_yaml_ng::Value as YamlValue;
+use std::collections::{HashMap, HashSet};
+
+// 核心内置策略
+const BUILTIN_POLICIES: &[&str] = &[
+    "DIRECT",
+    "REJECT",
+    "REJECT-DROP",
+    "PASS",
+    "COMPATIBLE",
+    "GLOBAL",
+];
+
+// 规则末尾可选的附加参数
+const RULE_OPTIONS: &[&str] = &["no-resolve", "src"];
+
+// 问题类型
+#[derive(Debug, Deserialize, Serialize, SignalPiece, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigIssueKind {
+    UnknownReference = 0,         // 代理组引用了不存在的代理、代理组或提供者
+    UnknownPolicy = 1,            // 规则指向不存在的策略
+    MissingProviderSource = 2,    // 规则集或代理集缺少 URL/路径
+    DuplicateName = 3,            // 名称重复
+    CyclicReference = 4,          // 代理组循环引用
+    EmptyGroup = 5,               // 代理组没有任何可选项
+    LanWithoutAuthentication = 6, // 允许局域网访问但未设置入站认证
+    OverrideWarning = 7,          // 覆写被跳过或模板占位符无法解析
+    InheritedPortConflict = 8,    // 订阅中的入站端口与已占用端口冲突，已移除
+}
+
+// 单个校验问题
+#[derive(Debug, Deserialize, Serialize, SignalPiece, Clone)]
+pub struct ConfigIssue {
+    pub kind: ConfigIssueKind,
+    pub path: String, // 问题所在位置，例如 `proxy-groups[2].proxies`
+    pub message: String,
+}
+
+// 校验结果
+#[derive(Debug, Default)]
+pub struct ValidationReport {
+    pub errors: Vec<ConfigIssue>,
+    pub warnings: Vec<ConfigIssue>,
+}
+
+impl ValidationReport {
+    fn error(&mut self, kind: ConfigIssueKind, path: String, message: String) {
+        self.errors.push(ConfigIssue {
+            kind,
+            path,
+            message,
+        });
+    }
+
+    fn warning(&mut self, kind: ConfigIssueKind, path: String, message: String) {
+        self.warnings.push(ConfigIssue {
+            kind,
+            path,
+            message,
+        });
+    }
+}
+
+// 代理组信息
+struct GroupInfo<'a> {
+    index: usize,
+    name: &'a str,
+    members: Vec<&'a str>,
+}
+
+// 校验配置
+pub fn validate_config(config: &YamlValue) -> ValidationReport {
+    let mut report = ValidationReport::default();
+
+    let proxies = names_of(config, "proxies");
+    let groups = collect_groups(config);
+    let proxy_providers = provider_names(config, "proxy-providers");
+    let rule_providers = provider_names(config, "rule-providers");
+    let sub_rules: HashSet<&str> = config
+        .get("sub-rules")
+        .and_then(|v| v.as_mapping())
+        .map(|m| m.keys().filter_map(|k| k.as_str()).collect())
+        .unwrap_or_default();
+
+    check_duplicates(&proxies, &groups, &mut report);
+
+    let mut policies: HashSet<&str> = BUILTIN_POLICIES.iter().copied().collect();
+    policies.extend(proxies.iter().map(|(_, name)| *name));
+    policies.extend(groups.iter().map(|g| g.name));
+
+    check_groups(config, &groups, &policies, &proxy_providers, &mut report);
+    check_cycles(&groups, &mut report);
+    check_providers(config, "proxy-providers", &mut report);
+    check_providers(config, "rule-providers", &mut report);
+
+    if let Some(rules) = config.get("rules").and_then(|v| v.as_sequence()) {
+        check_rules(
+            rules,
+            "rules",
+            &policies,
+            &rule_providers,
+            &sub_rules,
+            &mut report,
+        );
+    }
+    if let Some(sub_rule_map) = config.get("sub-rules").and_then(|v| v.as_mapping()) {
+        for (name, rules) in sub_rule_map {
+            if let (Some(name), Some(rules)) = (name.as_str(), rules.as_sequence()) {
+                check_rules(
+                    rules,
+                    &format!("sub-rules.{}", name),
+                    &policies,
+                    &rule_providers,
+                    &sub_rules,
+                    &mut report,
+                );
+            }
+        }
+    }
+
+    report
+}
+
+fn names_of<'a>(config: &'a YamlValue, field: &str) -> Vec<(usize, &'a str)> {
+    config
+        .get(field)
+        .and_then(|v| v.as_sequence())
+        .map(|items| {
+            items
+                .iter()
+                .enumerate()
+                .filter_map(|(i, item)| item.get("name").and_then(|n| n.as_str()).map(|n| (i, n)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn collect_groups(config: &YamlValue) -> Vec<GroupInfo<'_>> {
+    config
+        .get("proxy-groups")
+        .and_then(|v| v.as_sequence())
+        .map(|items| {
+            items
+                .iter()
+                .enumerate()
+                .filter_map(|(index, item)| {
+                    let name = item.get("name")?.as_str()?;
+                    let members = item
+                        .get("proxies")
+                        .and_then(|p| p.as_sequence())
+                        .map(|p| p.iter().filter_map(|m| m.as_str()).collect())
+                        .unwrap_or_default();
+                    Some(GroupInfo {
+                        index,
+                        name,
+                        members,
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn provider_names<'a>(config: &'a YamlValue, field: &str) -> HashSet<&'a str> {
+    config
+        .get(field)
+        .and_then(|v| v.as_mapping())
+        .map(|m| m.keys().filter_map(|k| k.as_str()).collect())
+        .unwrap_or_default()
+}
+
+fn check_duplicates(
+    proxies: &[(usize, &str)],
+    groups: &[GroupInfo<'_>],
+    report: &mut ValidationReport,
+) {
+    let mut seen: HashMap<&str, String> = HashMap::new();
+
+    let entries = proxies
+        .iter()
+        .map(|(i, name)| (*name, format!("proxies[{}]", i)))
+        .chain(
+            groups
+                .iter()
+                .map(|g| (g.name, format!("proxy-groups[{}]", g.index))),
+        );
+
+    for (name, path) in entries {
+        if let Some(first) = seen.get(name) {
+            report.error(
+                ConfigIssueKind::DuplicateName,
+                path,
+                format!("名称 \"{}\" 重复（首次出现于 {}）", name, first),
+            );
+        } else {
+            seen.insert(name, path);
+        }
+    }
+}
+
+fn check_groups(
+    config: &YamlValue,
+    groups: &[GroupInfo<'_>],
+    policies: &HashSet<&str>,
+    proxy_providers: &HashSet<&str>,
+    report: &mut ValidationReport,
+) {
+    let Some(items) = config.get("proxy-groups").and_then(|v| v.as_sequence()) else {
+        return;
+    };
+
+    for group in groups {
+        let path = format!("proxy-groups[{}]", group.index);
+
+        for member in &group.members {
+            if !policies.contains(member) {
+                report.error(
+                    ConfigIssueKind::UnknownReference,
+                    format!("{}.proxies", path),
+                    format!(
+                        "代理组 \"{}\" 引用了不存在的代理或代理组 \"{}\"",
+                        group.name, member
+                    ),
+                );
+            }
+        }
+
+        let item = &items[group.index];
+        let uses: Vec<&str> = item
+            .get("use")
+            .and_then(|u| u.as_sequence())
+            .map(|u| u.iter().filter_map(|p| p.as_str()).collect())
+            .unwrap_or_default();
+        for provider in &uses {
+            if !proxy_providers.contains(provider) {
+                report.error(
+                    ConfigIssueKind::UnknownReference,
+                    format!("{}.use", path),
+                    format!(
+                        "代理组 \"{}\" 引用了不存在的代理集 \"{}\"",
+                        group.name, provider
+                    ),
+                );
+            }
+        }
+
+        let is_include_all = [
+            "include-all",
+            "include-all-proxies",
+            "include-all-providers",
+        ]
+        .iter()
+        .any(|key| item.get(*key).and_then(|v| v.as_bool()).unwrap_or(false));
+        if group.members.is_empty() && uses.is_empty() && !is_include_all {
+            report.warning(
+                ConfigIssueKind::EmptyGroup,
+                path,
+                format!("代理组 \"{}\" 没有任何代理", group.name),
+            );
+        }
+    }
+}
+
+// 使用深度优先搜索检测代理组之间的循环引用
+fn check_cycles(groups: &[GroupInfo<'_>], report: &mut ValidationReport) {
+    let by_name: HashMap<&str, &GroupInfo<'_>> = groups.iter().map(|g| (g.name, g)).collect();
+    let mut finished: HashSet<&str> = HashSet::new();
+    let mut reported: HashSet<&str> = HashSet::new();
+
+    for group in groups {
+        let mut stack: Vec<&str> = Vec::new();
+        visit(
+            group.name,
+            &by_name,
+            &mut stack,
+            &mut finished,
+            &mut reported,
+            report,
+        );
+    }
+
+    fn visit<'a>(
+        name: &'a str,
+        by_name: &HashMap<&'a str, &GroupInfo<'a>>,
+        stack: &mut Vec<&'a str>,
+        finished: &mut HashSet<&'a str>,
+        reported: &mut HashSet<&'a str>,
+        report: &mut ValidationReport,
+    ) {
+        if finished.contains(name) {
+            return;
+        }
+        if let Some(pos) = stack.iter().position(|n| *n == name) {
+            let cycle = &stack[pos..];
+            if cycle.iter().all(|n| !reported.contains(n)) {
+                reported.extend(cycle.iter().copied());
+                let mut chain: Vec<&str> = cycle.to_vec();
+                chain.push(name);
+                let index = by_name.get(name).map(|g| g.index).unwrap_or_default();
+                report.error(
+                    ConfigIssueKind::CyclicReference,
+                    format!("proxy-groups[{}]", index),
+                    format!("代理组存在循环引用：{}", chain.join(" → ")),
+                );
+            }
+            return;
+        }
+        let Some(group) = by_name.get(name) else {
+            return;
+        };
+
+        stack.push(name);
+        for member in &group.members {
+            if by_name.contains_key(member) {
+                visit(member, by_name, stack, finished, reported, report);
+            }
+        }
+        stack.pop();
+        finished.insert(name);
+    }
+}
+
+fn check_providers(config: &YamlValue, field: &str, report: &mut ValidationReport) {
+    let Some(providers) = config.get(field).and_then(|v| v.as_mapping()) else {
+        return;
+    };
+
+    for (name, provider) in providers {
+        let name = name.as_str().unwrap_or_default();
+        let provider_type = provider.get("type").and_then(|t| t.as_str()).unwrap_or("");
+        let has = |key: &str| {
+            provider
+                .get(key)
+                .and_then(|v| v.as_str())
+                .is_some_and(|v| !v.trim().is_empty())
+        };
+
+        let message = match provider_type {
+            "http" if !has("url") => Some("缺少 url"),
+            "file" if !has("path") => Some("缺少 path"),
+            "inline" => None,
+            "http" | "file" => None,
+            _ if !has("url") && !has("path") => Some("缺少 url 或 path"),
+            _ => None,
+        };
+
+        if let Some(message) = message {
+            report.error(
+                ConfigIssueKind::MissingProviderSource,
+                format!("{}.{}", field, name),
+                format!("\"{}\" {}", name, message),
+            );
+        }
+    }
+}
+
+fn check_rules(
+    rules: &[YamlValue],
+    path: &str,
+    policies: &HashSet<&str>,
+    rule_providers: &HashSet<&str>,
+    sub_rules: &HashSet<&str>,
+    report: &mut ValidationReport,
+) {
+    for (i, rule) in rules.iter().enumerate() {
+        let Some(rule) = rule.as_str() else {
+            continue;
+        };
+        let rule_path = format!("{}[{}]", path, i);
+        let rule_type = rule.split(',').next().unwrap_or("").trim();
+
+        if rule_type.eq_ignore_ascii_case("RULE-SET")
+            && let Some(provider) = rule.split(',').nth(1).map(str::trim)
+            && !rule_providers.contains(provider)
+        {
+            report.error(
+                ConfigIssueKind::UnknownReference,
+                rule_path.clone(),
+                format!("规则引用了不存在的规则集 \"{}\"", provider),
+            );
+        }
+
+        let Some(target) = rule_target(rule) else {
+            continue;
+        };
+
+        let (is_known, kind_name) = if rule_type.eq_ignore_ascii_case("SUB-RULE") {
+            (sub_rules.contains(target), "子规则")
+        } else {
+            (policies.contains(target), "策略")
+        };
+
+        if !is_known {
+            report.error(
+                ConfigIssueKind::UnknownPolicy,
+                rule_path,
+                format!("规则 \"{}\" 指向不存在的{} \"{}\"", rule, kind_name, target),
+            );
+        }
+    }
+}
+
+// 提取规则的目标策略
+//
+// 逻辑规则（AND/OR/NOT/SUB-RULE）的策略位于最后一个括号之后
+fn rule_target(rule: &str) -> Option<&str> {
+    let rule_type = rule.split(',').next().unwrap_or("").trim();
+    let is_logical = ["AND", "OR", "NOT", "SUB-RULE"]
+        .iter()
+        .any(|t| rule_type.eq_ignore_ascii_case(t));
+
+    let tail = match rule.rfind(')') {
+        Some(pos) if is_logical => &rule[pos + 1..],
+        _ => rule,
+    };
+
+    let mut parts: Vec<&str> = tail
+        .split(',')
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .collect();
+    while parts
+        .last()
+        .is_some_and(|p| RULE_OPTIONS.iter().any(|o| p.eq_ignore_ascii_case(o)))
+    {
+        parts.pop();
+    }
+
+    if is_logical {
+        return parts.first().copied();
+    }
+
+    match parts.len() {
+        0 | 1 => None,
+        _ => parts.last().copied(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validate(yaml: &str) -> ValidationReport {
+        let config: YamlValue = serde_yaml_ng::from_str(yaml).unwrap_or_default();
+        validate_config(&config)
+    }
+
+    #[test]
+    fn test_valid_config_has_no_errors() {
+        let report = validate(
+            "proxies:\n  - name: a\nproxy-groups:\n  - name: G\n    proxies: [a, DIRECT]\nrules:\n  - DOMAIN,x.com,G\n  - IP-CIDR,10.0.0.0/8,DIRECT,no-resolve\n  - AND,((DOMAIN,y.com),(NETWORK,UDP)),REJECT\n  - MATCH,G\n",
+        );
+        assert!(report.errors.is_empty(), "{:?}", report.errors);
+    }
+
+    #[test]
+    fn test_reports_reference_errors() {
+        let report = validate(
+            "proxies:\n  - name: a\n  - name: a\nproxy-groups:\n  - name: G1\n    proxies: [G2, missing]\n  - name: G2\n    proxies: [G1]\nrule-providers:\n  ads:\n    type: http\nrules:\n  - RULE-SET,nope,G1\n  - MATCH,unknown\n",
+        );
+        let kinds: Vec<ConfigIssueKind> = report.errors.iter().map(|e| e.kind).collect();
+
+        assert!(kinds.contains(&ConfigIssueKind::DuplicateName));
+        assert!(kinds.contains(&ConfigIssueKind::UnknownReference));
+        assert!(kinds.contains(&ConfigIssueKind::CyclicReference));
+        assert!(kinds.contains(&ConfigIssueKind::MissingProviderSource));
+        assert!(kinds.contains(&ConfigIssueKind::UnknownPolicy));
+        assert_eq!(
+            kinds
+                .iter()
+                .filter(|k| **k == ConfigIssueKind::CyclicReference)
+                .count(),
+            1
+        );
+    }
+}
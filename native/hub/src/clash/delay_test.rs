@@ -6,7 +6,7 @@ use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::{spawn, sync::Semaphore};
 
-use crate::clash::network::internal_ipc_get;
+use crate::clash::network::ClashApiClient;
 
 // Dart → Rust：单节点延迟测试请求
 #[derive(Deserialize, DartSignal)]
@@ -233,39 +233,27 @@ async fn batch_test_delays(
 //
 // 通过 IPC 调用 Clash API: GET /proxies/{proxyName}/delay?timeout={timeout}&url={testUrl}
 async fn test_single_node(node_name: &str, test_url: &str, timeout_ms: u32) -> i32 {
-    // 构建 Clash API 路径
-    let encoded_name = urlencoding::encode(node_name);
-    let path = format!(
-        "/proxies/{}/delay?timeout={}&url={}",
-        encoded_name, timeout_ms, test_url
-    );
-
     log::debug!("测试节点延迟：{}", node_name);
 
-    // 发送 IPC GET 请求
-    match internal_ipc_get(&path).await {
-        Ok(body) => {
-            // 解析 JSON 响应：{"delay": 123}
-            match serde_json::from_str::<serde_json::Value>(&body) {
-                Ok(json) => {
-                    if let Some(delay) = json.get("delay").and_then(|v| v.as_i64()) {
-                        let delay_i32 = delay as i32;
-                        if delay_i32 > 0 {
-                            log::info!("节点延迟测试成功：{} - {}ms", node_name, delay_i32);
-                        } else {
-                            log::warn!("节点延迟测试失败：{} - 超时", node_name);
-                        }
-                        delay_i32
-                    } else {
-                        log::error!("节点延迟测试响应格式错误：{}", node_name);
-                        -1
-                    }
-                }
-                Err(e) => {
-                    log::error!("节点延迟测试 JSON 解析失败：{} - {}", node_name, e);
-                    -1
-                }
-            }
+    match ClashApiClient::new()
+        .proxy_delay(node_name, test_url, timeout_ms)
+        .await
+    {
+        Ok(delay) if delay > 0 => {
+            log::info!("节点延迟测试成功：{} - {}ms", node_name, delay);
+            delay as i32
+        }
+        Ok(delay) => {
+            log::warn!(
+                "节点延迟测试失败：{} - 核心返回无效延迟 {}",
+                node_name,
+                delay
+            );
+            -1
+        }
+        Err(e) if e.is_timeout() => {
+            log::warn!("节点延迟测试失败：{} - 超时", node_name);
+            -1
         }
         Err(e) => {
             log::warn!("节点延迟测试 IPC 请求失败：{} - {}", node_name, e);
@@ -273,3 +261,55 @@ async fn test_single_node(node_name: &str, test_url: &str, timeout_ms: u32) -> i
         }
     }
 }
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::clash::network::mock_core;
+    use std::sync::Mutex;
+
+    const TEST_URL: &str = "https://www.gstatic.com/generate_204";
+
+    #[tokio::test]
+    async fn test_delay_against_mock_core() {
+        let _guard = mock_core::exclusive().await;
+        let mock = mock_core::shared();
+        mock.set_proxy_delay("香港 01", Some(30));
+        mock.set_proxy_delay("日本 01", None);
+        mock.set_proxy_delay("美国 01", Some(500));
+        mock.set_proxy_delay("台湾 01", Some(0));
+
+        assert_eq!(test_single_node("香港 01", TEST_URL, 200).await, 30);
+        assert_eq!(test_single_node("日本 01", TEST_URL, 50).await, -1);
+        assert_eq!(test_single_node("美国 01", TEST_URL, 50).await, -1);
+        assert_eq!(test_single_node("不存在", TEST_URL, 50).await, -1);
+        assert_eq!(test_single_node("台湾 01", TEST_URL, 50).await, -1);
+
+        let progress = Arc::new(Mutex::new(Vec::new()));
+        let recorder = Arc::clone(&progress);
+        let node_names = ["香港 01", "日本 01", "美国 01", "不存在"]
+            .map(String::from)
+            .to_vec();
+        let results = batch_test_delays(
+            node_names,
+            TEST_URL.to_string(),
+            100,
+            2,
+            Arc::new(move |node_name, delay_ms| {
+                if let Ok(mut progress) = recorder.lock() {
+                    progress.push((node_name, delay_ms));
+                }
+            }),
+        )
+        .await;
+
+        assert_eq!(results.len(), 4);
+        assert_eq!(progress.lock().map(|p| p.len()).unwrap_or_default(), 4);
+        let successes: Vec<_> = results.iter().filter(|r| r.delay_ms > 0).collect();
+        assert_eq!(successes.len(), 1);
+        assert_eq!(successes[0].node_name, "香港 01");
+
+        // 批量测试复用连接池，连接数不超过并发数
+        assert!(mock.accepted_connections() <= 2);
+    }
+}
@@ -1,14 +1,24 @@
 #![allow(unused_imports)]
 
+pub mod api_client;
+pub mod bridge;
 pub mod connection;
+pub mod connection_tracker;
 pub mod handlers;
 pub mod ipc_client;
+pub mod log_parser;
+#[cfg(all(test, unix))]
+pub mod mock_core;
+pub mod transport;
 pub mod ws_client;
 
+pub use api_client::{ApiError, ClashApiClient};
 pub use handlers::{
-    IpcDeleteRequest, IpcGetRequest, IpcLogData, IpcPatchRequest, IpcPostRequest, IpcPutRequest,
-    IpcResponse, IpcTrafficData, StartLogStream, StartTrafficStream, StopLogStream,
-    StopTrafficStream, StreamResult, init_rest_api_listeners, internal_ipc_get,
+    IpcCancelRequest, IpcConnectionsDiff, IpcDeleteRequest, IpcGetRequest, IpcLogData,
+    IpcMemoryData, IpcPatchRequest, IpcPostRequest, IpcPutRequest, IpcResponse, IpcStreamState,
+    IpcTrafficData, StartConnectionsStream, StartLogStream, StartMemoryStream, StartTrafficStream,
+    StopConnectionsStream, StopLogStream, StopMemoryStream, StopTrafficStream, StreamResult,
+    init_rest_api_listeners, subscribe_ws, unsubscribe_ws,
 };
 pub use ipc_client::IpcClient;
 pub use ws_client::WebSocketClient;
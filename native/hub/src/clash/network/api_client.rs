@@ -0,0 +1,302 @@
+// Clash API 类型化客户端
+//
+// 目的：基于 IPC 传输封装 mihomo REST API，提供结构化的请求/响应类型和错误，
+// 供 Rust 侧功能使用，避免手工拼接路径和解析 JSON
+//
+// 请求经由全局 IPC 连接池发送；仅封装 Rust 侧用到的接口，其余接口由 Dart 端直接请求
+
+use super::ipc_client::HttpResponse;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// API 错误
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApiError {
+    // 连接失败或读写中断
+    Transport(String),
+    // 核心返回非 2xx 状态码，message 取自响应体的 message 字段
+    Status { status_code: u16, message: String },
+    // 响应体无法解析
+    Decode(String),
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::Transport(e) => write!(f, "IPC 请求失败：{}", e),
+            ApiError::Status {
+                status_code,
+                message,
+            } => write!(f, "HTTP {}：{}", status_code, message),
+            ApiError::Decode(e) => write!(f, "解析响应失败：{}", e),
+        }
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+impl From<ApiError> for String {
+    fn from(error: ApiError) -> Self {
+        error.to_string()
+    }
+}
+
+impl ApiError {
+    // 延迟测试超时时核心返回 504（部分版本为 503）
+    pub fn is_timeout(&self) -> bool {
+        matches!(
+            self,
+            ApiError::Status {
+                status_code: 503 | 504,
+                ..
+            }
+        )
+    }
+}
+
+pub type ApiResult<T> = Result<T, ApiError>;
+
+// ============================================================================
+// 响应类型
+// ============================================================================
+
+// 连接元数据（端口在 API 中为字符串）
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ConnectionMetadata {
+    pub network: String,
+    #[serde(rename = "type")]
+    pub inbound_type: String,
+    #[serde(rename = "sourceIP")]
+    pub source_ip: String,
+    #[serde(rename = "destinationIP")]
+    pub destination_ip: String,
+    pub source_port: String,
+    pub destination_port: String,
+    pub host: String,
+    pub process: String,
+    pub process_path: String,
+    pub sniff_host: String,
+}
+
+// 单个连接
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ConnectionInfo {
+    pub id: String,
+    pub metadata: ConnectionMetadata,
+    pub upload: u64,
+    pub download: u64,
+    pub start: String,
+    pub chains: Vec<String>,
+    pub rule: String,
+    pub rule_payload: String,
+}
+
+// GET /connections
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ConnectionsSnapshot {
+    pub download_total: u64,
+    pub upload_total: u64,
+    pub connections: Vec<ConnectionInfo>,
+    pub memory: u64,
+}
+
+#[derive(Deserialize)]
+struct DelayResponse {
+    delay: u32,
+}
+
+#[derive(Deserialize)]
+struct ErrorResponse {
+    message: String,
+}
+
+// ============================================================================
+// 客户端
+// ============================================================================
+
+#[derive(Debug, Clone, Default)]
+pub struct ClashApiClient;
+
+impl ClashApiClient {
+    pub fn new() -> Self {
+        Self
+    }
+
+    // 测试单个节点延迟（毫秒）
+    pub async fn proxy_delay(&self, name: &str, url: &str, timeout_ms: u32) -> ApiResult<u32> {
+        let path = format!(
+            "/proxies/{}/delay?timeout={}&url={}",
+            urlencoding::encode(name),
+            timeout_ms,
+            urlencoding::encode(url)
+        );
+        let response: DelayResponse = self.get_json(&path).await?;
+        Ok(response.delay)
+    }
+
+    // 修改运行时配置，patch 为任意可序列化为 JSON 对象的值
+    pub async fn patch_configs<T: Serialize>(&self, patch: &T) -> ApiResult<()> {
+        self.send_json("PATCH", "/configs", patch).await
+    }
+
+    // 以完整配置内容重载核心配置
+    pub async fn reload_configs(&self, payload: &str, is_force: bool) -> ApiResult<()> {
+        let body = serde_json::json!({ "path": "", "payload": payload });
+        let path = if is_force {
+            "/configs?force=true"
+        } else {
+            "/configs"
+        };
+        self.send_json("PUT", path, &body).await
+    }
+
+    // ------------------------------------------------------------------------
+    // 传输
+    // ------------------------------------------------------------------------
+
+    async fn get_json<T: DeserializeOwned>(&self, path: &str) -> ApiResult<T> {
+        let body = self.send("GET", path, None).await?;
+        serde_json::from_str(&body).map_err(|e| ApiError::Decode(e.to_string()))
+    }
+
+    async fn send_json<B: Serialize + ?Sized>(
+        &self,
+        method: &str,
+        path: &str,
+        body: &B,
+    ) -> ApiResult<()> {
+        let body = serde_json::to_string(body).map_err(|e| ApiError::Decode(e.to_string()))?;
+        self.send(method, path, Some(&body)).await.map(drop)
+    }
+
+    // 发送请求，2xx 时返回响应体
+    async fn send(&self, method: &str, path: &str, body: Option<&str>) -> ApiResult<String> {
+        let response = super::handlers::internal_ipc_send(method, path, body)
+            .await
+            .map_err(ApiError::Transport)?;
+
+        check_status(response)
+    }
+}
+
+fn check_status(response: HttpResponse) -> ApiResult<String> {
+    if (200..300).contains(&response.status_code) {
+        return Ok(response.body);
+    }
+
+    let message = serde_json::from_str::<ErrorResponse>(&response.body)
+        .map(|e| e.message)
+        .unwrap_or(response.body);
+
+    Err(ApiError::Status {
+        status_code: response.status_code,
+        message,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_connections() {
+        let body = r#"{"downloadTotal":10,"uploadTotal":5,"connections":[{"id":"c1",
+            "metadata":{"network":"tcp","type":"HTTP","sourceIP":"127.0.0.1","sourcePort":"5000",
+            "host":"example.com"},"upload":1,"download":2,"chains":["DIRECT"],"rule":"Match"}]}"#;
+        let snapshot = serde_json::from_str::<ConnectionsSnapshot>(body).unwrap_or_default();
+        assert_eq!(snapshot.connections.len(), 1);
+        assert_eq!(snapshot.connections[0].metadata.source_ip, "127.0.0.1");
+        assert_eq!(snapshot.connections[0].metadata.host, "example.com");
+    }
+
+    #[test]
+    fn test_status_errors() {
+        let error = check_status(HttpResponse {
+            status_code: 504,
+            body: r#"{"message":"Timeout"}"#.to_string(),
+        });
+        let Err(error) = error else {
+            panic!("应返回错误");
+        };
+        assert!(error.is_timeout());
+        assert_eq!(error.to_string(), "HTTP 504：Timeout");
+
+        let error = check_status(HttpResponse {
+            status_code: 404,
+            body: "not found".to_string(),
+        });
+        assert_eq!(
+            error,
+            Err(ApiError::Status {
+                status_code: 404,
+                message: "not found".to_string(),
+            })
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_client_against_mock_core() {
+        use super::super::handlers::internal_ipc_send;
+        use super::super::mock_core;
+
+        let _guard = mock_core::exclusive().await;
+        let mock = mock_core::shared();
+        let client = ClashApiClient::new();
+        mock.set_proxy_delay("香港 01", Some(5));
+        mock.set_proxy_delay("日本 01", None);
+
+        assert_eq!(
+            client.proxy_delay("香港 01", "http://test", 200).await,
+            Ok(5)
+        );
+        assert!(
+            client
+                .proxy_delay("日本 01", "http://test", 50)
+                .await
+                .is_err_and(|e| e.is_timeout())
+        );
+
+        let patch = serde_json::json!({ "mode": "global" });
+        let Ok(()) = client.patch_configs(&patch).await else {
+            panic!("修改配置失败");
+        };
+        let configs = internal_ipc_send("GET", "/configs", None)
+            .await
+            .map(|r| r.body)
+            .unwrap_or_default();
+        assert!(configs.contains(r#""mode":"global""#));
+        assert!(
+            client
+                .patch_configs(&"global")
+                .await
+                .is_err_and(|e| matches!(
+                    e,
+                    ApiError::Status {
+                        status_code: 400,
+                        ..
+                    }
+                ))
+        );
+
+        assert_eq!(client.reload_configs("mode: rule", true).await, Ok(()));
+
+        mock.fail_path("/configs", 500);
+        assert!(
+            client
+                .reload_configs("mode: rule", true)
+                .await
+                .is_err_and(|e| matches!(
+                    e,
+                    ApiError::Status {
+                        status_code: 500,
+                        ..
+                    }
+                ))
+        );
+    }
+}
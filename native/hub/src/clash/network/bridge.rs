@@ -0,0 +1,562 @@
+// 本地 API 桥接
+//
+// 目的：核心通过 Unix Socket / Named Pipe 控制，HTTP 外部控制器为可选项。
+// 在本机回环地址上提供 HTTP/WebSocket 服务并转发到 IPC 端点，
+// 使 yacd、metacubexd 等网页面板无需暴露核心自身的 HTTP 控制器即可使用
+//
+// - 仅监听 127.0.0.1，所有请求需携带令牌：
+//   REST 使用 Authorization: Bearer <令牌>，WebSocket 使用 ?token=<令牌>（与核心 secret 的用法一致）
+// - 仅转发面板所需的路径，拒绝重启、升级核心及从文件加载配置等操作
+// - 每个 TCP 连接只处理一个请求（Connection: close）
+
+use futures_util::{SinkExt, StreamExt};
+use once_cell::sync::Lazy;
+use rinf::{DartSignal, RustSignal};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::spawn;
+use tokio::sync::Mutex;
+use tokio::task::{JoinHandle, JoinSet};
+use tokio_tungstenite::WebSocketStream;
+use tokio_tungstenite::tungstenite::handshake::derive_accept_key;
+use tokio_tungstenite::tungstenite::protocol::Role;
+
+use super::handlers::internal_ipc_send;
+use super::ipc_client::IpcClient;
+use super::transport;
+use super::ws_client::WebSocketClient;
+use crate::clash::config::credentials::random_alphanumeric;
+
+const TOKEN_LENGTH: usize = 32;
+
+// 请求头及请求体大小上限
+const MAX_HEAD_SIZE: usize = 64 * 1024;
+const MAX_BODY_SIZE: usize = 4 * 1024 * 1024;
+const MAX_HEADERS: usize = 64;
+
+// 读取请求头及请求体的超时，避免空闲连接一直占用任务
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+// 允许转发的路径（按路径段前缀匹配）
+const ALLOWED_PATHS: &[&str] = &[
+    "/version",
+    "/configs",
+    "/proxies",
+    "/group",
+    "/providers",
+    "/rules",
+    "/connections",
+    "/traffic",
+    "/memory",
+    "/logs",
+    "/dns/query",
+    "/cache",
+];
+
+// 即使路径允许也拒绝的请求：PUT /configs 可通过 path 参数读取任意文件
+// （路径先经 normalize_api_path 规范化，/configs/ 同样被拒绝）
+const DENIED_REQUESTS: &[(&str, &str)] = &[("PUT", "/configs")];
+
+const CORS_HEADERS: &str = "Access-Control-Allow-Origin: *\r\n\
+    Access-Control-Allow-Methods: GET, POST, PUT, PATCH, DELETE, OPTIONS\r\n\
+    Access-Control-Allow-Headers: Authorization, Content-Type\r\n";
+
+// Dart → Rust：启动本地 API 桥接
+#[derive(Deserialize, DartSignal)]
+pub struct StartApiBridge {
+    pub port: u16,     // 0 表示由系统分配
+    pub token: String, // 为空时随机生成
+}
+
+// Dart → Rust：停止本地 API 桥接
+#[derive(Deserialize, DartSignal)]
+pub struct StopApiBridge;
+
+// Rust → Dart：本地 API 桥接状态
+#[derive(Serialize, RustSignal)]
+pub struct ApiBridgeResult {
+    pub is_successful: bool,
+    pub is_running: bool,
+    pub port: u16,     // 实际监听的端口
+    pub token: String, // 面板连接时使用的令牌
+    pub error_message: Option<String>,
+}
+
+// 运行中的桥接服务
+struct BridgeServer {
+    port: u16,
+    token: String,
+    task: JoinHandle<()>,
+}
+
+static BRIDGE_SERVER: Lazy<Mutex<Option<BridgeServer>>> = Lazy::new(|| Mutex::new(None));
+
+// 解析后的请求头
+struct BridgeRequest {
+    method: String,
+    path: String,  // 不含查询参数
+    query: String, // 已移除 token 参数
+    authorization: Option<String>,
+    query_token: Option<String>,
+    websocket_key: Option<String>,
+    content_length: usize,
+}
+
+impl BridgeRequest {
+    // 转发给核心的路径
+    fn target(&self) -> String {
+        if self.query.is_empty() {
+            self.path.clone()
+        } else {
+            format!("{}?{}", self.path, self.query)
+        }
+    }
+
+    fn is_authorized(&self, token: &str) -> bool {
+        let bearer = self
+            .authorization
+            .as_deref()
+            .and_then(|value| value.strip_prefix("Bearer "));
+        [bearer, self.query_token.as_deref()]
+            .into_iter()
+            .flatten()
+            .any(|candidate| constant_time_eq(candidate.trim(), token))
+    }
+}
+
+impl StartApiBridge {
+    async fn handle(self) {
+        let mut server_guard = BRIDGE_SERVER.lock().await;
+        if let Some(server) = server_guard.take() {
+            log::info!("重新启动本地 API 桥接");
+            server.task.abort();
+        }
+
+        let listener = match TcpListener::bind(("127.0.0.1", self.port)).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                let message = format!("本地 API 桥接监听端口 {} 失败：{}", self.port, e);
+                log::error!("{}", message);
+                send_result(false, None, Some(message));
+                return;
+            }
+        };
+        let port = match listener.local_addr() {
+            Ok(addr) => addr.port(),
+            Err(e) => {
+                let message = format!("获取本地 API 桥接地址失败：{}", e);
+                log::error!("{}", message);
+                send_result(false, None, Some(message));
+                return;
+            }
+        };
+
+        let token = if self.token.is_empty() {
+            random_alphanumeric(TOKEN_LENGTH)
+        } else {
+            self.token
+        };
+
+        log::info!("本地 API 桥接已启动：127.0.0.1:{}", port);
+        let task = spawn(serve(listener, token.clone()));
+        let server = server_guard.insert(BridgeServer { port, token, task });
+        send_result(true, Some(&*server), None);
+    }
+}
+
+impl StopApiBridge {
+    async fn handle() {
+        if let Some(server) = BRIDGE_SERVER.lock().await.take() {
+            server.task.abort();
+            log::info!("本地 API 桥接已停止：127.0.0.1:{}", server.port);
+        }
+        send_result(true, None, None);
+    }
+}
+
+fn send_result(is_successful: bool, server: Option<&BridgeServer>, error_message: Option<String>) {
+    ApiBridgeResult {
+        is_successful,
+        is_running: server.is_some(),
+        port: server.map_or(0, |s| s.port),
+        token: server.map(|s| s.token.clone()).unwrap_or_default(),
+        error_message,
+    }
+    .send_signal_to_dart();
+}
+
+// 接受连接循环
+//
+// 连接任务由 JoinSet 持有，停止桥接时随服务任务一起结束（包括面板的 WebSocket 连接）
+async fn serve(listener: TcpListener, token: String) {
+    let mut connections = JoinSet::new();
+    loop {
+        // 回收已结束的连接任务
+        while connections.try_join_next().is_some() {}
+
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                let token = token.clone();
+                connections.spawn(async move {
+                    if let Err(e) = handle_connection(stream, &token).await {
+                        log::debug!("本地 API 桥接请求处理失败：{}", e);
+                    }
+                });
+            }
+            Err(e) => {
+                log::warn!("本地 API 桥接接受连接失败：{}", e);
+            }
+        }
+    }
+}
+
+async fn handle_connection(mut stream: TcpStream, token: &str) -> Result<(), String> {
+    let (head, mut body) = tokio::time::timeout(READ_TIMEOUT, read_head(&mut stream))
+        .await
+        .map_err(|_| "读取请求头超时".to_string())??;
+    let request = match parse_request(&head) {
+        Ok(request) => request,
+        Err(e) => return write_error(&mut stream, 400, &e).await,
+    };
+
+    // CORS 预检请求不携带令牌
+    if request.method == "OPTIONS" {
+        return write_response(&mut stream, 204, "").await;
+    }
+
+    if !request.is_authorized(token) {
+        return write_error(&mut stream, 401, "Unauthorized").await;
+    }
+
+    if !is_request_allowed(&request.method, &request.path) {
+        log::warn!("本地 API 桥接拒绝请求：{} {}", request.method, request.path);
+        return write_error(&mut stream, 403, "Forbidden").await;
+    }
+
+    if let Some(key) = &request.websocket_key {
+        return proxy_websocket(stream, key, &request.target()).await;
+    }
+
+    if request.content_length > MAX_BODY_SIZE {
+        return write_error(&mut stream, 413, "Payload Too Large").await;
+    }
+    while body.len() < request.content_length {
+        let mut chunk = vec![0u8; request.content_length - body.len()];
+        let read = tokio::time::timeout(READ_TIMEOUT, stream.read(&mut chunk))
+            .await
+            .map_err(|_| "读取请求体超时".to_string())?
+            .map_err(|e| format!("读取请求体失败：{}", e))?;
+        if read == 0 {
+            return Err("请求体不完整".to_string());
+        }
+        body.extend_from_slice(&chunk[..read]);
+    }
+    body.truncate(request.content_length);
+
+    let body = match String::from_utf8(body) {
+        Ok(body) => body,
+        Err(_) => return write_error(&mut stream, 400, "请求体不是有效的 UTF-8").await,
+    };
+    let body = (!body.is_empty()).then_some(body.as_str());
+
+    match internal_ipc_send(&request.method, &request.target(), body).await {
+        Ok(response) => write_response(&mut stream, response.status_code, &response.body).await,
+        Err(e) => {
+            log::warn!("本地 API 桥接转发失败：{}", e);
+            write_error(&mut stream, 502, &e).await
+        }
+    }
+}
+
+// 读取请求头，返回请求头及已读取的部分请求体
+async fn read_head(stream: &mut TcpStream) -> Result<(Vec<u8>, Vec<u8>), String> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+    loop {
+        let read = stream
+            .read(&mut chunk)
+            .await
+            .map_err(|e| format!("读取请求失败：{}", e))?;
+        if read == 0 {
+            return Err("连接已关闭".to_string());
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+
+        if let Some(end) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+            let body = buffer.split_off(end + 4);
+            return Ok((buffer, body));
+        }
+        if buffer.len() > MAX_HEAD_SIZE {
+            return Err("请求头过大".to_string());
+        }
+    }
+}
+
+fn parse_request(head: &[u8]) -> Result<BridgeRequest, String> {
+    let mut headers = [httparse::EMPTY_HEADER; MAX_HEADERS];
+    let mut parsed = httparse::Request::new(&mut headers);
+    parsed
+        .parse(head)
+        .map_err(|e| format!("解析请求失败：{}", e))?;
+
+    let method = parsed.method.unwrap_or_default().to_string();
+    let (path, query) = match parsed.path.unwrap_or("/").split_once('?') {
+        Some((path, query)) => (path, query),
+        None => (parsed.path.unwrap_or("/"), ""),
+    };
+
+    // 移除 token 参数，其余参数原样转发
+    let mut query_token = None;
+    let query = query
+        .split('&')
+        .filter(|pair| match pair.strip_prefix("token=") {
+            Some(value) => {
+                query_token = urlencoding::decode(value).ok().map(|v| v.into_owned());
+                false
+            }
+            None => !pair.is_empty(),
+        })
+        .collect::<Vec<_>>()
+        .join("&");
+
+    let header = |name: &str| {
+        parsed
+            .headers
+            .iter()
+            .find(|h| h.name.eq_ignore_ascii_case(name))
+            .and_then(|h| std::str::from_utf8(h.value).ok())
+            .map(|v| v.trim().to_string())
+    };
+
+    let is_websocket = header("upgrade").is_some_and(|v| v.eq_ignore_ascii_case("websocket"));
+
+    Ok(BridgeRequest {
+        method,
+        path: path.to_string(),
+        query,
+        authorization: header("authorization"),
+        query_token,
+        websocket_key: header("sec-websocket-key").filter(|_| is_websocket),
+        content_length: header("content-length")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0),
+    })
+}
+
+// 规范化 API 路径：去除末尾的 "/"，拒绝 ".." 段、空段及编码后的 "/"、"\"、"."，
+// 避免 "/configs/"、"/proxies/../configs" 等写法绕过按路径的过滤
+pub fn normalize_api_path(path: &str) -> Option<&str> {
+    let lowercase = path.to_ascii_lowercase();
+    if ["%2f", "%5c", "%2e"]
+        .iter()
+        .any(|encoded| lowercase.contains(encoded))
+    {
+        return None;
+    }
+
+    let path = path.trim_end_matches('/');
+    if path.is_empty() {
+        return Some("/");
+    }
+    if !path.starts_with('/')
+        || path
+            .split('/')
+            .skip(1)
+            .any(|segment| matches!(segment, "" | "." | ".."))
+    {
+        return None;
+    }
+
+    Some(path)
+}
+
+fn is_request_allowed(method: &str, path: &str) -> bool {
+    let Some(path) = normalize_api_path(path) else {
+        return false;
+    };
+
+    let matches = |prefix: &str| {
+        path == prefix
+            || path
+                .strip_prefix(prefix)
+                .is_some_and(|rest| rest.starts_with('/'))
+    };
+
+    ALLOWED_PATHS.iter().any(|prefix| matches(prefix))
+        && !DENIED_REQUESTS
+            .iter()
+            .any(|(denied_method, denied_path)| method == *denied_method && path == *denied_path)
+}
+
+// 在核心与面板之间双向转发 WebSocket 消息（核心连接跟随当前传输方式）
+async fn proxy_websocket(stream: TcpStream, key: &str, target: &str) -> Result<(), String> {
+    if let Some(remote) = transport::remote_controller() {
+        let core_ws = remote.open_websocket(target).await;
+        return pipe_websocket(stream, key, core_ws).await;
+    }
+
+    let core_ws = WebSocketClient::open_stream(&IpcClient::default_ipc_path(), target).await;
+    pipe_websocket(stream, key, core_ws).await
+}
+
+async fn pipe_websocket<S>(
+    mut stream: TcpStream,
+    key: &str,
+    core_ws: Result<WebSocketStream<S>, String>,
+) -> Result<(), String>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let core_ws = match core_ws {
+        Ok(ws) => ws,
+        Err(e) => return write_error(&mut stream, 502, &e).await,
+    };
+
+    let handshake = format!(
+        "HTTP/1.1 101 Switching Protocols\r\n\
+         Upgrade: websocket\r\n\
+         Connection: Upgrade\r\n\
+         Sec-WebSocket-Accept: {}\r\n\r\n",
+        derive_accept_key(key.as_bytes())
+    );
+    stream
+        .write_all(handshake.as_bytes())
+        .await
+        .map_err(|e| format!("发送 WebSocket 握手响应失败：{}", e))?;
+
+    let client_ws = WebSocketStream::from_raw_socket(stream, Role::Server, None).await;
+    let (mut client_tx, mut client_rx) = client_ws.split();
+    let (mut core_tx, mut core_rx) = core_ws.split();
+
+    // 任一方向断开即结束转发
+    tokio::select! {
+        _ = async {
+            while let Some(Ok(message)) = core_rx.next().await {
+                if client_tx.send(message).await.is_err() {
+                    break;
+                }
+            }
+        } => {}
+        _ = async {
+            while let Some(Ok(message)) = client_rx.next().await {
+                if core_tx.send(message).await.is_err() {
+                    break;
+                }
+            }
+        } => {}
+    }
+
+    let _ = client_tx.close().await;
+    let _ = core_tx.close().await;
+    Ok(())
+}
+
+async fn write_error(
+    stream: &mut TcpStream,
+    status_code: u16,
+    message: &str,
+) -> Result<(), String> {
+    let body = serde_json::json!({ "message": message }).to_string();
+    write_response(stream, status_code, &body).await
+}
+
+async fn write_response(
+    stream: &mut TcpStream,
+    status_code: u16,
+    body: &str,
+) -> Result<(), String> {
+    let reason = http::StatusCode::from_u16(status_code)
+        .ok()
+        .and_then(|status| status.canonical_reason())
+        .unwrap_or("");
+    let response = format!(
+        "HTTP/1.1 {} {}\r\n\
+         Content-Type: application/json; charset=utf-8\r\n\
+         Content-Length: {}\r\n\
+         Connection: close\r\n\
+         {}\r\n{}",
+        status_code,
+        reason,
+        body.len(),
+        CORS_HEADERS,
+        body
+    );
+    stream
+        .write_all(response.as_bytes())
+        .await
+        .map_err(|e| format!("发送响应失败：{}", e))?;
+    let _ = stream.shutdown().await;
+    Ok(())
+}
+
+// 逐字节比较令牌，避免通过响应时间推测令牌内容
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |diff, (x, y)| diff | (x ^ y))
+            == 0
+}
+
+// 初始化本地 API 桥接消息监听器
+pub fn init_message_listeners() {
+    spawn(async {
+        let receiver = StartApiBridge::get_dart_signal_receiver();
+        while let Some(dart_signal) = receiver.recv().await {
+            dart_signal.message.handle().await;
+        }
+    });
+
+    spawn(async {
+        let receiver = StopApiBridge::get_dart_signal_receiver();
+        while let Some(_dart_signal) = receiver.recv().await {
+            StopApiBridge::handle().await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(head: &str) -> BridgeRequest {
+        parse_request(head.as_bytes()).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    #[test]
+    fn test_bridge_request_auth() {
+        let req = request("GET /logs?token=abc%2B1&level=debug HTTP/1.1\r\nHost: x\r\n\r\n");
+        assert_eq!(req.target(), "/logs?level=debug");
+        assert!(req.is_authorized("abc+1"));
+        assert!(!req.is_authorized("abc"));
+
+        let req = request("GET /version HTTP/1.1\r\nAuthorization: Bearer secret\r\n\r\n");
+        assert_eq!(req.target(), "/version");
+        assert!(req.is_authorized("secret"));
+
+        let req = request("GET /version HTTP/1.1\r\n\r\n");
+        assert!(!req.is_authorized("secret"));
+    }
+
+    #[test]
+    fn test_bridge_path_filter() {
+        assert!(is_request_allowed("GET", "/proxies"));
+        assert!(is_request_allowed("PUT", "/proxies/GLOBAL"));
+        assert!(is_request_allowed("PATCH", "/configs"));
+        assert!(!is_request_allowed("PUT", "/configs"));
+        assert!(!is_request_allowed("PUT", "/configs/"));
+        assert!(!is_request_allowed("PUT", "/configs//"));
+        assert!(!is_request_allowed("PUT", "/proxies/../configs"));
+        assert!(!is_request_allowed("PUT", "/proxies/%2e%2e/configs"));
+        assert!(!is_request_allowed("PUT", "/proxies%2F..%2Fconfigs"));
+        assert!(!is_request_allowed("GET", "//configs"));
+        assert!(is_request_allowed("GET", "/proxies/"));
+        assert_eq!(normalize_api_path("/connections/"), Some("/connections"));
+        assert_eq!(normalize_api_path("/"), Some("/"));
+        assert!(!is_request_allowed("POST", "/restart"));
+        assert!(!is_request_allowed("GET", "/proxiesx"));
+        assert!(!is_request_allowed("GET", "/debug/pprof"));
+    }
+}
@@ -0,0 +1,250 @@
+// 连接快照增量计算
+//
+// 目的：核心每个周期推送完整的连接快照，体积大且大部分连接没有变化，
+// 在 Rust 侧与上一次快照对比，只把新增、关闭和流量变化的连接发送给 Dart
+
+use super::api_client::{ConnectionInfo, ConnectionsSnapshot};
+use rinf::SignalPiece;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Instant;
+
+// 新增连接
+#[derive(Debug, Clone, Serialize, Deserialize, SignalPiece, PartialEq)]
+pub struct ConnectionEntry {
+    pub id: String,
+    pub network: String,      // "tcp" | "udp"
+    pub inbound_type: String, // "HTTP" | "Socks5" | "Tun" 等
+    pub source_ip: String,
+    pub source_port: String,
+    pub destination_ip: String,
+    pub destination_port: String,
+    pub host: String,
+    pub process: String,
+    pub process_path: String,
+    pub chains: Vec<String>,
+    pub rule: String,
+    pub rule_payload: String,
+    pub start: String,
+    pub upload: u64,
+    pub download: u64,
+}
+
+impl From<ConnectionInfo> for ConnectionEntry {
+    fn from(connection: ConnectionInfo) -> Self {
+        let metadata = connection.metadata;
+        Self {
+            id: connection.id,
+            network: metadata.network,
+            inbound_type: metadata.inbound_type,
+            source_ip: metadata.source_ip,
+            source_port: metadata.source_port,
+            destination_ip: metadata.destination_ip,
+            destination_port: metadata.destination_port,
+            // 没有域名时（如 TUN 下的纯 IP 连接）使用嗅探到的域名
+            host: if metadata.host.is_empty() {
+                metadata.sniff_host
+            } else {
+                metadata.host
+            },
+            process: metadata.process,
+            process_path: metadata.process_path,
+            chains: connection.chains,
+            rule: connection.rule,
+            rule_payload: connection.rule_payload,
+            start: connection.start,
+            upload: connection.upload,
+            download: connection.download,
+        }
+    }
+}
+
+// 已有连接的流量变化
+#[derive(Debug, Clone, Serialize, Deserialize, SignalPiece, PartialEq)]
+pub struct ConnectionUpdate {
+    pub id: String,
+    pub upload: u64,
+    pub download: u64,
+    pub upload_delta: u64,
+    pub download_delta: u64,
+    pub upload_speed: u64, // 字节/秒
+    pub download_speed: u64,
+}
+
+// 两次快照之间的差异
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConnectionsDiff {
+    pub added: Vec<ConnectionEntry>,
+    pub updated: Vec<ConnectionUpdate>,
+    pub closed: Vec<String>,
+    pub upload_total: u64,
+    pub download_total: u64,
+}
+
+impl ConnectionsDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.updated.is_empty() && self.closed.is_empty()
+    }
+}
+
+// 上一次快照中连接的累计流量和上报的速度
+#[derive(Debug, Default, Clone, Copy)]
+struct TrackedConnection {
+    upload: u64,
+    download: u64,
+    upload_speed: u64,
+    download_speed: u64,
+}
+
+// 保存上一次快照中各连接的状态
+#[derive(Debug, Default)]
+pub struct ConnectionTracker {
+    connections: HashMap<String, TrackedConnection>,
+    last_update: Option<Instant>,
+}
+
+impl ConnectionTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // 对比新快照，返回差异并记录为当前状态
+    pub fn update(&mut self, snapshot: ConnectionsSnapshot, now: Instant) -> ConnectionsDiff {
+        let elapsed_secs = self
+            .last_update
+            .map(|last| now.duration_since(last).as_secs_f64())
+            .filter(|secs| *secs > 0.0);
+        self.last_update = Some(now);
+
+        let mut previous = std::mem::take(&mut self.connections);
+        let mut diff = ConnectionsDiff {
+            upload_total: snapshot.upload_total,
+            download_total: snapshot.download_total,
+            ..Default::default()
+        };
+
+        for connection in snapshot.connections {
+            let mut tracked = TrackedConnection {
+                upload: connection.upload,
+                download: connection.download,
+                ..Default::default()
+            };
+
+            let Some(last) = previous.remove(&connection.id) else {
+                self.connections.insert(connection.id.clone(), tracked);
+                diff.added.push(connection.into());
+                continue;
+            };
+
+            let upload_delta = connection.upload.saturating_sub(last.upload);
+            let download_delta = connection.download.saturating_sub(last.download);
+            let speed = |delta: u64| elapsed_secs.map_or(0, |secs| (delta as f64 / secs) as u64);
+            tracked.upload_speed = speed(upload_delta);
+            tracked.download_speed = speed(download_delta);
+            self.connections.insert(connection.id.clone(), tracked);
+
+            // 流量没有变化时，仅在上次上报的速度不为 0 时发送一次速度归零的更新
+            let was_idle = last.upload_speed == 0 && last.download_speed == 0;
+            if upload_delta == 0 && download_delta == 0 && was_idle {
+                continue;
+            }
+
+            diff.updated.push(ConnectionUpdate {
+                id: connection.id,
+                upload: connection.upload,
+                download: connection.download,
+                upload_delta,
+                download_delta,
+                upload_speed: tracked.upload_speed,
+                download_speed: tracked.download_speed,
+            });
+        }
+
+        // 上一次存在但本次快照中没有的连接视为已关闭
+        diff.closed = previous.into_keys().collect();
+        diff.closed.sort();
+
+        diff
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn connection(id: &str, upload: u64, download: u64) -> ConnectionInfo {
+        ConnectionInfo {
+            id: id.to_string(),
+            upload,
+            download,
+            ..Default::default()
+        }
+    }
+
+    fn snapshot(connections: Vec<ConnectionInfo>) -> ConnectionsSnapshot {
+        ConnectionsSnapshot {
+            connections,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_connection_diff() {
+        let mut tracker = ConnectionTracker::new();
+        let start = Instant::now();
+
+        let diff = tracker.update(
+            snapshot(vec![connection("a", 10, 20), connection("b", 0, 0)]),
+            start,
+        );
+        assert_eq!(diff.added.len(), 2);
+        assert!(diff.updated.is_empty() && diff.closed.is_empty());
+
+        let diff = tracker.update(
+            snapshot(vec![connection("a", 110, 2020), connection("c", 1, 1)]),
+            start + Duration::from_secs(2),
+        );
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].id, "c");
+        assert_eq!(diff.closed, vec!["b".to_string()]);
+        assert_eq!(
+            diff.updated,
+            vec![ConnectionUpdate {
+                id: "a".to_string(),
+                upload: 110,
+                download: 2020,
+                upload_delta: 100,
+                download_delta: 2000,
+                upload_speed: 50,
+                download_speed: 1000,
+            }]
+        );
+
+        // 连接 a 进入空闲：发送一次速度归零的更新
+        let diff = tracker.update(
+            snapshot(vec![connection("a", 110, 2020), connection("c", 1, 1)]),
+            start + Duration::from_secs(3),
+        );
+        assert!(diff.added.is_empty() && diff.closed.is_empty());
+        assert_eq!(
+            diff.updated,
+            vec![ConnectionUpdate {
+                id: "a".to_string(),
+                upload: 110,
+                download: 2020,
+                upload_delta: 0,
+                download_delta: 0,
+                upload_speed: 0,
+                download_speed: 0,
+            }]
+        );
+
+        // 保持空闲：不再发送更新
+        let diff = tracker.update(
+            snapshot(vec![connection("a", 110, 2020), connection("c", 1, 1)]),
+            start + Duration::from_secs(4),
+        );
+        assert!(diff.is_empty());
+    }
+}
@@ -2,15 +2,19 @@
 //
 // 处理 Dart 层发送的 IPC 请求，通过 IpcClient 转发给 Clash 核心
 
-use super::ipc_client::IpcClient;
+use super::api_client::ConnectionsSnapshot;
+use super::connection_tracker::{ConnectionEntry, ConnectionTracker, ConnectionUpdate};
+use super::ipc_client::{HttpResponse, IpcClient};
+use super::log_parser;
+use super::transport;
 use super::ws_client::WebSocketClient;
 use once_cell::sync::Lazy;
-use rinf::{DartSignal, RustSignal};
+use rinf::{DartSignal, RustSignal, SignalPiece};
 use serde::{Deserialize, Serialize};
-use std::collections::VecDeque;
-use std::sync::Arc;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, PoisonError};
 use std::time::{Duration, Instant};
-use tokio::sync::{RwLock, Semaphore};
+use tokio::sync::{RwLock, Semaphore, oneshot};
 
 #[cfg(unix)]
 use tokio::net::UnixStream;
@@ -23,6 +27,7 @@ use tokio::net::windows::named_pipe::NamedPipeClient;
 pub struct IpcGetRequest {
     pub request_id: i64,
     pub path: String,
+    pub timeout_ms: Option<u32>, // 整个请求（含重试）的超时时间，为空表示不限制
 }
 
 // Dart → Rust：通过 IPC 发送 POST 请求
@@ -31,6 +36,7 @@ pub struct IpcPostRequest {
     pub request_id: i64,
     pub path: String,
     pub body: Option<String>,
+    pub timeout_ms: Option<u32>, // 整个请求（含重试）的超时时间，为空表示不限制
 }
 
 // Dart → Rust：通过 IPC 发送 PUT 请求
@@ -39,6 +45,7 @@ pub struct IpcPutRequest {
     pub request_id: i64,
     pub path: String,
     pub body: Option<String>,
+    pub timeout_ms: Option<u32>, // 整个请求（含重试）的超时时间，为空表示不限制
 }
 
 // Dart → Rust：通过 IPC 发送 PATCH 请求
@@ -47,6 +54,7 @@ pub struct IpcPatchRequest {
     pub request_id: i64,
     pub path: String,
     pub body: Option<String>,
+    pub timeout_ms: Option<u32>, // 整个请求（含重试）的超时时间，为空表示不限制
 }
 
 // Dart → Rust：通过 IPC 发送 DELETE 请求
@@ -54,6 +62,13 @@ pub struct IpcPatchRequest {
 pub struct IpcDeleteRequest {
     pub request_id: i64,
     pub path: String,
+    pub timeout_ms: Option<u32>, // 整个请求（含重试）的超时时间，为空表示不限制
+}
+
+// Dart → Rust：取消进行中的 IPC 请求
+#[derive(Deserialize, DartSignal)]
+pub struct IpcCancelRequest {
+    pub request_id: i64,
 }
 
 // Rust → Dart：IPC 请求响应
@@ -69,23 +84,40 @@ pub struct IpcResponse {
     pub is_successful: bool,
     // 错误消息（如果有）
     pub error_message: Option<String>,
+    // 是否因 IpcCancelRequest 被取消
+    pub is_cancelled: bool,
+    // 是否因超过 timeout_ms 被中止
+    pub is_timed_out: bool,
 }
 
 // WebSocket 流式数据
 
 // Dart → Rust：开始监听 Clash 日志
 #[derive(Deserialize, DartSignal)]
-pub struct StartLogStream;
+pub struct StartLogStream {
+    pub level: String, // "debug" | "info" | "warning" | "error" | "silent"，为空时使用 info
+    pub keywords: Vec<String>, // 仅转发包含任一关键字的日志，为空时不过滤
+}
 
 // Dart → Rust：停止监听 Clash 日志
 #[derive(Deserialize, DartSignal)]
 pub struct StopLogStream;
 
 // Rust → Dart：Clash 日志数据
+//
+// 连接日志会解析出结构化字段，其他日志的这些字段为空
 #[derive(Serialize, RustSignal)]
 pub struct IpcLogData {
     pub log_type: String,
     pub payload: String,
+    pub timestamp: String, // 收到日志的本地时间（RFC 3339）
+    pub network: String,   // "TCP" | "UDP"
+    pub source: String,
+    pub process: String,
+    pub destination: String,
+    pub rule: String,
+    pub rule_payload: String,
+    pub proxy_chain: Vec<String>,
 }
 
 // Dart → Rust：开始监听流量数据
@@ -103,6 +135,54 @@ pub struct IpcTrafficData {
     pub download: u64,
 }
 
+// Dart → Rust：开始监听核心内存占用
+#[derive(Deserialize, DartSignal)]
+pub struct StartMemoryStream;
+
+// Dart → Rust：停止监听核心内存占用
+#[derive(Deserialize, DartSignal)]
+pub struct StopMemoryStream;
+
+// Rust → Dart：核心内存占用（字节）
+#[derive(Serialize, RustSignal)]
+pub struct IpcMemoryData {
+    pub inuse: u64,
+    pub oslimit: u64, // 系统内存限制，0 表示未限制
+}
+
+// Dart → Rust：开始监听连接变化
+#[derive(Deserialize, DartSignal)]
+pub struct StartConnectionsStream;
+
+// Dart → Rust：停止监听连接变化
+#[derive(Deserialize, DartSignal)]
+pub struct StopConnectionsStream;
+
+// Rust → Dart：连接变化（仅包含与上一次快照的差异）
+#[derive(Serialize, RustSignal)]
+pub struct IpcConnectionsDiff {
+    pub added: Vec<ConnectionEntry>,
+    pub updated: Vec<ConnectionUpdate>,
+    pub closed: Vec<String>, // 已关闭的连接 ID
+    pub upload_total: u64,
+    pub download_total: u64,
+}
+
+// WebSocket 流连接状态
+#[derive(Serialize, Deserialize, SignalPiece, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StreamConnectionState {
+    Connected = 0,    // 已（重新）连接
+    Reconnecting = 1, // 连接断开，正在重连
+}
+
+// Rust → Dart：WebSocket 流连接状态变化（核心重启等导致断开时）
+#[derive(Serialize, RustSignal)]
+pub struct IpcStreamState {
+    pub endpoint: String, // 如 "/logs"、"/traffic"
+    pub state: StreamConnectionState,
+    pub attempt: u32, // 重连尝试次数
+}
+
 // Rust → Dart：流操作结果
 #[derive(Serialize, RustSignal)]
 pub struct StreamResult {
@@ -139,18 +219,176 @@ fn should_retry_on_error(error_msg: &str, attempt: usize, max_retries: usize) ->
 // - method: HTTP 方法名（"GET"/"POST"/"PUT"/"PATCH"/"DELETE"）
 // - path: 请求路径
 // - body: 请求体（Option<&str>）
-// - request_id: 请求 ID
+// - request_id: 请求 ID（用于取消）
+// - timeout_ms: 超时时间（毫秒）
 // - log_response: 是否记录响应体（仅 GET 请求）
 async fn handle_ipc_request_with_retry(
     method: &str,
     path: &str,
     body: Option<&str>,
     request_id: i64,
+    timeout_ms: Option<u32>,
     should_log_response: bool,
 ) {
+    let cancel_receiver = register_in_flight_request(request_id);
+    let outcome = run_cancellable(
+        ipc_request_with_retry(method, path, body, should_log_response),
+        cancel_receiver,
+        timeout_ms,
+    )
+    .await;
+    unregister_in_flight_request(request_id);
+
+    let failed = |error_message: String, is_cancelled: bool, is_timed_out: bool| IpcResponse {
+        request_id,
+        status_code: 0,
+        body: String::new(),
+        is_successful: false,
+        error_message: Some(error_message),
+        is_cancelled,
+        is_timed_out,
+    };
+
+    let response = match outcome {
+        RequestOutcome::Completed(Ok(response)) => IpcResponse {
+            request_id,
+            status_code: response.status_code,
+            body: response.body,
+            is_successful: true,
+            error_message: None,
+            is_cancelled: false,
+            is_timed_out: false,
+        },
+        RequestOutcome::Completed(Err(e)) => failed(e, false, false),
+        RequestOutcome::Cancelled => {
+            log::debug!("IPC {} 请求已取消：{}", method, path);
+            failed("请求已取消".to_string(), true, false)
+        }
+        RequestOutcome::TimedOut => {
+            log::warn!("IPC {} 请求超时：{}", method, path);
+            failed(
+                format!("请求超时（{}ms）", timeout_ms.unwrap_or_default()),
+                false,
+                true,
+            )
+        }
+    };
+
+    response.send_signal_to_dart();
+}
+
+// 可取消请求的结果
+enum RequestOutcome<T> {
+    Completed(T),
+    Cancelled,
+    TimedOut,
+}
+
+// 进行中请求的取消通道
+//
+// 请求与取消信号由不同的监听任务处理，取消可能先于请求注册到达，
+// 此时先记录下来，请求注册时直接视为已取消
+#[derive(Default)]
+struct InFlightRequests {
+    senders: HashMap<i64, oneshot::Sender<()>>, // 请求 ID → 取消信号发送端
+    early_cancels: VecDeque<i64>,
+}
+
+// 提前到达的取消最多保留的数量（对应请求已结束的取消不会被消费）
+const MAX_EARLY_CANCELS: usize = 64;
+
+static IN_FLIGHT_REQUESTS: Lazy<std::sync::Mutex<InFlightRequests>> =
+    Lazy::new(|| std::sync::Mutex::new(InFlightRequests::default()));
+
+fn register_in_flight_request(request_id: i64) -> oneshot::Receiver<()> {
+    let (sender, receiver) = oneshot::channel();
+    let mut requests = IN_FLIGHT_REQUESTS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+
+    if let Some(index) = requests
+        .early_cancels
+        .iter()
+        .position(|&id| id == request_id)
+    {
+        requests.early_cancels.remove(index);
+        log::debug!("IPC 请求在开始前已取消：{}", request_id);
+        let _ = sender.send(());
+    } else {
+        requests.senders.insert(request_id, sender);
+    }
+    receiver
+}
+
+// 仅移除已结束的请求（ID 重复时不影响后注册的请求）
+fn unregister_in_flight_request(request_id: i64) {
+    let mut requests = IN_FLIGHT_REQUESTS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    if requests
+        .senders
+        .get(&request_id)
+        .is_some_and(|sender| sender.is_closed())
+    {
+        requests.senders.remove(&request_id);
+    }
+}
+
+// 在取消或超时时中止请求
+//
+// 中止时请求 Future 被直接丢弃：正在使用的连接随之关闭而不会归还连接池，
+// 避免残留未读完的响应；持有的信号量许可同时释放
+async fn run_cancellable<F: Future>(
+    request: F,
+    cancel_receiver: oneshot::Receiver<()>,
+    timeout_ms: Option<u32>,
+) -> RequestOutcome<F::Output> {
+    let timeout = async {
+        match timeout_ms {
+            Some(ms) if ms > 0 => tokio::time::sleep(Duration::from_millis(ms.into())).await,
+            _ => std::future::pending().await,
+        }
+    };
+
+    // 优先检查取消，开始前已取消的请求不会被发送
+    tokio::select! {
+        biased;
+        Ok(()) = cancel_receiver => RequestOutcome::Cancelled,
+        output = request => RequestOutcome::Completed(output),
+        () = timeout => RequestOutcome::TimedOut,
+    }
+}
+
+// 通过连接池发送 IPC 请求，连接失效时清空连接池后重试
+async fn ipc_request_with_retry(
+    method: &str,
+    path: &str,
+    body: Option<&str>,
+    should_log_response: bool,
+) -> Result<HttpResponse, String> {
     const MAX_RETRIES: usize = 2;
 
-    for attempt in 0..=MAX_RETRIES {
+    // PUT 请求获取配置更新信号量，防止并发配置修改（等待期间同样可被取消）
+    let _permit = if method == "PUT" {
+        Some(
+            CONFIG_UPDATE_SEMAPHORE
+                .acquire()
+                .await
+                .map_err(|e| format!("获取配置更新信号量失败：{}", e))?,
+        )
+    } else {
+        None
+    };
+
+    // 远程 external-controller 走 HTTP(S)，不经过 IPC 连接池
+    if let Some(remote) = transport::remote_controller() {
+        return remote.request(method, path, body).await.inspect_err(|e| {
+            log::error!("远程 {} 请求失败：{}，error：{}", method, path, e);
+        });
+    }
+
+    let mut attempt = 0;
+    loop {
         // 从连接池获取连接
         let ipc_conn = match acquire_connection().await {
             Ok(c) => c,
@@ -162,15 +400,7 @@ async fn handle_ipc_request_with_retry(
                     log::error!("IPC {} 获取连接失败：{}，error：{}", method, path, e);
                 }
 
-                IpcResponse {
-                    request_id,
-                    status_code: 0,
-                    body: String::new(),
-                    is_successful: false,
-                    error_message: Some(format!("获取连接失败：{}", e)),
-                }
-                .send_signal_to_dart();
-                return;
+                return Err(format!("获取连接失败：{}", e));
             }
         };
 
@@ -194,15 +424,7 @@ async fn handle_ipc_request_with_retry(
                     }
                 }
 
-                IpcResponse {
-                    request_id,
-                    status_code: response.status_code,
-                    body: response.body,
-                    is_successful: true,
-                    error_message: None,
-                }
-                .send_signal_to_dart();
-                return;
+                return Ok(response);
             }
             Err(e) => {
                 // 连接已失效，不归还
@@ -223,6 +445,7 @@ async fn handle_ipc_request_with_retry(
 
                     // 等待 200ms 后重试
                     tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
+                    attempt += 1;
                     continue;
                 }
 
@@ -233,15 +456,7 @@ async fn handle_ipc_request_with_retry(
                     log::error!("IPC {} 请求失败：{}，error：{}", method, path, e);
                 }
 
-                IpcResponse {
-                    request_id,
-                    status_code: 0,
-                    body: String::new(),
-                    is_successful: false,
-                    error_message: Some(format!("IPC 请求失败：{}", e)),
-                }
-                .send_signal_to_dart();
-                return;
+                return Err(format!("IPC 请求失败：{}", e));
             }
         }
     }
@@ -456,10 +671,21 @@ static WS_CLIENT: Lazy<Arc<RwLock<Option<WebSocketClient>>>> =
 static TRAFFIC_CONNECTION_ID: Lazy<Arc<RwLock<Option<u32>>>> =
     Lazy::new(|| Arc::new(RwLock::new(None)));
 
+// 核心支持的日志级别
+const LOG_LEVELS: &[&str] = &["debug", "info", "warning", "error", "silent"];
+
 // 存储当前的日志监控连接 ID
 static LOG_CONNECTION_ID: Lazy<Arc<RwLock<Option<u32>>>> =
     Lazy::new(|| Arc::new(RwLock::new(None)));
 
+// 存储当前的内存监控连接 ID
+static MEMORY_CONNECTION_ID: Lazy<Arc<RwLock<Option<u32>>>> =
+    Lazy::new(|| Arc::new(RwLock::new(None)));
+
+// 存储当前的连接监控连接 ID
+static CONNECTIONS_CONNECTION_ID: Lazy<Arc<RwLock<Option<u32>>>> =
+    Lazy::new(|| Arc::new(RwLock::new(None)));
+
 // 确保 WebSocket 客户端已初始化（统一入口）
 async fn ensure_ws_client_initialized() {
     let mut client_guard = WS_CLIENT.write().await;
@@ -470,6 +696,58 @@ async fn ensure_ws_client_initialized() {
     }
 }
 
+// 断开并清除指定流的连接
+async fn disconnect_stream(connection_id: &RwLock<Option<u32>>) {
+    let Some(id) = connection_id.write().await.take() else {
+        return;
+    };
+    let client = WS_CLIENT.read().await;
+    if let Some(ws_client) = client.as_ref() {
+        ws_client.disconnect(id).await;
+    }
+}
+
+// 断开推送给 Dart 的所有流（在 Clash 停止时调用）
+//
+// 流在连接断开后会持续重连，核心被主动停止时需要结束重连；
+// 核心再次启动后由 Dart 重新发送对应的 Start 信号。
+// subscribe_ws 建立的后台订阅（如流量统计）由调用方自行管理，不在此断开
+pub async fn cleanup_ws_streams() {
+    for connection_id in [
+        &TRAFFIC_CONNECTION_ID,
+        &LOG_CONNECTION_ID,
+        &MEMORY_CONNECTION_ID,
+        &CONNECTIONS_CONNECTION_ID,
+    ] {
+        disconnect_stream(connection_id).await;
+    }
+}
+
+// 订阅本机核心 WebSocket 端点（供流量统计等后台任务使用），返回连接 ID
+//
+// 与 Start*Stream 不同，订阅结果不会发送给 Dart，由调用方自行管理连接 ID；
+// 切换到远程控制器后仍连接本机核心，避免远程流量计入本机统计
+pub async fn subscribe_ws<F>(endpoint: &str, on_message: F) -> Result<u32, String>
+where
+    F: Fn(serde_json::Value) + Send + 'static,
+{
+    ensure_ws_client_initialized().await;
+
+    let client = WS_CLIENT.read().await;
+    match client.as_ref() {
+        Some(ws_client) => ws_client.connect_local(endpoint, on_message).await,
+        None => Err("WebSocket 客户端未初始化".to_string()),
+    }
+}
+
+// 取消 subscribe_ws 建立的订阅
+pub async fn unsubscribe_ws(connection_id: u32) {
+    let client = WS_CLIENT.read().await;
+    if let Some(ws_client) = client.as_ref() {
+        ws_client.disconnect(connection_id).await;
+    }
+}
+
 // 清理 IPC 连接池（在 Clash 停止时调用）
 pub async fn cleanup_ipc_connection_pool() {
     let mut pool = IPC_CONNECTION_POOL.write().await;
@@ -480,21 +758,14 @@ pub async fn cleanup_ipc_connection_pool() {
     }
 }
 
-// 清理 WebSocket 客户端（在 Clash 停止时调用）
-pub async fn cleanup_ws_client() {
-    let mut client_guard = WS_CLIENT.write().await;
-    if let Some(ws_client) = client_guard.take() {
-        ws_client.disconnect_all().await;
-        log::info!("WebSocket 客户端已清理");
-    }
-}
-
 // 清理所有网络资源（在 Clash 停止时调用的统一入口）
+//
+// 核心意外退出（未经此入口）时流保持重连，核心恢复后自动继续推送
 pub async fn cleanup_all_network_resources() {
     log::info!("开始清理所有网络资源");
 
-    // 1. 清理 WebSocket 连接
-    cleanup_ws_client().await;
+    // 1. 断开推送给 Dart 的 WebSocket 流
+    cleanup_ws_streams().await;
 
     // 2. 清理 IPC 连接池
     cleanup_ipc_connection_pool().await;
@@ -506,7 +777,15 @@ pub async fn cleanup_all_network_resources() {
 impl IpcGetRequest {
     pub fn handle(self) {
         tokio::spawn(async move {
-            handle_ipc_request_with_retry("GET", &self.path, None, self.request_id, true).await;
+            handle_ipc_request_with_retry(
+                "GET",
+                &self.path,
+                None,
+                self.request_id,
+                self.timeout_ms,
+                true,
+            )
+            .await;
         });
     }
 }
@@ -520,6 +799,7 @@ impl IpcPostRequest {
                 &self.path,
                 self.body.as_deref(),
                 self.request_id,
+                self.timeout_ms,
                 false,
             )
             .await;
@@ -531,28 +811,12 @@ impl IpcPostRequest {
 impl IpcPutRequest {
     pub fn handle(self) {
         tokio::spawn(async move {
-            // 获取配置更新信号量，防止并发配置修改
-            let _permit = match CONFIG_UPDATE_SEMAPHORE.acquire().await {
-                Ok(permit) => permit,
-                Err(e) => {
-                    log::error!("获取配置更新信号量失败：{}", e);
-                    IpcResponse {
-                        request_id: self.request_id,
-                        status_code: 0,
-                        body: String::new(),
-                        is_successful: false,
-                        error_message: Some(format!("获取配置更新信号量失败：{}", e)),
-                    }
-                    .send_signal_to_dart();
-                    return;
-                }
-            };
-
             handle_ipc_request_with_retry(
                 "PUT",
                 &self.path,
                 self.body.as_deref(),
                 self.request_id,
+                self.timeout_ms,
                 false,
             )
             .await;
@@ -569,6 +833,7 @@ impl IpcPatchRequest {
                 &self.path,
                 self.body.as_deref(),
                 self.request_id,
+                self.timeout_ms,
                 false,
             )
             .await;
@@ -580,11 +845,43 @@ impl IpcPatchRequest {
 impl IpcDeleteRequest {
     pub fn handle(self) {
         tokio::spawn(async move {
-            handle_ipc_request_with_retry("DELETE", &self.path, None, self.request_id, false).await;
+            handle_ipc_request_with_retry(
+                "DELETE",
+                &self.path,
+                None,
+                self.request_id,
+                self.timeout_ms,
+                false,
+            )
+            .await;
         });
     }
 }
 
+// 取消请求处理器
+impl IpcCancelRequest {
+    pub fn handle(self) {
+        let mut requests = IN_FLIGHT_REQUESTS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        match requests.senders.remove(&self.request_id) {
+            Some(sender) => {
+                let _ = sender.send(());
+                log::debug!("已取消 IPC 请求：{}", self.request_id);
+            }
+            None => {
+                // 请求可能尚未注册，也可能已经结束
+                if requests.early_cancels.len() >= MAX_EARLY_CANCELS {
+                    requests.early_cancels.pop_front();
+                }
+                requests.early_cancels.push_back(self.request_id);
+                log::debug!("IPC 请求未在进行中，记录取消：{}", self.request_id);
+            }
+        }
+    }
+}
+
 // 初始化 IPC REST API 消息监听器
 pub fn init_rest_api_listeners() {
     log::info!("初始化 IPC REST API 监听器");
@@ -627,6 +924,13 @@ pub fn init_rest_api_listeners() {
         }
     });
 
+    tokio::spawn(async {
+        let receiver = IpcCancelRequest::get_dart_signal_receiver();
+        while let Some(dart_signal) = receiver.recv().await {
+            dart_signal.message.handle();
+        }
+    });
+
     // WebSocket 流式数据监听器
     tokio::spawn(async {
         let receiver = StartTrafficStream::get_dart_signal_receiver();
@@ -644,8 +948,8 @@ pub fn init_rest_api_listeners() {
 
     tokio::spawn(async {
         let receiver = StartLogStream::get_dart_signal_receiver();
-        while let Some(_dart_signal) = receiver.recv().await {
-            StartLogStream::handle_start().await;
+        while let Some(dart_signal) = receiver.recv().await {
+            dart_signal.message.handle_start().await;
         }
     });
 
@@ -655,6 +959,34 @@ pub fn init_rest_api_listeners() {
             StopLogStream::handle_stop().await;
         }
     });
+
+    tokio::spawn(async {
+        let receiver = StartMemoryStream::get_dart_signal_receiver();
+        while let Some(_dart_signal) = receiver.recv().await {
+            StartMemoryStream::handle_start().await;
+        }
+    });
+
+    tokio::spawn(async {
+        let receiver = StopMemoryStream::get_dart_signal_receiver();
+        while let Some(_dart_signal) = receiver.recv().await {
+            StopMemoryStream::handle_stop().await;
+        }
+    });
+
+    tokio::spawn(async {
+        let receiver = StartConnectionsStream::get_dart_signal_receiver();
+        while let Some(_dart_signal) = receiver.recv().await {
+            StartConnectionsStream::handle_start().await;
+        }
+    });
+
+    tokio::spawn(async {
+        let receiver = StopConnectionsStream::get_dart_signal_receiver();
+        while let Some(_dart_signal) = receiver.recv().await {
+            StopConnectionsStream::handle_stop().await;
+        }
+    });
 }
 
 // WebSocket 流式数据处理器
@@ -663,6 +995,9 @@ impl StartTrafficStream {
     async fn handle_start() {
         log::info!("开始监听流量数据");
 
+        // 重复开始时先断开旧连接，避免两个流同时推送
+        disconnect_stream(&TRAFFIC_CONNECTION_ID).await;
+
         // 确保 WebSocket 客户端已初始化
         ensure_ws_client_initialized().await;
 
@@ -734,17 +1069,29 @@ impl StopTrafficStream {
 }
 
 impl StartLogStream {
-    async fn handle_start() {
-        log::info!("开始监听日志数据");
+    async fn handle_start(self) {
+        let level = if self.level.is_empty() {
+            "info".to_string()
+        } else if LOG_LEVELS.contains(&self.level.as_str()) {
+            self.level
+        } else {
+            log::warn!("未知的日志级别：{}，使用 info", self.level);
+            "info".to_string()
+        };
+        log::info!("开始监听日志数据（级别：{}）", level);
+
+        // 重复开始时先断开旧连接，使新的级别和关键字生效
+        disconnect_stream(&LOG_CONNECTION_ID).await;
 
         // 确保 WebSocket 客户端已初始化
         ensure_ws_client_initialized().await;
 
         // 建立 WebSocket 连接
+        let keywords = self.keywords;
         let client = WS_CLIENT.read().await;
         if let Some(ws_client) = client.as_ref() {
             match ws_client
-                .connect("/logs?level=info", |json_value| {
+                .connect(&format!("/logs?level={}", level), move |json_value| {
                     // 解析日志数据
                     if let Some(obj) = json_value.as_object() {
                         let log_type = obj
@@ -758,8 +1105,26 @@ impl StartLogStream {
                             .unwrap_or("")
                             .to_string();
 
+                        if !log_parser::matches_keywords(&payload, &keywords) {
+                            return;
+                        }
+
+                        let parsed = log_parser::parse_log_payload(&payload);
+
                         // 发送到 Dart 层
-                        IpcLogData { log_type, payload }.send_signal_to_dart();
+                        IpcLogData {
+                            log_type,
+                            payload,
+                            timestamp: chrono::Local::now().to_rfc3339(),
+                            network: parsed.network,
+                            source: parsed.source,
+                            process: parsed.process,
+                            destination: parsed.destination,
+                            rule: parsed.rule,
+                            rule_payload: parsed.rule_payload,
+                            proxy_chain: parsed.proxy_chain,
+                        }
+                        .send_signal_to_dart();
                     }
                 })
                 .await
@@ -815,25 +1180,336 @@ impl StopLogStream {
     }
 }
 
-// 公开的 IPC GET 请求接口（供 Rust 内部模块使用）
+impl StartMemoryStream {
+    async fn handle_start() {
+        log::info!("开始监听内存数据");
+
+        // 重复开始时先断开旧连接，避免两个流同时推送
+        disconnect_stream(&MEMORY_CONNECTION_ID).await;
+
+        // 确保 WebSocket 客户端已初始化
+        ensure_ws_client_initialized().await;
+
+        // 建立 WebSocket 连接
+        let client = WS_CLIENT.read().await;
+        if let Some(ws_client) = client.as_ref() {
+            match ws_client
+                .connect("/memory", |json_value| {
+                    // 解析内存数据
+                    if let Some(obj) = json_value.as_object() {
+                        let inuse = obj.get("inuse").and_then(|v| v.as_u64()).unwrap_or(0);
+                        let oslimit = obj.get("oslimit").and_then(|v| v.as_u64()).unwrap_or(0);
+
+                        // 核心建立连接后首条消息固定为 0，跳过
+                        if inuse == 0 {
+                            return;
+                        }
+
+                        // 发送到 Dart 层
+                        IpcMemoryData { inuse, oslimit }.send_signal_to_dart();
+                    }
+                })
+                .await
+            {
+                Ok(connection_id) => {
+                    log::info!("内存监控 WebSocket 连接已建立：{}", connection_id);
+
+                    // 保存连接 ID
+                    let mut id_guard = MEMORY_CONNECTION_ID.write().await;
+                    *id_guard = Some(connection_id);
+
+                    StreamResult {
+                        is_successful: true,
+                        error_message: None,
+                    }
+                    .send_signal_to_dart();
+                }
+                Err(e) => {
+                    log::error!("内存监控 WebSocket 连接失败：{}", e);
+                    StreamResult {
+                        is_successful: false,
+                        error_message: Some(e),
+                    }
+                    .send_signal_to_dart();
+                }
+            }
+        }
+    }
+}
+
+impl StopMemoryStream {
+    async fn handle_stop() {
+        log::info!("停止监听内存数据");
+
+        // 获取并清除连接 ID
+        let connection_id = {
+            let mut id_guard = MEMORY_CONNECTION_ID.write().await;
+            id_guard.take()
+        };
+
+        if let Some(id) = connection_id {
+            let client = WS_CLIENT.read().await;
+            if let Some(ws_client) = client.as_ref() {
+                ws_client.disconnect(id).await;
+            }
+        }
+
+        StreamResult {
+            is_successful: true,
+            error_message: None,
+        }
+        .send_signal_to_dart();
+    }
+}
+
+impl StartConnectionsStream {
+    async fn handle_start() {
+        log::info!("开始监听连接变化");
+
+        // 重复开始时先断开旧连接，避免两个流同时推送
+        disconnect_stream(&CONNECTIONS_CONNECTION_ID).await;
+
+        // 确保 WebSocket 客户端已初始化
+        ensure_ws_client_initialized().await;
+
+        // 每个流维护独立的快照状态，首个快照中的连接全部视为新增
+        let tracker = std::sync::Mutex::new(ConnectionTracker::new());
+
+        // 建立 WebSocket 连接
+        let client = WS_CLIENT.read().await;
+        if let Some(ws_client) = client.as_ref() {
+            match ws_client
+                .connect("/connections", move |json_value| {
+                    let snapshot: ConnectionsSnapshot = match serde_json::from_value(json_value) {
+                        Ok(snapshot) => snapshot,
+                        Err(e) => {
+                            log::error!("连接快照解析失败：{}", e);
+                            return;
+                        }
+                    };
+
+                    let diff = match tracker.lock() {
+                        Ok(mut tracker) => tracker.update(snapshot, Instant::now()),
+                        Err(e) => {
+                            log::error!("连接快照状态不可用：{}", e);
+                            return;
+                        }
+                    };
+
+                    if diff.is_empty() {
+                        return;
+                    }
+
+                    // 发送到 Dart 层
+                    IpcConnectionsDiff {
+                        added: diff.added,
+                        updated: diff.updated,
+                        closed: diff.closed,
+                        upload_total: diff.upload_total,
+                        download_total: diff.download_total,
+                    }
+                    .send_signal_to_dart();
+                })
+                .await
+            {
+                Ok(connection_id) => {
+                    log::info!("连接监控 WebSocket 连接已建立：{}", connection_id);
+
+                    // 保存连接 ID
+                    let mut id_guard = CONNECTIONS_CONNECTION_ID.write().await;
+                    *id_guard = Some(connection_id);
+
+                    StreamResult {
+                        is_successful: true,
+                        error_message: None,
+                    }
+                    .send_signal_to_dart();
+                }
+                Err(e) => {
+                    log::error!("连接监控 WebSocket 连接失败：{}", e);
+                    StreamResult {
+                        is_successful: false,
+                        error_message: Some(e),
+                    }
+                    .send_signal_to_dart();
+                }
+            }
+        }
+    }
+}
+
+impl StopConnectionsStream {
+    async fn handle_stop() {
+        log::info!("停止监听连接变化");
+
+        // 获取并清除连接 ID
+        let connection_id = {
+            let mut id_guard = CONNECTIONS_CONNECTION_ID.write().await;
+            id_guard.take()
+        };
+
+        if let Some(id) = connection_id {
+            let client = WS_CLIENT.read().await;
+            if let Some(ws_client) = client.as_ref() {
+                ws_client.disconnect(id).await;
+            }
+        }
+
+        StreamResult {
+            is_successful: true,
+            error_message: None,
+        }
+        .send_signal_to_dart();
+    }
+}
+
+// 使用连接池发送 IPC 请求，返回原始 HTTP 响应（供 Rust 内部模块使用）
 //
-// 用于批量延迟测试等场景，直接使用连接池发送 IPC GET 请求
-pub async fn internal_ipc_get(path: &str) -> Result<String, String> {
+// 已切换到远程控制器时改为通过 HTTP(S) 发送；PUT 请求与 Dart 发起的 PUT 共用配置更新信号量，避免并发修改配置
+pub async fn internal_ipc_send(
+    method: &str,
+    path: &str,
+    body: Option<&str>,
+) -> Result<HttpResponse, String> {
+    let _permit = if method == "PUT" {
+        Some(
+            CONFIG_UPDATE_SEMAPHORE
+                .acquire()
+                .await
+                .map_err(|e| format!("获取配置更新信号量失败：{}", e))?,
+        )
+    } else {
+        None
+    };
+
+    if let Some(remote) = transport::remote_controller() {
+        return remote.request(method, path, body).await;
+    }
+
     // 从连接池获取连接
     let ipc_conn = acquire_connection().await?;
 
-    // 使用连接发送请求
-    match IpcClient::request_with_connection("GET", path, None, ipc_conn).await {
-        Ok((response, ipc_conn)) => {
-            // 归还连接
-            release_connection(ipc_conn).await;
+    // 使用连接发送请求，失败的连接不归还
+    let (response, ipc_conn) =
+        IpcClient::request_with_connection(method, path, body, ipc_conn).await?;
+    release_connection(ipc_conn).await;
 
-            if response.status_code >= 200 && response.status_code < 300 {
-                Ok(response.body)
-            } else {
-                Err(format!("HTTP {}", response.status_code))
-            }
+    Ok(response)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::super::mock_core;
+    use super::*;
+
+    #[tokio::test]
+    async fn test_connection_pool_reuse() {
+        let _guard = mock_core::exclusive().await;
+        let mock = mock_core::shared();
+
+        for _ in 0..3 {
+            let response = internal_ipc_send("GET", "/version", None)
+                .await
+                .unwrap_or_else(|e| panic!("{}", e));
+            assert_eq!(response.status_code, 200);
         }
-        Err(e) => Err(e),
+        assert_eq!(mock.accepted_connections(), 1);
+
+        // 并发请求时池中只有一个连接，其余请求新建连接，完成后全部归还
+        let send = || internal_ipc_send("GET", "/configs", None);
+        let results = tokio::join!(send(), send(), send(), send());
+        assert!(results.0.is_ok() && results.1.is_ok() && results.2.is_ok() && results.3.is_ok());
+        assert_eq!(mock.accepted_connections(), 4);
+
+        let results = tokio::join!(send(), send(), send(), send());
+        assert!(results.0.is_ok() && results.1.is_ok() && results.2.is_ok() && results.3.is_ok());
+        assert_eq!(mock.accepted_connections(), 4);
+    }
+
+    // 对端关闭时未读取的数据会使 Linux 返回 ECONNRESET，其他平台可能只返回 EOF
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_request_retry_on_reset() {
+        let _guard = mock_core::exclusive().await;
+        let mock = mock_core::shared();
+
+        mock.reset_next_connections(1);
+        let response = ipc_request_with_retry("GET", "/version", None, false)
+            .await
+            .unwrap_or_else(|e| panic!("{}", e));
+        assert_eq!(response.status_code, 200);
+        assert_eq!(mock.accepted_connections(), 2);
+        assert_eq!(mock.requests(), vec!["GET /version"]);
+
+        // 池中连接失效（首次尝试复用旧连接）且超过重试次数后返回错误
+        mock.reset_next_connections(3);
+        let Err(e) = ipc_request_with_retry("GET", "/version", None, false).await else {
+            panic!("连续重置时请求不应成功");
+        };
+        assert!(e.starts_with("IPC 请求失败"));
+        assert_eq!(mock.accepted_connections(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_request_cancel_and_timeout() {
+        let _guard = mock_core::exclusive().await;
+        let mock = mock_core::shared();
+        mock.set_response_latency(Duration::from_millis(300));
+
+        let request = ipc_request_with_retry("GET", "/version", None, false);
+        let outcome = run_cancellable(request, oneshot::channel().1, Some(50)).await;
+        assert!(matches!(outcome, RequestOutcome::TimedOut));
+
+        let cancel_receiver = register_in_flight_request(7);
+        let request = ipc_request_with_retry("GET", "/version", None, false);
+        let (outcome, ()) = tokio::join!(run_cancellable(request, cancel_receiver, None), async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            IpcCancelRequest { request_id: 7 }.handle();
+        });
+        assert!(matches!(outcome, RequestOutcome::Cancelled));
+        unregister_in_flight_request(7);
+
+        // 取消先于请求注册到达时，请求不会被发送
+        IpcCancelRequest { request_id: 8 }.handle();
+        let cancel_receiver = register_in_flight_request(8);
+        let request = ipc_request_with_retry("GET", "/version", None, false);
+        let outcome = run_cancellable(request, cancel_receiver, None).await;
+        assert!(matches!(outcome, RequestOutcome::Cancelled));
+        unregister_in_flight_request(8);
+
+        // 中止的连接未归还连接池，后续请求使用新连接且不会读到残留的响应
+        mock.set_response_latency(Duration::ZERO);
+        let response = ipc_request_with_retry("GET", "/version", None, false)
+            .await
+            .unwrap_or_else(|e| panic!("{}", e));
+        assert!(response.body.contains("v1.19.0-mock"));
+        assert_eq!(mock.accepted_connections(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_repeated_stream_start() {
+        let _guard = mock_core::exclusive().await;
+        let mock = mock_core::shared();
+
+        // 重复开始只保留一个连接
+        StartTrafficStream::handle_start().await;
+        StartTrafficStream::handle_start().await;
+        StartMemoryStream::handle_start().await;
+        StartMemoryStream::handle_start().await;
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert_eq!(mock.active_websockets("/traffic"), 1);
+        assert_eq!(mock.active_websockets("/memory"), 1);
+
+        // 停止后不再推送，主动停止核心时其余流也不再重连
+        StopTrafficStream::handle_stop().await;
+        cleanup_all_network_resources().await;
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert_eq!(mock.active_websockets("/traffic"), 0);
+        assert_eq!(mock.active_websockets("/memory"), 0);
+
+        let accepted = mock.accepted_connections();
+        mock.close_websockets();
+        tokio::time::sleep(Duration::from_millis(800)).await;
+        assert_eq!(mock.accepted_connections(), accepted);
     }
 }
@@ -4,6 +4,8 @@
 // 使用 Tokio 原生实现 + 手动 HTTP 协议解析
 
 use super::connection;
+use once_cell::sync::Lazy;
+use std::sync::{PoisonError, RwLock};
 use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
 
 #[cfg(unix)]
@@ -18,6 +20,9 @@ pub struct HttpResponse {
     pub body: String,
 }
 
+// IPC 路径覆盖，未设置时使用平台默认路径
+static IPC_PATH_OVERRIDE: Lazy<RwLock<Option<String>>> = Lazy::new(|| RwLock::new(None));
+
 // IPC 客户端
 pub struct IpcClient;
 
@@ -25,6 +30,14 @@ impl IpcClient {
     // 获取默认 IPC 路径
     // Debug/Profile 模式使用 _dev 后缀，避免与 Release 模式冲突
     pub fn default_ipc_path() -> String {
+        if let Some(path) = IPC_PATH_OVERRIDE
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .as_ref()
+        {
+            return path.clone();
+        }
+
         #[cfg(windows)]
         {
             #[cfg(debug_assertions)]
@@ -50,6 +63,14 @@ impl IpcClient {
         }
     }
 
+    // 覆盖默认 IPC 路径（测试时指向模拟核心）
+    #[cfg(test)]
+    pub fn set_ipc_path(path: impl Into<String>) {
+        *IPC_PATH_OVERRIDE
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = Some(path.into());
+    }
+
     // 使用已有连接发送请求（连接池场景）
     #[cfg(windows)]
     pub async fn request_with_connection(
@@ -232,3 +253,40 @@ impl IpcClient {
         String::from_utf8(body).map_err(|e| format!("解码 chunked body 失败：{}", e))
     }
 }
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::super::mock_core::MockCore;
+    use super::*;
+
+    #[tokio::test]
+    async fn test_chunked_response() {
+        let mock = MockCore::start();
+        mock.set_proxy_delay("香港 01", Some(10));
+        mock.set_proxy_delay("日本 01", None);
+
+        let stream = connection::connect_unix_socket(mock.ipc_path())
+            .await
+            .unwrap_or_else(|e| panic!("{}", e));
+        let (plain, stream) = IpcClient::request_with_connection("GET", "/proxies", None, stream)
+            .await
+            .unwrap_or_else(|e| panic!("{}", e));
+
+        // chunk 边界会切开多字节字符，解码需在拼接完成后进行
+        mock.set_chunk_size(Some(5));
+        let (chunked, stream) = IpcClient::request_with_connection("GET", "/proxies", None, stream)
+            .await
+            .unwrap_or_else(|e| panic!("{}", e));
+        assert_eq!(chunked.status_code, 200);
+        assert_eq!(chunked.body, plain.body);
+        assert!(chunked.body.contains("香港 01"));
+
+        // 同一连接上继续读取下一个响应，说明 chunked 结束标记已完整消费
+        let (missing, _) =
+            IpcClient::request_with_connection("GET", "/proxies/missing", None, stream)
+                .await
+                .unwrap_or_else(|e| panic!("{}", e));
+        assert_eq!(missing.status_code, 404);
+        assert_eq!(mock.accepted_connections(), 1);
+    }
+}
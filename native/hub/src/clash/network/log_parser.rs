@@ -0,0 +1,131 @@
+// 核心日志解析
+//
+// 目的：将核心的连接日志解析为结构化字段，并在 Rust 侧完成关键字过滤，
+// 避免 Dart 日志页面对每一行执行正则匹配
+//
+// 连接日志格式示例：
+// - [TCP] 127.0.0.1:52000(chrome) --> example.com:443 match DomainSuffix(example.com) using 节点选择[香港 01]
+// - [UDP] 127.0.0.1:52001 --> 8.8.8.8:53 doesn't match any rule using DIRECT
+// - [TCP] 127.0.0.1:52002 --> example.com:443 using GLOBAL
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+// 注意：此正则表达式是硬编码的字面量，编译时已验证正确性
+#[allow(clippy::expect_used)]
+static CONNECTION_LOG_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^\[(?P<network>TCP|UDP)\] (?P<source>[^\s(]+)(?:\((?P<process>[^)]*)\))? --> (?P<destination>\S+) (?:match (?P<rule>[^\s(]+)(?:\((?P<payload>.*?)\))? using |doesn't match any rule using |using )(?P<chain>.+)$",
+    )
+    .expect("正则表达式编译失败：这是编译时错误，不应该在运行时发生")
+});
+
+// 解析后的连接日志字段（非连接日志时全部为空）
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ParsedLog {
+    pub network: String, // "TCP" | "UDP"
+    pub source: String,
+    pub process: String,
+    pub destination: String,
+    pub rule: String, // 未匹配任何规则时为空
+    pub rule_payload: String,
+    pub proxy_chain: Vec<String>, // 如 ["节点选择", "香港 01"]
+}
+
+pub fn parse_log_payload(payload: &str) -> ParsedLog {
+    let Some(caps) = CONNECTION_LOG_RE.captures(payload.trim()) else {
+        return ParsedLog::default();
+    };
+    let field = |name: &str| {
+        caps.name(name)
+            .map(|m| m.as_str().to_string())
+            .unwrap_or_default()
+    };
+
+    ParsedLog {
+        network: field("network"),
+        source: field("source"),
+        process: field("process"),
+        destination: field("destination"),
+        rule: field("rule"),
+        rule_payload: field("payload"),
+        proxy_chain: parse_proxy_chain(&field("chain")),
+    }
+}
+
+// 拆分 "代理组[节点]" 形式的代理链
+fn parse_proxy_chain(chain: &str) -> Vec<String> {
+    match chain.strip_suffix(']').and_then(|c| c.split_once('[')) {
+        Some((group, node)) => vec![group.to_string(), node.to_string()],
+        None => vec![chain.to_string()],
+    }
+}
+
+// 关键字过滤：未设置关键字（或均为空白）时全部通过，否则包含任一关键字（不区分大小写）即通过
+pub fn matches_keywords(payload: &str, keywords: &[String]) -> bool {
+    let keywords: Vec<String> = keywords
+        .iter()
+        .map(|k| k.trim())
+        .filter(|k| !k.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    if keywords.is_empty() {
+        return true;
+    }
+
+    let payload = payload.to_lowercase();
+    keywords.iter().any(|k| payload.contains(k))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_connection_logs() {
+        let parsed = parse_log_payload(
+            "[TCP] 127.0.0.1:52000(chrome) --> example.com:443 match DomainSuffix(example.com) using 节点选择[香港 01]",
+        );
+        assert_eq!(parsed.network, "TCP");
+        assert_eq!(parsed.source, "127.0.0.1:52000");
+        assert_eq!(parsed.process, "chrome");
+        assert_eq!(parsed.destination, "example.com:443");
+        assert_eq!(parsed.rule, "DomainSuffix");
+        assert_eq!(parsed.rule_payload, "example.com");
+        assert_eq!(parsed.proxy_chain, vec!["节点选择", "香港 01"]);
+
+        let parsed = parse_log_payload(
+            "[UDP] 127.0.0.1:52001 --> 8.8.8.8:53 doesn't match any rule using DIRECT",
+        );
+        assert_eq!(parsed.network, "UDP");
+        assert!(parsed.rule.is_empty());
+        assert_eq!(parsed.proxy_chain, vec!["DIRECT"]);
+
+        let parsed = parse_log_payload("[TCP] 127.0.0.1:52002 --> example.com:443 using GLOBAL");
+        assert_eq!(parsed.destination, "example.com:443");
+        assert_eq!(parsed.proxy_chain, vec!["GLOBAL"]);
+
+        assert_eq!(
+            parse_log_payload("Start initial configuration in progress"),
+            ParsedLog::default()
+        );
+    }
+
+    #[test]
+    fn test_keyword_filter() {
+        let keywords = vec!["Example.COM".to_string()];
+        assert!(matches_keywords(
+            "[TCP] a --> example.com:443 using DIRECT",
+            &keywords
+        ));
+        assert!(!matches_keywords(
+            "[TCP] a --> other.org:443 using DIRECT",
+            &keywords
+        ));
+        assert!(matches_keywords("anything", &[]));
+
+        // 搜索框为空时 Dart 可能发送空字符串，不应过滤掉所有日志
+        assert!(matches_keywords("anything", &[String::new()]));
+        assert!(matches_keywords("anything", &[" ".to_string()]));
+    }
+}
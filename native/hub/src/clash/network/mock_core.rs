@@ -0,0 +1,647 @@
+// 模拟 mihomo 核心（仅测试）
+//
+// 目的：在 Unix Socket 上实现 hub 使用的 mihomo REST/WebSocket API 子集，
+// 无需真实核心即可测试 IPC 客户端、连接池、WebSocket 客户端和延迟测试
+// （同时监听本机 TCP 端口，模拟远程 external-controller）
+//
+// - REST：/version、/proxies、/proxies/{name}、/proxies/{name}/delay、/configs、/connections
+// - WebSocket：/logs、/traffic、/memory、/connections（每 50ms 推送一次）
+// - 可脚本化：节点延迟、响应延迟、指定路径失败、重置连接、chunked 响应、断开 WebSocket
+//
+// 每个实例在独立线程的运行时中运行，不受测试运行时结束的影响
+
+#![allow(dead_code)] // 脚本化接口供不同模块的测试按需使用
+
+use futures_util::SinkExt;
+use once_cell::sync::Lazy;
+use serde_json::{Map, Value, json};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpListener, TcpStream, UnixListener, UnixStream};
+use tokio::sync::oneshot;
+use tokio_tungstenite::WebSocketStream;
+use tokio_tungstenite::tungstenite::handshake::derive_accept_key;
+use tokio_tungstenite::tungstenite::protocol::{Message, Role};
+
+const WS_ENDPOINTS: &[&str] = &["/logs", "/traffic", "/memory", "/connections"];
+const WS_PUSH_INTERVAL: Duration = Duration::from_millis(50);
+const DEFAULT_DELAY_TIMEOUT_MS: u64 = 5000;
+
+// 模拟核心的可变状态
+struct MockState {
+    proxy_delays: BTreeMap<String, Option<u32>>, // 节点 → 延迟，None 表示超时
+    selected: String,                            // GLOBAL 当前选中的节点
+    config: Map<String, Value>,
+    connections: BTreeMap<String, Value>,
+    response_latency: Duration,
+    failures: BTreeMap<String, u16>, // 路径 → 返回的状态码
+    resets_remaining: usize,
+    chunk_size: Option<usize>,
+    accepted_connections: usize,
+    requests: Vec<String>, // 如 "GET /version"
+    ws_generation: u64,
+    active_websockets: BTreeMap<String, usize>, // 端点 → 当前连接数
+}
+
+impl MockState {
+    fn new() -> Self {
+        let config = json!({
+            "mixed-port": 7890,
+            "mode": "rule",
+            "log-level": "info",
+            "allow-lan": false,
+            "ipv6": false,
+        });
+
+        Self {
+            proxy_delays: BTreeMap::new(),
+            selected: "DIRECT".to_string(),
+            config: config.as_object().cloned().unwrap_or_default(),
+            connections: BTreeMap::new(),
+            response_latency: Duration::ZERO,
+            failures: BTreeMap::new(),
+            resets_remaining: 0,
+            chunk_size: None,
+            accepted_connections: 0,
+            requests: Vec::new(),
+            ws_generation: 0,
+            active_websockets: BTreeMap::new(),
+        }
+    }
+}
+
+fn lock(state: &Mutex<MockState>) -> MutexGuard<'_, MockState> {
+    state.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+pub struct MockCore {
+    ipc_path: String,
+    http_port: u16,
+    state: Arc<Mutex<MockState>>,
+    shutdown: Mutex<Option<oneshot::Sender<()>>>,
+}
+
+impl MockCore {
+    // 在临时目录的新 Socket 上启动模拟核心
+    pub fn start() -> Self {
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+        let ipc_path = std::env::temp_dir()
+            .join(format!(
+                "stelliberty_mock_{}_{}.sock",
+                std::process::id(),
+                NEXT_ID.fetch_add(1, Ordering::Relaxed)
+            ))
+            .to_string_lossy()
+            .into_owned();
+        let _ = std::fs::remove_file(&ipc_path);
+
+        // 在当前线程同步绑定，返回后即可连接
+        let listener = std::os::unix::net::UnixListener::bind(&ipc_path)
+            .and_then(|listener| listener.set_nonblocking(true).map(|()| listener))
+            .unwrap_or_else(|e| panic!("模拟核心监听失败：{}", e));
+        let tcp_listener = std::net::TcpListener::bind(("127.0.0.1", 0))
+            .and_then(|listener| listener.set_nonblocking(true).map(|()| listener))
+            .unwrap_or_else(|e| panic!("模拟核心监听失败：{}", e));
+        let http_port = tcp_listener
+            .local_addr()
+            .unwrap_or_else(|e| panic!("模拟核心监听失败：{}", e))
+            .port();
+
+        let state = Arc::new(Mutex::new(MockState::new()));
+        let (shutdown_tx, shutdown_rx) = oneshot::channel();
+        let server_state = Arc::clone(&state);
+        std::thread::spawn(move || {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap_or_else(|e| panic!("模拟核心运行时创建失败：{}", e));
+            runtime.block_on(async move {
+                let listener = UnixListener::from_std(listener)
+                    .unwrap_or_else(|e| panic!("模拟核心监听失败：{}", e));
+                let tcp_listener = TcpListener::from_std(tcp_listener)
+                    .unwrap_or_else(|e| panic!("模拟核心监听失败：{}", e));
+                let tcp_state = Arc::clone(&server_state);
+                tokio::select! {
+                    _ = accept_loop(|| listener.accept(), server_state) => {}
+                    _ = accept_loop(|| tcp_listener.accept(), tcp_state) => {}
+                    _ = shutdown_rx => {}
+                }
+            });
+        });
+
+        Self {
+            ipc_path,
+            http_port,
+            state,
+            shutdown: Mutex::new(Some(shutdown_tx)),
+        }
+    }
+
+    pub fn ipc_path(&self) -> &str {
+        &self.ipc_path
+    }
+
+    // 作为远程 external-controller 访问的地址
+    pub fn http_url(&self) -> String {
+        format!("http://127.0.0.1:{}", self.http_port)
+    }
+
+    // 恢复初始状态（共享实例在每个测试开始时调用）
+    pub fn reset(&self) {
+        *lock(&self.state) = MockState::new();
+    }
+
+    // 设置节点延迟，None 表示测试超时
+    pub fn set_proxy_delay(&self, name: &str, delay_ms: Option<u32>) {
+        lock(&self.state)
+            .proxy_delays
+            .insert(name.to_string(), delay_ms);
+    }
+
+    // 每个 REST 响应前的额外延迟
+    pub fn set_response_latency(&self, latency: Duration) {
+        lock(&self.state).response_latency = latency;
+    }
+
+    // 指定路径返回错误状态码
+    pub fn fail_path(&self, path: &str, status_code: u16) {
+        lock(&self.state)
+            .failures
+            .insert(path.to_string(), status_code);
+    }
+
+    // 接下来的 count 个请求不读取直接关闭连接（客户端收到 ECONNRESET）
+    pub fn reset_next_connections(&self, count: usize) {
+        lock(&self.state).resets_remaining = count;
+    }
+
+    // 使用 chunked 编码返回响应，None 表示使用 Content-Length
+    pub fn set_chunk_size(&self, chunk_size: Option<usize>) {
+        lock(&self.state).chunk_size = chunk_size;
+    }
+
+    pub fn set_connection(&self, id: &str, host: &str, upload: u64, download: u64) {
+        let connection = json!({
+            "id": id,
+            "metadata": {
+                "network": "tcp",
+                "type": "HTTP",
+                "sourceIP": "127.0.0.1",
+                "sourcePort": "50000",
+                "host": host,
+                "process": "mock",
+            },
+            "upload": upload,
+            "download": download,
+            "start": "2026-01-01T00:00:00Z",
+            "chains": ["DIRECT"],
+            "rule": "Match",
+            "rulePayload": "",
+        });
+        lock(&self.state)
+            .connections
+            .insert(id.to_string(), connection);
+    }
+
+    // 断开所有 WebSocket 连接（模拟核心重启）
+    pub fn close_websockets(&self) {
+        lock(&self.state).ws_generation += 1;
+    }
+
+    pub fn accepted_connections(&self) -> usize {
+        lock(&self.state).accepted_connections
+    }
+
+    // 指定端点当前保持的 WebSocket 连接数
+    pub fn active_websockets(&self, endpoint: &str) -> usize {
+        lock(&self.state)
+            .active_websockets
+            .get(endpoint)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    pub fn requests(&self) -> Vec<String> {
+        lock(&self.state).requests.clone()
+    }
+}
+
+impl Drop for MockCore {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self
+            .shutdown
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+        {
+            let _ = shutdown.send(());
+        }
+        let _ = std::fs::remove_file(&self.ipc_path);
+    }
+}
+
+// 共享实例：创建时接管 IpcClient::default_ipc_path()，供使用全局连接池的测试使用
+static SHARED: Lazy<MockCore> = Lazy::new(|| {
+    let mock = MockCore::start();
+    super::ipc_client::IpcClient::set_ipc_path(mock.ipc_path());
+    mock
+});
+static SHARED_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+pub fn shared() -> &'static MockCore {
+    &SHARED
+}
+
+// 独占共享实例：连接池是全局的，且池中连接绑定在创建它的测试运行时上，
+// 因此使用共享实例的测试需要串行执行，并在开始时重置状态、清空连接池
+pub async fn exclusive() -> tokio::sync::MutexGuard<'static, ()> {
+    let guard = SHARED_LOCK.lock().await;
+    shared().reset();
+    super::handlers::cleanup_ipc_connection_pool().await;
+    guard
+}
+
+// 可由模拟核心服务的连接（Unix Socket 或 TCP）
+trait MockStream: AsyncRead + AsyncWrite + Unpin + Send + 'static {
+    fn wait_readable(&self) -> impl Future<Output = std::io::Result<()>> + Send;
+}
+
+impl MockStream for UnixStream {
+    fn wait_readable(&self) -> impl Future<Output = std::io::Result<()>> + Send {
+        self.readable()
+    }
+}
+
+impl MockStream for TcpStream {
+    fn wait_readable(&self) -> impl Future<Output = std::io::Result<()>> + Send {
+        self.readable()
+    }
+}
+
+async fn accept_loop<S, Addr, A, F>(accept: A, state: Arc<Mutex<MockState>>)
+where
+    S: MockStream,
+    A: Fn() -> F,
+    F: Future<Output = std::io::Result<(S, Addr)>>,
+{
+    loop {
+        let Ok((stream, _)) = accept().await else {
+            continue;
+        };
+        lock(&state).accepted_connections += 1;
+        tokio::spawn(handle_connection(stream, Arc::clone(&state)));
+    }
+}
+
+// 解析后的请求
+struct MockRequest {
+    method: String,
+    path: String,
+    query: BTreeMap<String, String>,
+    body: String,
+    websocket_key: Option<String>,
+}
+
+// 处理同一连接上的多个请求（keep-alive）
+async fn handle_connection<S: MockStream>(stream: S, state: Arc<Mutex<MockState>>) {
+    let mut reader = BufReader::new(stream);
+    loop {
+        let should_reset = {
+            let mut state = lock(&state);
+            let should_reset = state.resets_remaining > 0;
+            state.resets_remaining = state.resets_remaining.saturating_sub(1);
+            should_reset
+        };
+        if should_reset {
+            // 等请求到达但不读取，关闭时内核向客户端返回 ECONNRESET
+            let _ = reader.get_ref().wait_readable().await;
+            return;
+        }
+
+        let Some(request) = read_request(&mut reader).await else {
+            return;
+        };
+
+        if let Some(key) = request.websocket_key.clone() {
+            serve_websocket(reader.into_inner(), &key, request, state).await;
+            return;
+        }
+
+        let (status_code, body) = route(&state, &request).await;
+        let chunk_size = lock(&state).chunk_size;
+        if write_response(reader.get_mut(), status_code, &body, chunk_size)
+            .await
+            .is_err()
+        {
+            return;
+        }
+    }
+}
+
+async fn read_request<S: MockStream>(reader: &mut BufReader<S>) -> Option<MockRequest> {
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).await.ok()? == 0 {
+        return None;
+    }
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next()?.to_string();
+    let target = parts.next()?.to_string();
+
+    let mut content_length = 0;
+    let mut websocket_key = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await.ok()? == 0 {
+            return None;
+        }
+        if line == "\r\n" {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            let value = value.trim();
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.parse().unwrap_or(0);
+            } else if name.eq_ignore_ascii_case("sec-websocket-key") {
+                websocket_key = Some(value.to_string());
+            }
+        }
+    }
+
+    let mut body = vec![0u8; content_length];
+    reader.read_exact(&mut body).await.ok()?;
+
+    let (path, query) = target.split_once('?').unwrap_or((&target, ""));
+    Some(MockRequest {
+        method,
+        path: path.to_string(),
+        query: url::form_urlencoded::parse(query.as_bytes())
+            .into_owned()
+            .collect(),
+        body: String::from_utf8(body).ok()?,
+        websocket_key,
+    })
+}
+
+fn json_response(status_code: u16, body: Value) -> (u16, String) {
+    (status_code, body.to_string())
+}
+
+fn not_found() -> (u16, String) {
+    json_response(404, json!({ "message": "resource not found" }))
+}
+
+async fn route(state: &Mutex<MockState>, request: &MockRequest) -> (u16, String) {
+    let (latency, failure) = {
+        let mut state = lock(state);
+        state
+            .requests
+            .push(format!("{} {}", request.method, request.path));
+        (
+            state.response_latency,
+            state.failures.get(&request.path).copied(),
+        )
+    };
+
+    if !latency.is_zero() {
+        tokio::time::sleep(latency).await;
+    }
+    if let Some(status_code) = failure {
+        return json_response(status_code, json!({ "message": "mock failure" }));
+    }
+
+    let segments: Vec<String> = request
+        .path
+        .trim_start_matches('/')
+        .split('/')
+        .map(|segment| {
+            urlencoding::decode(segment)
+                .map(|s| s.into_owned())
+                .unwrap_or_default()
+        })
+        .collect();
+    let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+
+    match (request.method.as_str(), segments.as_slice()) {
+        ("GET", ["version"]) => {
+            json_response(200, json!({ "meta": true, "version": "v1.19.0-mock" }))
+        }
+        ("GET", ["proxies"]) => json_response(200, json!({ "proxies": proxies_json(state) })),
+        ("GET", ["proxies", name]) => match proxies_json(state).remove(*name) {
+            Some(proxy) => json_response(200, proxy),
+            None => not_found(),
+        },
+        ("PUT", ["proxies", "GLOBAL"]) => select_proxy(state, &request.body),
+        ("GET", ["proxies", name, "delay"]) => proxy_delay(state, name, &request.query).await,
+        ("GET", ["configs"]) => json_response(200, Value::Object(lock(state).config.clone())),
+        ("PATCH", ["configs"]) => match serde_json::from_str::<Map<String, Value>>(&request.body) {
+            Ok(patch) => {
+                lock(state).config.extend(patch);
+                (204, String::new())
+            }
+            Err(e) => json_response(400, json!({ "message": e.to_string() })),
+        },
+        ("PUT", ["configs"]) => (204, String::new()),
+        ("GET", ["connections"]) => json_response(200, connections_json(state)),
+        _ => not_found(),
+    }
+}
+
+fn proxies_json(state: &Mutex<MockState>) -> Map<String, Value> {
+    let state = lock(state);
+    let mut proxies: Map<String, Value> = state
+        .proxy_delays
+        .iter()
+        .map(|(name, delay)| {
+            let proxy = json!({
+                "name": name,
+                "type": "Shadowsocks",
+                "alive": delay.is_some(),
+                "udp": true,
+                "history": [],
+            });
+            (name.clone(), proxy)
+        })
+        .collect();
+
+    let mut all = vec!["DIRECT".to_string()];
+    all.extend(state.proxy_delays.keys().cloned());
+    proxies.insert(
+        "GLOBAL".to_string(),
+        json!({
+            "name": "GLOBAL",
+            "type": "Selector",
+            "now": state.selected,
+            "all": all,
+            "history": [],
+        }),
+    );
+    proxies
+}
+
+fn select_proxy(state: &Mutex<MockState>, body: &str) -> (u16, String) {
+    let name = serde_json::from_str::<Value>(body)
+        .ok()
+        .and_then(|v| v.get("name").and_then(Value::as_str).map(str::to_string))
+        .unwrap_or_default();
+
+    let mut state = lock(state);
+    if name == "DIRECT" || state.proxy_delays.contains_key(&name) {
+        state.selected = name;
+        (204, String::new())
+    } else {
+        json_response(
+            400,
+            json!({ "message": "Selector update error: not found this proxy" }),
+        )
+    }
+}
+
+// 按脚本延迟返回；超过 timeout 时与核心一样返回 504
+async fn proxy_delay(
+    state: &Mutex<MockState>,
+    name: &str,
+    query: &BTreeMap<String, String>,
+) -> (u16, String) {
+    let timeout_ms = query
+        .get("timeout")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_DELAY_TIMEOUT_MS);
+
+    let Some(delay) = lock(state).proxy_delays.get(name).copied() else {
+        return not_found();
+    };
+
+    match delay {
+        Some(delay_ms) if u64::from(delay_ms) <= timeout_ms => {
+            tokio::time::sleep(Duration::from_millis(delay_ms.into())).await;
+            json_response(200, json!({ "delay": delay_ms }))
+        }
+        _ => {
+            tokio::time::sleep(Duration::from_millis(timeout_ms)).await;
+            json_response(504, json!({ "message": "Timeout" }))
+        }
+    }
+}
+
+fn connections_json(state: &Mutex<MockState>) -> Value {
+    let state = lock(state);
+    let upload_total: u64 = state
+        .connections
+        .values()
+        .filter_map(|c| c["upload"].as_u64())
+        .sum();
+    let download_total: u64 = state
+        .connections
+        .values()
+        .filter_map(|c| c["download"].as_u64())
+        .sum();
+    json!({
+        "uploadTotal": upload_total,
+        "downloadTotal": download_total,
+        "connections": state.connections.values().cloned().collect::<Vec<_>>(),
+        "memory": 10_485_760,
+    })
+}
+
+async fn write_response(
+    stream: &mut (impl AsyncWrite + Unpin),
+    status_code: u16,
+    body: &str,
+    chunk_size: Option<usize>,
+) -> std::io::Result<()> {
+    let reason = http::StatusCode::from_u16(status_code)
+        .ok()
+        .and_then(|status| status.canonical_reason())
+        .unwrap_or("");
+    let mut response = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\n",
+        status_code, reason
+    )
+    .into_bytes();
+
+    match chunk_size {
+        // 按字节切分，多字节字符可能跨越 chunk 边界
+        Some(size) if size > 0 && !body.is_empty() => {
+            response.extend_from_slice(b"Transfer-Encoding: chunked\r\n\r\n");
+            for chunk in body.as_bytes().chunks(size) {
+                response.extend_from_slice(format!("{:x}\r\n", chunk.len()).as_bytes());
+                response.extend_from_slice(chunk);
+                response.extend_from_slice(b"\r\n");
+            }
+            response.extend_from_slice(b"0\r\n\r\n");
+        }
+        _ => {
+            response
+                .extend_from_slice(format!("Content-Length: {}\r\n\r\n", body.len()).as_bytes());
+            response.extend_from_slice(body.as_bytes());
+        }
+    }
+
+    stream.write_all(&response).await
+}
+
+// 完成握手后定时推送数据，直到客户端断开或调用 close_websockets
+async fn serve_websocket<S: MockStream>(
+    mut stream: S,
+    key: &str,
+    request: MockRequest,
+    state: Arc<Mutex<MockState>>,
+) {
+    if !WS_ENDPOINTS.contains(&request.path.as_str()) {
+        let (status_code, body) = not_found();
+        let _ = write_response(&mut stream, status_code, &body, None).await;
+        return;
+    }
+
+    let handshake = format!(
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+        derive_accept_key(key.as_bytes())
+    );
+    if stream.write_all(handshake.as_bytes()).await.is_err() {
+        return;
+    }
+
+    let mut ws = WebSocketStream::from_raw_socket(stream, Role::Server, None).await;
+    let generation = {
+        let mut state = lock(&state);
+        *state
+            .active_websockets
+            .entry(request.path.clone())
+            .or_default() += 1;
+        state.ws_generation
+    };
+    let mut interval = tokio::time::interval(WS_PUSH_INTERVAL);
+
+    let is_client_gone = loop {
+        interval.tick().await;
+        if lock(&state).ws_generation != generation {
+            break false;
+        }
+
+        let message = match request.path.as_str() {
+            "/traffic" => json!({ "up": 1024, "down": 2048 }),
+            "/memory" => json!({ "inuse": 10_485_760, "oslimit": 0 }),
+            "/logs" => json!({
+                "type": request.query.get("level").map_or("info", String::as_str),
+                "payload": "[TCP] 127.0.0.1:50000(mock) --> example.com:443 match Match using DIRECT",
+            }),
+            _ => connections_json(&state),
+        };
+
+        if ws.send(Message::text(message.to_string())).await.is_err() {
+            break true;
+        }
+    };
+
+    if let Some(count) = lock(&state).active_websockets.get_mut(&request.path) {
+        *count = count.saturating_sub(1);
+    }
+    if !is_client_gone {
+        let _ = ws.close(None).await;
+    }
+}
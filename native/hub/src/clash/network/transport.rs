@@ -0,0 +1,353 @@
+// 控制器传输方式
+//
+// 目的：除本机核心的 IPC 端点外，支持通过 HTTP(S) 连接路由器、服务器上运行的
+// mihomo external-controller，使同一界面可以监控和切换远程核心的节点
+//
+// - 传输方式按会话选择（不持久化），切换时先请求 /version 验证连接与密钥
+// - REST 请求与 WebSocket 流均按当前传输方式发送；切换后已有的流自动重连到新的核心
+// - 远程请求使用 Authorization: Bearer <secret>
+// - 远程核心仅用于监控和切换节点：配置管理（PUT/PATCH /configs 等）会覆盖
+//   路由器、服务器上的配置，一律拒绝
+
+use once_cell::sync::Lazy;
+use rinf::{DartSignal, RustSignal};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::spawn;
+use tokio::sync::watch;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, connect_async};
+use url::Url;
+
+use super::ipc_client::HttpResponse;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+// 整个请求的超时（含读取响应体），需覆盖策略组延迟测试的耗时
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+// 远程核心允许只读访问的路径（含子路径）
+const REMOTE_READ_PATHS: &[&str] = &[
+    "/version",
+    "/proxies",
+    "/providers",
+    "/group",
+    "/rules",
+    "/configs",
+    "/connections",
+    "/logs",
+    "/traffic",
+    "/memory",
+];
+
+// Dart → Rust：设置本次会话的控制器传输方式
+#[derive(Deserialize, DartSignal)]
+pub struct SetControllerTransport {
+    pub remote_url: String, // 如 "https://192.168.1.1:9090"，为空表示使用本机核心（IPC）
+    pub secret: String,
+}
+
+// Rust → Dart：传输方式设置结果
+#[derive(Serialize, RustSignal)]
+pub struct ControllerTransportResult {
+    pub is_successful: bool,
+    pub is_remote: bool, // 当前生效的传输方式
+    pub version: String, // 远程核心版本（本机核心为空）
+    pub error_message: Option<String>,
+}
+
+// 控制器传输方式
+#[derive(Clone, Default)]
+pub enum ControllerTransport {
+    #[default]
+    Ipc, // 本机核心（Unix Socket / Named Pipe）
+    Remote(RemoteController),
+}
+
+// 远程 external-controller
+#[derive(Clone)]
+pub struct RemoteController {
+    base_url: Url,
+    secret: String,
+    http_client: reqwest::Client,
+}
+
+pub type RemoteWebSocket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+// 当前传输方式，WebSocket 接收循环订阅其变化以触发重连
+static TRANSPORT: Lazy<watch::Sender<ControllerTransport>> =
+    Lazy::new(|| watch::Sender::new(ControllerTransport::Ipc));
+
+// 当前使用的远程控制器，本机核心时返回 None
+pub fn remote_controller() -> Option<RemoteController> {
+    match &*TRANSPORT.borrow() {
+        ControllerTransport::Ipc => None,
+        ControllerTransport::Remote(remote) => Some(remote.clone()),
+    }
+}
+
+// 订阅传输方式变化
+pub fn subscribe_changes() -> watch::Receiver<ControllerTransport> {
+    TRANSPORT.subscribe()
+}
+
+impl RemoteController {
+    pub fn new(url: &str, secret: &str) -> Result<Self, String> {
+        let base_url = Url::parse(url.trim()).map_err(|e| format!("无效的控制器地址：{}", e))?;
+        if !matches!(base_url.scheme(), "http" | "https") {
+            return Err(format!(
+                "控制器地址仅支持 http 或 https：{}",
+                base_url.scheme()
+            ));
+        }
+
+        let http_client = reqwest::Client::builder()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .timeout(REQUEST_TIMEOUT)
+            .no_proxy() // 避免请求经由本机代理转发
+            .build()
+            .map_err(|e| format!("创建 HTTP 客户端失败：{}", e))?;
+
+        Ok(Self {
+            base_url,
+            secret: secret.to_string(),
+            http_client,
+        })
+    }
+
+    // 拼接请求地址，path 可包含查询参数（如 "/logs?level=info"）
+    fn endpoint_url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url.as_str().trim_end_matches('/'), path)
+    }
+
+    pub async fn request(
+        &self,
+        method: &str,
+        path: &str,
+        body: Option<&str>,
+    ) -> Result<HttpResponse, String> {
+        check_remote_request(method, path)?;
+        let method = reqwest::Method::from_bytes(method.as_bytes())
+            .map_err(|e| format!("无效的请求方法：{}", e))?;
+
+        let mut request = self.http_client.request(method, self.endpoint_url(path));
+        if !self.secret.is_empty() {
+            request = request.bearer_auth(&self.secret);
+        }
+        if let Some(body) = body {
+            request = request
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(body.to_string());
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("远程控制器请求失败：{}", e))?;
+        let status_code = response.status().as_u16();
+        let body = response
+            .text()
+            .await
+            .map_err(|e| format!("读取远程控制器响应失败：{}", e))?;
+
+        Ok(HttpResponse { status_code, body })
+    }
+
+    pub async fn open_websocket(&self, endpoint: &str) -> Result<RemoteWebSocket, String> {
+        check_remote_request("GET", endpoint)?;
+        let mut url = Url::parse(&self.endpoint_url(endpoint))
+            .map_err(|e| format!("无效的 WebSocket 地址：{}", e))?;
+        let scheme = if url.scheme() == "https" { "wss" } else { "ws" };
+        url.set_scheme(scheme)
+            .map_err(|()| format!("无效的 WebSocket 地址：{}", url))?;
+
+        let mut request = url
+            .as_str()
+            .into_client_request()
+            .map_err(|e| format!("构造 WebSocket 请求失败：{}", e))?;
+        if !self.secret.is_empty() {
+            let value = HeaderValue::from_str(&format!("Bearer {}", self.secret))
+                .map_err(|e| format!("无效的密钥：{}", e))?;
+            request.headers_mut().insert("Authorization", value);
+        }
+
+        let (ws_stream, _) = tokio::time::timeout(CONNECT_TIMEOUT, connect_async(request))
+            .await
+            .map_err(|_| "连接远程控制器超时".to_string())?
+            .map_err(|e| format!("WebSocket 握手失败：{}", e))?;
+
+        Ok(ws_stream)
+    }
+}
+
+// 检查请求是否允许发往远程核心：只读监控接口、切换节点（PUT /proxies/{name}）
+// 和关闭连接（DELETE /connections[/{id}]）
+pub fn check_remote_request(method: &str, path: &str) -> Result<(), String> {
+    let denied = || {
+        Err(format!(
+            "远程核心仅支持监控和切换节点，不允许 {} {}",
+            method, path
+        ))
+    };
+
+    // 按解码后的路径段判断，节点名中编码的 `/`（如 `HK%2F01`）仍属于同一段
+    let Some(segments) = decode_path_segments(path) else {
+        return denied();
+    };
+    let is_under = |prefix: &str| {
+        let prefix: Vec<&str> = prefix.split('/').skip(1).collect();
+        segments.len() >= prefix.len() && segments[..prefix.len()] == prefix[..]
+    };
+
+    let is_allowed = match method {
+        "GET" => REMOTE_READ_PATHS.iter().any(|prefix| is_under(prefix)),
+        "PUT" => segments.len() == 2 && segments[0] == "proxies",
+        "DELETE" => is_under("/connections") && segments.len() <= 2,
+        _ => false,
+    };
+
+    if is_allowed { Ok(()) } else { denied() }
+}
+
+// 去除查询参数并逐段百分号解码路径
+//
+// 路径不以 `/` 开头、含空段或解码后为 `.`、`..` 时返回 None
+fn decode_path_segments(path: &str) -> Option<Vec<String>> {
+    let path = path.split('?').next().unwrap_or(path);
+    let path = path.trim_end_matches('/');
+    if path.is_empty() {
+        return Some(Vec::new());
+    }
+
+    let path = path.strip_prefix('/')?;
+    path.split('/')
+        .map(|segment| {
+            let segment = urlencoding::decode(segment).ok()?;
+            (!matches!(segment.as_ref(), "" | "." | "..")).then(|| segment.into_owned())
+        })
+        .collect()
+}
+
+impl SetControllerTransport {
+    pub async fn handle(self) -> ControllerTransportResult {
+        if self.remote_url.trim().is_empty() {
+            log::info!("控制器传输方式：本机核心（IPC）");
+            TRANSPORT.send_replace(ControllerTransport::Ipc);
+            return ControllerTransportResult {
+                is_successful: true,
+                is_remote: false,
+                version: String::new(),
+                error_message: None,
+            };
+        }
+
+        match Self::connect_remote(&self.remote_url, &self.secret).await {
+            Ok((remote, version)) => {
+                log::info!(
+                    "控制器传输方式：远程核心 {}（{}）",
+                    self.remote_url,
+                    version
+                );
+                TRANSPORT.send_replace(ControllerTransport::Remote(remote));
+                ControllerTransportResult {
+                    is_successful: true,
+                    is_remote: true,
+                    version,
+                    error_message: None,
+                }
+            }
+            Err(e) => {
+                // 验证失败时保持原有传输方式
+                log::error!("连接远程控制器失败：{}", e);
+                ControllerTransportResult {
+                    is_successful: false,
+                    is_remote: remote_controller().is_some(),
+                    version: String::new(),
+                    error_message: Some(e),
+                }
+            }
+        }
+    }
+
+    // 请求 /version 验证地址与密钥，返回核心版本
+    async fn connect_remote(url: &str, secret: &str) -> Result<(RemoteController, String), String> {
+        let remote = RemoteController::new(url, secret)?;
+        let response = remote.request("GET", "/version", None).await?;
+
+        match response.status_code {
+            200 => {}
+            401 | 403 => return Err("密钥错误".to_string()),
+            status_code => return Err(format!("远程控制器返回 HTTP {}", status_code)),
+        }
+
+        let version = serde_json::from_str::<serde_json::Value>(&response.body)
+            .ok()
+            .and_then(|v| {
+                v.get("version")
+                    .and_then(|v| v.as_str())
+                    .map(str::to_string)
+            })
+            .ok_or_else(|| "响应不是 mihomo 控制器的版本信息".to_string())?;
+
+        Ok((remote, version))
+    }
+}
+
+// 初始化传输方式消息监听器
+pub fn init_message_listeners() {
+    spawn(async {
+        let receiver = SetControllerTransport::get_dart_signal_receiver();
+        while let Some(dart_signal) = receiver.recv().await {
+            dart_signal.message.handle().await.send_signal_to_dart();
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remote_controller_urls() {
+        let Ok(remote) = RemoteController::new("https://router.lan:9090/", "secret") else {
+            panic!("地址应有效");
+        };
+        assert_eq!(
+            remote.endpoint_url("/logs?level=info"),
+            "https://router.lan:9090/logs?level=info"
+        );
+
+        let Ok(remote) = RemoteController::new("http://10.0.0.1:9090/api", "") else {
+            panic!("地址应有效");
+        };
+        assert_eq!(
+            remote.endpoint_url("/version"),
+            "http://10.0.0.1:9090/api/version"
+        );
+
+        assert!(RemoteController::new("ftp://router.lan", "").is_err());
+        assert!(RemoteController::new("router.lan:9090", "").is_err());
+    }
+
+    #[test]
+    fn test_remote_request_filter() {
+        assert!(check_remote_request("GET", "/proxies").is_ok());
+        assert!(check_remote_request("GET", "/proxies/香港/delay?timeout=5000").is_ok());
+        assert!(check_remote_request("GET", "/configs").is_ok());
+        assert!(check_remote_request("GET", "/logs?level=info").is_ok());
+        assert!(check_remote_request("PUT", "/proxies/GLOBAL").is_ok());
+        assert!(check_remote_request("PUT", "/proxies/HK%2F01").is_ok());
+        assert!(check_remote_request("GET", "/proxies/HK%2F01/delay?timeout=5000").is_ok());
+        assert!(check_remote_request("DELETE", "/connections/abc").is_ok());
+
+        assert!(check_remote_request("PUT", "/configs?force=true").is_err());
+        assert!(check_remote_request("PUT", "/configs/").is_err());
+        assert!(check_remote_request("PATCH", "/configs").is_err());
+        assert!(check_remote_request("POST", "/restart").is_err());
+        assert!(check_remote_request("PUT", "/proxies/../configs").is_err());
+        assert!(check_remote_request("PUT", "/proxies/%2E%2E").is_err());
+        assert!(check_remote_request("PUT", "/proxies/HK/01").is_err());
+        assert!(check_remote_request("PUT", "/providers/proxies/sub").is_err());
+    }
+}
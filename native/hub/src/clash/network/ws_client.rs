@@ -1,12 +1,18 @@
 // WebSocket over IPC 客户端
-// 通过 Named Pipe/Unix Socket 建立 WebSocket 连接
+// 通过 Named Pipe/Unix Socket 建立 WebSocket 连接；已切换到远程控制器时改用 ws(s)://
 
 use super::connection;
+use super::handlers::{IpcStreamState, StreamConnectionState};
+use super::transport::{self, ControllerTransport};
 use base64::Engine;
-use futures_util::stream::StreamExt;
+use futures_util::stream::{BoxStream, StreamExt};
+use rinf::RustSignal;
 use std::collections::HashMap;
 use std::sync::Arc;
-use tokio_tungstenite::{WebSocketStream, client_async, tungstenite::protocol::Message};
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio_tungstenite::tungstenite::{self, protocol::Message};
+use tokio_tungstenite::{WebSocketStream, client_async};
 
 #[cfg(unix)]
 use tokio::net::UnixStream;
@@ -14,6 +20,15 @@ use tokio::net::UnixStream;
 #[cfg(windows)]
 use tokio::net::windows::named_pipe::NamedPipeClient;
 
+#[cfg(unix)]
+pub type IpcStream = UnixStream;
+
+#[cfg(windows)]
+pub type IpcStream = NamedPipeClient;
+
+// 读取流与底层传输（IPC / TCP）无关
+type WsReader = BoxStream<'static, Result<Message, tungstenite::Error>>;
+
 // HTTP Request 构建器 (来自 http crate)
 use http::Request;
 use http::header::{CONNECTION, HOST, SEC_WEBSOCKET_KEY, SEC_WEBSOCKET_VERSION, UPGRADE};
@@ -21,6 +36,10 @@ use http::header::{CONNECTION, HOST, SEC_WEBSOCKET_KEY, SEC_WEBSOCKET_VERSION, U
 // WebSocket 连接 ID
 pub type ConnectionId = u32;
 
+// 自动重连退避参数
+const RECONNECT_INITIAL_DELAY_MS: u64 = 500;
+const RECONNECT_MAX_DELAY_MS: u64 = 10_000;
+
 // WebSocket 客户端
 pub struct WebSocketClient {
     ipc_path: String,
@@ -50,6 +69,9 @@ impl WebSocketClient {
 
     // 连接到 WebSocket 端点
     //
+    // 首次连接失败时直接返回错误；连接建立后若被核心关闭（如核心重启），
+    // 按退避间隔使用相同的端点及参数自动重连，直到调用 disconnect
+    //
     // # 参数
     // - `endpoint`: WebSocket 端点路径，如 "/traffic", "/logs?level=info"
     // - `on_message`: 消息回调函数
@@ -57,6 +79,30 @@ impl WebSocketClient {
     // # 返回
     // 连接 ID，用于后续管理和断开连接
     pub async fn connect<F>(&self, endpoint: &str, on_message: F) -> Result<ConnectionId, String>
+    where
+        F: Fn(serde_json::Value) + Send + 'static,
+    {
+        self.connect_with(endpoint, on_message, false).await
+    }
+
+    // 连接到本机核心的 WebSocket 端点，不随控制器传输方式切换
+    pub async fn connect_local<F>(
+        &self,
+        endpoint: &str,
+        on_message: F,
+    ) -> Result<ConnectionId, String>
+    where
+        F: Fn(serde_json::Value) + Send + 'static,
+    {
+        self.connect_with(endpoint, on_message, true).await
+    }
+
+    async fn connect_with<F>(
+        &self,
+        endpoint: &str,
+        on_message: F,
+        is_local_only: bool,
+    ) -> Result<ConnectionId, String>
     where
         F: Fn(serde_json::Value) + Send + 'static,
     {
@@ -70,14 +116,101 @@ impl WebSocketClient {
             id
         };
 
-        // 2. 连接到 IPC 端点
+        // 2. 建立连接（先订阅传输方式变化，避免遗漏建立连接期间的切换）
+        // 仅连接本机核心时使用发送端已关闭的通道，接收循环不会因切换而断开
+        let mut transport_changes = if is_local_only {
+            watch::channel(ControllerTransport::Ipc).1
+        } else {
+            transport::subscribe_changes()
+        };
+        let reader = Self::open(&self.ipc_path, endpoint, is_local_only).await?;
+        log::info!("WebSocket 连接建立成功[{}]：{}", connection_id, endpoint);
+
+        // 3. 启动消息接收循环（断开后自动重连）
+        let ipc_path = self.ipc_path.clone();
+        let endpoint = endpoint.to_string();
+        let handle = tokio::spawn(async move {
+            log::trace!("WebSocket 消息接收循环已启动 [{}]", connection_id);
+
+            let mut reader = reader;
+            let mut on_message = on_message;
+            loop {
+                on_message = Self::receive_messages(
+                    connection_id,
+                    &mut reader,
+                    &mut transport_changes,
+                    on_message,
+                )
+                .await;
+                log::warn!(
+                    "WebSocket 连接已断开[{}]，准备重连：{}",
+                    connection_id,
+                    endpoint
+                );
+
+                let mut attempt = 0;
+                reader = loop {
+                    attempt += 1;
+                    send_stream_state(&endpoint, StreamConnectionState::Reconnecting, attempt);
+                    tokio::time::sleep(reconnect_delay(attempt)).await;
+
+                    match Self::open(&ipc_path, &endpoint, is_local_only).await {
+                        Ok(reader) => break reader,
+                        Err(e) => {
+                            log::debug!(
+                                "WebSocket 重连失败[{}]（第 {} 次）：{}",
+                                connection_id,
+                                attempt,
+                                e
+                            );
+                        }
+                    }
+                };
+
+                log::info!(
+                    "WebSocket 已重新连接[{}]（第 {} 次尝试）：{}",
+                    connection_id,
+                    attempt,
+                    endpoint
+                );
+                send_stream_state(&endpoint, StreamConnectionState::Connected, attempt);
+            }
+        });
+
+        // 存储连接句柄
+        {
+            let mut conns = self.connections.lock().await;
+            conns.insert(connection_id, handle);
+        }
+
+        Ok(connection_id)
+    }
+
+    // 按当前传输方式（或固定使用本机核心）连接并完成 WebSocket 握手，返回读取流
+    async fn open(ipc_path: &str, endpoint: &str, is_local_only: bool) -> Result<WsReader, String> {
+        // 分离读写流
+        if !is_local_only && let Some(remote) = transport::remote_controller() {
+            let (_writer, reader) = remote.open_websocket(endpoint).await?.split();
+            return Ok(reader.boxed());
+        }
+
+        let (_writer, reader) = Self::open_stream(ipc_path, endpoint).await?.split();
+        Ok(reader.boxed())
+    }
+
+    // 连接 IPC 端点并完成 WebSocket 握手，返回可双向读写的流
+    pub async fn open_stream(
+        ipc_path: &str,
+        endpoint: &str,
+    ) -> Result<WebSocketStream<IpcStream>, String> {
+        // 1. 连接到 IPC 端点
         #[cfg(windows)]
-        let stream = self.connect_windows().await?;
+        let stream = connection::connect_named_pipe(ipc_path).await?;
 
         #[cfg(unix)]
-        let stream = self.connect_unix().await?;
+        let stream = connection::connect_unix_socket(ipc_path).await?;
 
-        // 3. 构造 WebSocket 握手请求（使用 http::Request）
+        // 2. 构造 WebSocket 握手请求（使用 http::Request）
         // 关键：使用 ws:// scheme 以通过 tungstenite 的 URI 验证
         let uri = format!("ws://localhost{}", endpoint);
         log::trace!("构造 URI：{}", uri);
@@ -92,85 +225,81 @@ impl WebSocketClient {
             .body(())
             .map_err(|e| format!("构造 WebSocket 请求失败：{}", e))?;
 
-        log::trace!("WebSocket 请求构造成功，URI：{:?}", request.uri());
-
         log::trace!("发送 WebSocket 握手请求：{}", endpoint);
 
-        // 4. 使用 client_async 建立 WebSocket 连接
+        // 3. 使用 client_async 建立 WebSocket 连接
         let (ws_stream, _) = client_async(request, stream)
             .await
             .map_err(|e| format!("WebSocket 握手失败：{}", e))?;
 
-        log::info!("WebSocket 连接建立成功[{}]：{}", connection_id, endpoint);
-
-        // 5. 分离读写流
-        let (_writer, mut reader) = ws_stream.split();
-
-        // 6. 启动消息接收循环
-        let connections = self.connections.clone();
-        let handle = tokio::spawn(async move {
-            log::trace!("WebSocket 消息接收循环已启动 [{}]", connection_id);
+        Ok(ws_stream)
+    }
 
-            while let Some(message) = reader.next().await {
-                match message {
-                    Ok(Message::Text(text)) => {
-                        // 解析 JSON 消息
-                        match serde_json::from_str::<serde_json::Value>(&text) {
-                            Ok(json_value) => {
-                                log::trace!(
-                                    "WebSocket 收到消息[{}]：{}bytes",
-                                    connection_id,
-                                    text.len()
-                                );
-                                on_message(json_value);
-                            }
-                            Err(e) => {
-                                log::error!(
-                                    "WebSocket 消息 JSON 解析失败[{}]：{}",
-                                    connection_id,
-                                    e
-                                );
-                            }
+    // 接收消息直到连接关闭、出错或传输方式切换，归还回调供重连后继续使用
+    async fn receive_messages<F>(
+        connection_id: ConnectionId,
+        reader: &mut WsReader,
+        transport_changes: &mut watch::Receiver<ControllerTransport>,
+        on_message: F,
+    ) -> F
+    where
+        F: Fn(serde_json::Value),
+    {
+        loop {
+            let message = tokio::select! {
+                message = reader.next() => match message {
+                    Some(message) => message,
+                    None => break,
+                },
+                Ok(()) = transport_changes.changed() => {
+                    log::info!("控制器传输方式已切换，断开当前连接[{}]", connection_id);
+                    break;
+                }
+            };
+
+            match message {
+                Ok(Message::Text(text)) => {
+                    // 解析 JSON 消息
+                    match serde_json::from_str::<serde_json::Value>(&text) {
+                        Ok(json_value) => {
+                            log::trace!(
+                                "WebSocket 收到消息[{}]：{}bytes",
+                                connection_id,
+                                text.len()
+                            );
+                            on_message(json_value);
+                        }
+                        Err(e) => {
+                            log::error!("WebSocket 消息 JSON 解析失败[{}]：{}", connection_id, e);
                         }
                     }
-                    Ok(Message::Close(close_frame)) => {
-                        log::info!("WebSocket 连接关闭[{}]：{:?}", connection_id, close_frame);
-                        break;
-                    }
-                    Ok(Message::Ping(_)) | Ok(Message::Pong(_)) => {
-                        // Ping/Pong 由 tokio-tungstenite 自动处理
-                    }
-                    Ok(Message::Binary(data)) => {
-                        log::debug!(
-                            "WebSocket 收到二进制消息[{}]：{}bytes",
-                            connection_id,
-                            data.len()
-                        );
-                    }
-                    Ok(Message::Frame(_)) => {
-                        // 忽略原始帧
-                    }
-                    Err(e) => {
-                        log::error!("WebSocket 消息读取错误[{}]：{}", connection_id, e);
-                        break;
-                    }
+                }
+                Ok(Message::Close(close_frame)) => {
+                    log::info!("WebSocket 连接关闭[{}]：{:?}", connection_id, close_frame);
+                    break;
+                }
+                Ok(Message::Ping(_)) | Ok(Message::Pong(_)) => {
+                    // Ping/Pong 由 tokio-tungstenite 自动处理
+                }
+                Ok(Message::Binary(data)) => {
+                    log::debug!(
+                        "WebSocket 收到二进制消息[{}]：{}bytes",
+                        connection_id,
+                        data.len()
+                    );
+                }
+                Ok(Message::Frame(_)) => {
+                    // 忽略原始帧
+                }
+                Err(e) => {
+                    log::error!("WebSocket 消息读取错误[{}]：{}", connection_id, e);
+                    break;
                 }
             }
-
-            log::debug!("WebSocket 消息接收循环已结束[{}]", connection_id);
-
-            // 连接结束后，从连接表中移除
-            let mut conns = connections.lock().await;
-            conns.remove(&connection_id);
-        });
-
-        // 存储连接句柄
-        {
-            let mut conns = self.connections.lock().await;
-            conns.insert(connection_id, handle);
         }
 
-        Ok(connection_id)
+        log::debug!("WebSocket 消息接收循环已结束[{}]", connection_id);
+        on_message
     }
 
     // 断开指定的 WebSocket 连接
@@ -203,18 +332,23 @@ impl WebSocketClient {
             log::info!("所有 WebSocket 连接已断开");
         }
     }
+}
 
-    // Windows: 连接到 Named Pipe
-    #[cfg(windows)]
-    async fn connect_windows(&self) -> Result<NamedPipeClient, String> {
-        connection::connect_named_pipe(&self.ipc_path).await
-    }
+// 重连间隔：从 500 毫秒开始指数增长，最长 10 秒
+fn reconnect_delay(attempt: u32) -> Duration {
+    let delay = RECONNECT_INITIAL_DELAY_MS.saturating_mul(1 << attempt.saturating_sub(1).min(16));
+    Duration::from_millis(delay.min(RECONNECT_MAX_DELAY_MS))
+}
 
-    // Unix: 连接到 Unix Socket
-    #[cfg(unix)]
-    async fn connect_unix(&self) -> Result<UnixStream, String> {
-        connection::connect_unix_socket(&self.ipc_path).await
+// 通知 Dart 流的连接状态（端点不含查询参数，如 "/logs"）
+fn send_stream_state(endpoint: &str, state: StreamConnectionState, attempt: u32) {
+    let path = endpoint.split('?').next().unwrap_or(endpoint);
+    IpcStreamState {
+        endpoint: path.to_string(),
+        state,
+        attempt,
     }
+    .send_signal_to_dart();
 }
 
 #[cfg(test)]
@@ -237,6 +371,13 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_reconnect_delay() {
+        assert_eq!(reconnect_delay(1), Duration::from_millis(500));
+        assert_eq!(reconnect_delay(3), Duration::from_millis(2000));
+        assert_eq!(reconnect_delay(100), Duration::from_millis(10_000));
+    }
+
     #[test]
     fn test_connection_id_increment() {
         let client = WebSocketClient::new(String::from("test"));
@@ -244,4 +385,64 @@ mod tests {
         // 验证初始 ID 从 1 开始
         assert_eq!(*client.next_connection_id.blocking_lock(), 1);
     }
+
+    #[cfg(unix)]
+    async fn next_message(
+        receiver: &mut tokio::sync::mpsc::UnboundedReceiver<serde_json::Value>,
+    ) -> serde_json::Value {
+        match tokio::time::timeout(Duration::from_secs(3), receiver.recv()).await {
+            Ok(Some(value)) => value,
+            _ => panic!("未在超时前收到 WebSocket 消息"),
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_stream_reconnects_after_core_restart() {
+        // 持有共享实例的锁，避免其他测试切换到远程传输方式
+        let _guard = super::super::mock_core::exclusive().await;
+        let mock = super::super::mock_core::MockCore::start();
+        let client = WebSocketClient::new(mock.ipc_path().to_string());
+
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        let connection_id = client
+            .connect("/traffic", move |value| {
+                let _ = sender.send(value);
+            })
+            .await
+            .unwrap_or_else(|e| panic!("{}", e));
+        assert_eq!(next_message(&mut receiver).await["up"], 1024);
+
+        // 核心断开后按退避间隔重连，继续推送到同一回调
+        mock.close_websockets();
+        tokio::time::sleep(reconnect_delay(1) + Duration::from_millis(200)).await;
+        while receiver.try_recv().is_ok() {}
+        assert_eq!(next_message(&mut receiver).await["down"], 2048);
+        assert_eq!(mock.accepted_connections(), 2);
+
+        client.disconnect(connection_id).await;
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_stream_endpoint_query() {
+        // 持有共享实例的锁，避免其他测试切换到远程传输方式
+        let _guard = super::super::mock_core::exclusive().await;
+        let mock = super::super::mock_core::MockCore::start();
+        let client = WebSocketClient::new(mock.ipc_path().to_string());
+
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        client
+            .connect("/logs?level=warning", move |value| {
+                let _ = sender.send(value);
+            })
+            .await
+            .unwrap_or_else(|e| panic!("{}", e));
+        assert_eq!(next_message(&mut receiver).await["type"], "warning");
+
+        let result = client.connect("/unknown", |_| {}).await;
+        assert!(result.is_err());
+
+        client.disconnect_all().await;
+    }
 }
@@ -2,9 +2,14 @@
 //
 // 目的：提供 YAML 和 JavaScript 格式的配置覆写功能
 
+pub mod cache;
+pub mod diff;
 pub mod downloader;
 pub mod js_executor;
+pub mod js_module_loader;
 pub mod processor;
+pub mod scope;
+pub mod template;
 pub mod yaml_merger;
 
 pub use downloader::DownloadOverrideRequest;
@@ -0,0 +1,111 @@
+// 远程覆写缓存
+//
+// 目的：保存远程覆写的最后一次可用版本及 ETag，
+// 用于条件请求和下载失败时的回退
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// 缓存元数据
+#[derive(Debug, Deserialize, Serialize)]
+pub struct OverrideCacheMeta {
+    pub url: String,
+    pub etag: Option<String>,
+    pub sha256: String,
+    pub updated_at: String, // RFC 3339 格式
+}
+
+// 已缓存的覆写内容
+//
+// 哈希按下载的原始字节计算，与 sha256sum 的结果一致
+pub struct CachedOverride {
+    pub content: String,
+    pub meta: OverrideCacheMeta,
+}
+
+// 单个远程覆写的缓存条目
+//
+// 文件名由 URL 的 SHA-256 决定，内容与元数据分开存放
+pub struct OverrideCache {
+    content_path: PathBuf,
+    meta_path: PathBuf,
+    url: String,
+}
+
+impl OverrideCache {
+    pub fn new(cache_dir: &Path, url: &str) -> Self {
+        let key = sha256_hex(url.as_bytes());
+        Self {
+            content_path: cache_dir.join(format!("{}.override", key)),
+            meta_path: cache_dir.join(format!("{}.json", key)),
+            url: url.to_string(),
+        }
+    }
+
+    // 读取缓存
+    //
+    // 内容与元数据中记录的哈希不一致时视为缓存损坏，返回 None
+    pub fn load(&self) -> Option<CachedOverride> {
+        let meta_text = fs::read_to_string(&self.meta_path).ok()?;
+        let meta: OverrideCacheMeta = match serde_json::from_str(&meta_text) {
+            Ok(meta) => meta,
+            Err(e) => {
+                log::warn!("覆写缓存元数据损坏：{}", e);
+                return None;
+            }
+        };
+
+        let bytes = fs::read(&self.content_path).ok()?;
+        if sha256_hex(&bytes) != meta.sha256 {
+            log::warn!("覆写缓存内容校验失败，忽略缓存：{}", self.url);
+            return None;
+        }
+
+        let content = match decode_override_text(&bytes) {
+            Ok(content) => content,
+            Err(e) => {
+                log::warn!("覆写缓存内容无效：{}", e);
+                return None;
+            }
+        };
+
+        Some(CachedOverride { content, meta })
+    }
+
+    // 写入缓存（作为最后一次可用版本），保存下载的原始字节
+    pub fn store(&self, bytes: &[u8], etag: Option<String>) -> Result<(), String> {
+        if let Some(dir) = self.content_path.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("创建缓存目录失败：{}", e))?;
+        }
+
+        let meta = OverrideCacheMeta {
+            url: self.url.clone(),
+            etag,
+            sha256: sha256_hex(bytes),
+            updated_at: chrono::Utc::now().to_rfc3339(),
+        };
+        let meta_text = serde_json::to_string_pretty(&meta)
+            .map_err(|e| format!("序列化缓存元数据失败：{}", e))?;
+
+        fs::write(&self.content_path, bytes).map_err(|e| format!("写入缓存内容失败：{}", e))?;
+        fs::write(&self.meta_path, meta_text).map_err(|e| format!("写入缓存元数据失败：{}", e))?;
+
+        Ok(())
+    }
+}
+
+// 将覆写文件的原始字节解码为文本（UTF-8，去除 BOM）
+pub fn decode_override_text(bytes: &[u8]) -> Result<String, String> {
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    String::from_utf8(bytes.to_vec()).map_err(|e| format!("覆写文件不是有效的 UTF-8 文本：{}", e))
+}
+
+// 计算 SHA-256 并返回小写十六进制字符串
+pub fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
@@ -0,0 +1,170 @@
+// 覆写步骤差异报告
+//
+// 目的：记录每个覆写步骤对配置造成的结构化变更，便于审计覆写链
+
+use rinf::SignalPiece;
+use serde::{Deserialize, Serialize};
+use serde_yaml_ng::Value as YamlValue;
+use std::collections::HashMap;
+
+// 单个覆写步骤的差异
+//
+// 键路径使用 `.` 连接嵌套映射的键，例如 `dns.nameserver`
+#[derive(Debug, Default, Deserialize, Serialize, SignalPiece, Clone)]
+pub struct OverrideStepDiff {
+    pub step_index: u32,
+    pub override_id: String,
+    pub override_name: String,
+    pub added_keys: Vec<String>,
+    pub removed_keys: Vec<String>,
+    pub changed_keys: Vec<String>,
+    pub added_proxies: Vec<String>,
+    pub removed_proxies: Vec<String>,
+    pub added_proxy_groups: Vec<String>,
+    pub removed_proxy_groups: Vec<String>,
+    pub added_rules: Vec<String>,
+    pub removed_rules: Vec<String>,
+}
+
+impl OverrideStepDiff {
+    // 比较覆写前后的配置
+    pub fn compute(before: &str, after: &str) -> Result<Self, String> {
+        let before: YamlValue =
+            serde_yaml_ng::from_str(before).map_err(|e| format!("解析覆写前配置失败：{}", e))?;
+        let after: YamlValue =
+            serde_yaml_ng::from_str(after).map_err(|e| format!("解析覆写后配置失败：{}", e))?;
+
+        let mut diff = Self::default();
+        diff_keys(&before, &after, "", &mut diff);
+
+        (diff.added_proxies, diff.removed_proxies) = diff_multiset(
+            named_items(&before, "proxies"),
+            named_items(&after, "proxies"),
+        );
+        (diff.added_proxy_groups, diff.removed_proxy_groups) = diff_multiset(
+            named_items(&before, "proxy-groups"),
+            named_items(&after, "proxy-groups"),
+        );
+        (diff.added_rules, diff.removed_rules) =
+            diff_multiset(rule_items(&before), rule_items(&after));
+
+        Ok(diff)
+    }
+}
+
+// 递归比较映射的键，非映射值只比较是否相等
+fn diff_keys(before: &YamlValue, after: &YamlValue, prefix: &str, diff: &mut OverrideStepDiff) {
+    let (Some(before_map), Some(after_map)) = (before.as_mapping(), after.as_mapping()) else {
+        if before != after && !prefix.is_empty() {
+            diff.changed_keys.push(prefix.to_string());
+        }
+        return;
+    };
+
+    for (key, before_value) in before_map {
+        let path = join_path(prefix, key);
+        match after_map.get(key) {
+            Some(after_value) => diff_keys(before_value, after_value, &path, diff),
+            None => diff.removed_keys.push(path),
+        }
+    }
+
+    for key in after_map.keys() {
+        if !before_map.contains_key(key) {
+            diff.added_keys.push(join_path(prefix, key));
+        }
+    }
+}
+
+fn join_path(prefix: &str, key: &YamlValue) -> String {
+    let key = match key {
+        YamlValue::String(s) => s.clone(),
+        other => serde_yaml_ng::to_string(other)
+            .map(|s| s.trim_end().to_string())
+            .unwrap_or_default(),
+    };
+
+    if prefix.is_empty() {
+        key
+    } else {
+        format!("{}.{}", prefix, key)
+    }
+}
+
+// 提取列表中各项的 name 字段
+fn named_items(config: &YamlValue, field: &str) -> Vec<String> {
+    config
+        .get(field)
+        .and_then(|v| v.as_sequence())
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| item.get("name").and_then(|n| n.as_str()))
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn rule_items(config: &YamlValue) -> Vec<String> {
+    config
+        .get("rules")
+        .and_then(|v| v.as_sequence())
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| item.as_str())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// 按多重集比较两个列表，返回（新增项，删除项），保持原有顺序
+fn diff_multiset(before: Vec<String>, after: Vec<String>) -> (Vec<String>, Vec<String>) {
+    let mut remaining: HashMap<&str, usize> = HashMap::new();
+    for item in &before {
+        *remaining.entry(item.as_str()).or_default() += 1;
+    }
+
+    let mut added = Vec::new();
+    for item in &after {
+        match remaining.get_mut(item.as_str()) {
+            Some(count) if *count > 0 => *count -= 1,
+            _ => added.push(item.clone()),
+        }
+    }
+
+    let mut removed = Vec::new();
+    for item in &before {
+        if let Some(count) = remaining.get_mut(item.as_str())
+            && *count > 0
+        {
+            *count -= 1;
+            removed.push(item.clone());
+        }
+    }
+
+    (added, removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_step_diff_reports_keys_and_list_items() {
+        let before = "mode: rule\ndns:\n  enable: true\nproxies:\n  - name: a\n  - name: b\nrules:\n  - MATCH,DIRECT\n";
+        let after = "mode: global\ndns:\n  enable: true\n  ipv6: false\nproxies:\n  - name: b\n  - name: c\nrules:\n  - DOMAIN,x.com,DIRECT\n  - MATCH,DIRECT\nlog-level: info\n";
+
+        let diff = OverrideStepDiff::compute(before, after).unwrap_or_default();
+
+        assert_eq!(diff.added_keys, vec!["dns.ipv6", "log-level"]);
+        assert!(diff.removed_keys.is_empty());
+        assert_eq!(diff.changed_keys, vec!["mode", "proxies", "rules"]);
+        assert_eq!(diff.added_proxies, vec!["c"]);
+        assert_eq!(diff.removed_proxies, vec!["a"]);
+        assert_eq!(diff.added_rules, vec!["DOMAIN,x.com,DIRECT"]);
+        assert!(diff.removed_rules.is_empty());
+    }
+}
@@ -1,10 +1,13 @@
 // 覆写文件下载器
 // 处理覆写文件的 HTTP 下载，支持多种代理模式
+// 支持 SHA-256 固定、ETag 条件请求以及下载失败时回退到最后一次可用版本
 
+use super::cache::{CachedOverride, OverrideCache, decode_override_text, sha256_hex};
 use crate::clash::subscription::downloader::ProxyMode;
-use reqwest::Client;
-use rinf::{DartSignal, RustSignal};
+use reqwest::{Client, StatusCode};
+use rinf::{DartSignal, RustSignal, SignalPiece};
 use serde::{Deserialize, Serialize};
+use std::path::Path;
 use std::time::Duration;
 
 // Dart → Rust：下载覆写文件请求
@@ -16,6 +19,16 @@ pub struct DownloadOverrideRequest {
     pub user_agent: String,
     pub timeout_seconds: u64,
     pub mixed_port: u16,
+    pub sha256_pin: Option<String>, // 固定的内容哈希（十六进制），为空表示不校验
+    pub cache_dir: Option<String>,  // 缓存目录，为空表示不缓存
+}
+
+// 覆写内容来源
+#[derive(Serialize, SignalPiece, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverrideContentSource {
+    Network = 0,       // 新下载的内容
+    NotModified = 1,   // 服务器返回 304，使用缓存
+    LastKnownGood = 2, // 下载失败或校验失败，回退到缓存
 }
 
 // Rust → Dart：下载覆写文件响应
@@ -24,48 +37,172 @@ pub struct DownloadOverrideResponse {
     pub request_id: String, // 请求标识符，用于请求匹配
     pub is_successful: bool,
     pub content: String,
-    pub error_message: Option<String>,
+    pub error_message: Option<String>, // 回退到缓存时记录回退原因
+    pub content_source: OverrideContentSource,
+    pub sha256: String,        // 返回内容原始字节的 SHA-256
+    pub is_pin_mismatch: bool, // 远程内容与固定哈希不一致
+}
+
+// HTTP 下载结果
+pub enum OverrideFetch {
+    Modified {
+        bytes: Vec<u8>, // 原始响应体，用于哈希校验和缓存
+        etag: Option<String>,
+    },
+    NotModified,
 }
 
 impl DownloadOverrideRequest {
     pub async fn handle(self) {
         log::info!("收到下载覆写文件请求 [{}]：{}", self.request_id, self.url);
 
+        let response = self.fetch().await;
+
+        if response.is_successful {
+            log::info!(
+                "覆写文件获取成功 [{}]（{:?}），内容长度：{} 字节",
+                self.request_id,
+                response.content_source,
+                response.content.len()
+            );
+        } else {
+            log::error!(
+                "覆写文件下载失败 [{}]：{}",
+                self.request_id,
+                response.error_message.as_deref().unwrap_or_default()
+            );
+        }
+
+        response.send_signal_to_dart();
+    }
+
+    async fn fetch(&self) -> DownloadOverrideResponse {
+        let pin = self
+            .sha256_pin
+            .as_deref()
+            .map(|p| p.trim().to_ascii_lowercase())
+            .filter(|p| !p.is_empty());
+        let cache = self
+            .cache_dir
+            .as_deref()
+            .filter(|d| !d.is_empty())
+            .map(|d| OverrideCache::new(Path::new(d), &self.url));
+
+        // 只使用与固定哈希一致的缓存
+        let cached = cache.as_ref().and_then(|c| c.load()).filter(|c| {
+            pin.as_ref()
+                .is_none_or(|pin| c.meta.sha256.eq_ignore_ascii_case(pin))
+        });
+        let etag = cached.as_ref().and_then(|c| c.meta.etag.as_deref());
+
         let result = download_override(
             &self.url,
             self.proxy_mode,
             &self.user_agent,
             self.timeout_seconds,
             self.mixed_port,
+            etag,
         )
         .await;
 
-        let response = match result {
-            Ok(content) => {
-                log::info!(
-                    "覆写文件下载成功 [{}]，内容长度：{} 字节",
-                    self.request_id,
-                    content.len()
-                );
+        match result {
+            Ok(OverrideFetch::NotModified) => match cached {
+                Some(cached) => {
+                    log::info!("覆写文件未修改，使用缓存：{}", self.url);
+                    self.cached_response(cached, OverrideContentSource::NotModified, None, false)
+                }
+                None => self.failed_response("服务器返回 304，但本地没有缓存".to_string(), false),
+            },
+            Ok(OverrideFetch::Modified { bytes, etag }) => {
+                // 按原始字节计算哈希，与发布文件的 sha256sum 一致
+                let sha256 = sha256_hex(&bytes);
+
+                if let Some(pin) = &pin
+                    && &sha256 != pin
+                {
+                    log::warn!(
+                        "覆写文件内容与固定哈希不一致：期望 {}，实际 {}",
+                        pin,
+                        sha256
+                    );
+                    let reason = format!("内容与固定的 SHA-256 不一致（实际：{}）", sha256);
+                    return self.fallback_response(cached, reason, true);
+                }
+
+                let content = match decode_override_text(&bytes) {
+                    Ok(content) => content,
+                    Err(e) => return self.fallback_response(cached, e, false),
+                };
+
+                if let Some(cache) = &cache
+                    && let Err(e) = cache.store(&bytes, etag)
+                {
+                    log::warn!("写入覆写缓存失败：{}", e);
+                }
+
                 DownloadOverrideResponse {
-                    request_id: self.request_id,
+                    request_id: self.request_id.clone(),
                     is_successful: true,
                     content,
                     error_message: None,
+                    content_source: OverrideContentSource::Network,
+                    sha256,
+                    is_pin_mismatch: false,
                 }
             }
-            Err(e) => {
-                log::error!("覆写文件下载失败 [{}]：{}", self.request_id, e);
-                DownloadOverrideResponse {
-                    request_id: self.request_id,
-                    is_successful: false,
-                    content: String::new(),
-                    error_message: Some(e.to_string()),
-                }
+            Err(e) => self.fallback_response(cached, e.to_string(), false),
+        }
+    }
+
+    // 下载或校验失败时回退到最后一次可用版本
+    fn fallback_response(
+        &self,
+        cached: Option<CachedOverride>,
+        reason: String,
+        is_pin_mismatch: bool,
+    ) -> DownloadOverrideResponse {
+        match cached {
+            Some(cached) => {
+                log::warn!("回退到缓存的覆写文件：{}", reason);
+                self.cached_response(
+                    cached,
+                    OverrideContentSource::LastKnownGood,
+                    Some(reason),
+                    is_pin_mismatch,
+                )
             }
-        };
+            None => self.failed_response(reason, is_pin_mismatch),
+        }
+    }
 
-        response.send_signal_to_dart();
+    fn cached_response(
+        &self,
+        cached: CachedOverride,
+        content_source: OverrideContentSource,
+        error_message: Option<String>,
+        is_pin_mismatch: bool,
+    ) -> DownloadOverrideResponse {
+        DownloadOverrideResponse {
+            request_id: self.request_id.clone(),
+            is_successful: true,
+            content: cached.content,
+            error_message,
+            content_source,
+            sha256: cached.meta.sha256,
+            is_pin_mismatch,
+        }
+    }
+
+    fn failed_response(&self, error: String, is_pin_mismatch: bool) -> DownloadOverrideResponse {
+        DownloadOverrideResponse {
+            request_id: self.request_id.clone(),
+            is_successful: false,
+            content: String::new(),
+            error_message: Some(error),
+            content_source: OverrideContentSource::Network,
+            sha256: String::new(),
+            is_pin_mismatch,
+        }
     }
 }
 
@@ -77,15 +214,17 @@ impl DownloadOverrideRequest {
 // - user_agent: User-Agent 头
 // - timeout_seconds: 超时时间（秒）
 // - mixed_port: Clash 混合端口
+// - etag: 缓存的 ETag，存在时发送条件请求
 //
-// 返回：文件内容
+// 返回：文件内容及 ETag，或未修改
 pub async fn download_override(
     url: &str,
     proxy_mode: ProxyMode,
     user_agent: &str,
     timeout_seconds: u64,
     mixed_port: u16,
-) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    etag: Option<&str>,
+) -> Result<OverrideFetch, Box<dyn std::error::Error + Send + Sync>> {
     log::info!("开始下载覆写文件：{}", url);
     log::info!("代理模式：{:?}", proxy_mode);
 
@@ -93,14 +232,17 @@ pub async fn download_override(
     let client = create_http_client(proxy_mode, timeout_seconds, mixed_port)?;
 
     // 发送 HTTP GET 请求
-    let response = client
-        .get(url)
-        .header("User-Agent", user_agent)
-        .send()
-        .await?;
+    let mut request = client.get(url).header("User-Agent", user_agent);
+    if let Some(etag) = etag {
+        request = request.header("If-None-Match", etag);
+    }
+    let response = request.send().await?;
 
     // 检查 HTTP 状态码
     let status = response.status();
+    if status == StatusCode::NOT_MODIFIED {
+        return Ok(OverrideFetch::NotModified);
+    }
     if !status.is_success() {
         return Err(format!(
             "HTTP {}: {}",
@@ -110,16 +252,22 @@ pub async fn download_override(
         .into());
     }
 
-    // 读取响应体
-    let content = response.text().await?;
+    let etag = response
+        .headers()
+        .get("ETag")
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+
+    // 读取原始响应体，解码在校验哈希之后进行
+    let bytes = response.bytes().await?.to_vec();
 
-    if content.is_empty() {
+    if bytes.is_empty() {
         return Err("覆写文件内容为空".into());
     }
 
-    log::info!("覆写文件下载成功，内容长度：{} 字节", content.len());
+    log::info!("覆写文件下载成功，内容长度：{} 字节", bytes.len());
 
-    Ok(content)
+    Ok(OverrideFetch::Modified { bytes, etag })
 }
 
 // 创建 HTTP 客户端（复用订阅下载的逻辑）
@@ -156,3 +304,206 @@ fn create_http_client(
 
     Ok(builder.build()?)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::sync::{Arc, Mutex, PoisonError};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    const OK_BODY: &str = "mode: rule\n";
+
+    fn ok_response(body: &str, etag: &str) -> String {
+        format!(
+            "HTTP/1.1 200 OK\r\nETag: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            etag,
+            body.len(),
+            body
+        )
+    }
+
+    const NOT_MODIFIED: &str = "HTTP/1.1 304 Not Modified\r\nConnection: close\r\n\r\n";
+    const SERVER_ERROR: &str =
+        "HTTP/1.1 500 Internal Server Error\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+
+    // 按顺序返回预设响应的 HTTP 服务器，记录每个请求的 If-None-Match
+    async fn serve(responses: Vec<String>) -> (String, Arc<Mutex<Vec<Option<String>>>>) {
+        let listener = TcpListener::bind(("127.0.0.1", 0))
+            .await
+            .unwrap_or_else(|e| panic!("监听失败：{}", e));
+        let url = match listener.local_addr() {
+            Ok(addr) => format!("http://{}/override.yaml", addr),
+            Err(e) => panic!("获取监听地址失败：{}", e),
+        };
+        let etags = Arc::new(Mutex::new(Vec::new()));
+
+        let recorded = etags.clone();
+        tokio::spawn(async move {
+            for response in responses {
+                let Ok((mut stream, _)) = listener.accept().await else {
+                    return;
+                };
+
+                let mut head = Vec::new();
+                let mut buf = [0u8; 1024];
+                while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+                    match stream.read(&mut buf).await {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => head.extend_from_slice(&buf[..n]),
+                    }
+                }
+
+                let etag = String::from_utf8_lossy(&head).lines().find_map(|line| {
+                    let (name, value) = line.split_once(':')?;
+                    name.eq_ignore_ascii_case("if-none-match")
+                        .then(|| value.trim().to_string())
+                });
+                recorded
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .push(etag);
+
+                let _ = stream.write_all(response.as_bytes()).await;
+                let _ = stream.shutdown().await;
+            }
+        });
+
+        (url, etags)
+    }
+
+    fn cache_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "stelliberty_override_cache_{}_{}",
+            std::process::id(),
+            name
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn request(url: &str, cache_dir: &Path, sha256_pin: Option<String>) -> DownloadOverrideRequest {
+        DownloadOverrideRequest {
+            request_id: "test".to_string(),
+            url: url.to_string(),
+            proxy_mode: ProxyMode::Direct,
+            user_agent: "stelliberty-test".to_string(),
+            timeout_seconds: 5,
+            mixed_port: 0,
+            sha256_pin,
+            cache_dir: Some(cache_dir.to_string_lossy().into_owned()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_not_modified_reuses_cache() {
+        let dir = cache_dir("not_modified");
+        let (url, etags) = serve(vec![
+            ok_response(OK_BODY, "\"v1\""),
+            NOT_MODIFIED.to_string(),
+        ])
+        .await;
+
+        let response = request(&url, &dir, None).fetch().await;
+        assert!(response.is_successful);
+        assert_eq!(response.content_source, OverrideContentSource::Network);
+
+        // 第二次请求携带缓存的 ETag，服务器返回 304 时使用缓存内容
+        let response = request(&url, &dir, None).fetch().await;
+        assert!(response.is_successful);
+        assert_eq!(response.content_source, OverrideContentSource::NotModified);
+        assert_eq!(response.content, OK_BODY);
+        assert_eq!(response.sha256, sha256_hex(OK_BODY.as_bytes()));
+        assert_eq!(
+            *etags.lock().unwrap_or_else(PoisonError::into_inner),
+            vec![None, Some("\"v1\"".to_string())]
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_download_failure_falls_back_to_cache() {
+        let dir = cache_dir("fallback");
+        let (url, _) = serve(vec![
+            ok_response(OK_BODY, "\"v1\""),
+            SERVER_ERROR.to_string(),
+        ])
+        .await;
+
+        let response = request(&url, &dir, None).fetch().await;
+        assert_eq!(response.content_source, OverrideContentSource::Network);
+
+        let response = request(&url, &dir, None).fetch().await;
+        assert!(response.is_successful);
+        assert_eq!(
+            response.content_source,
+            OverrideContentSource::LastKnownGood
+        );
+        assert_eq!(response.content, OK_BODY);
+        assert!(response.error_message.is_some_and(|e| e.contains("500")));
+
+        // 没有缓存时下载失败直接报错
+        let empty_dir = cache_dir("fallback_empty");
+        let (url, _) = serve(vec![SERVER_ERROR.to_string()]).await;
+        let response = request(&url, &empty_dir, None).fetch().await;
+        assert!(!response.is_successful);
+        assert!(response.content.is_empty());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_pin_mismatch_rejected() {
+        let dir = cache_dir("pin");
+        let pin = sha256_hex(OK_BODY.as_bytes());
+        let changed_body = "mode: global\n";
+        let (url, _) = serve(vec![
+            ok_response(OK_BODY, "\"v1\""),
+            ok_response(changed_body, "\"v2\""),
+        ])
+        .await;
+
+        let response = request(&url, &dir, Some(pin.to_uppercase())).fetch().await;
+        assert!(response.is_successful);
+        assert_eq!(response.content_source, OverrideContentSource::Network);
+        assert!(!response.is_pin_mismatch);
+
+        // 远程内容变化后与固定哈希不一致：拒绝新内容并回退到缓存
+        let response = request(&url, &dir, Some(pin.clone())).fetch().await;
+        assert!(response.is_successful);
+        assert!(response.is_pin_mismatch);
+        assert_eq!(
+            response.content_source,
+            OverrideContentSource::LastKnownGood
+        );
+        assert_eq!(response.content, OK_BODY);
+
+        // 不一致的内容不会写入缓存
+        let cached = OverrideCache::new(&dir, &url).load();
+        assert!(cached.is_some_and(|c| c.content == OK_BODY));
+
+        // 哈希按原始字节计算：带 BOM 的文件与 sha256sum 结果一致，返回的内容去除 BOM
+        let bom_body = format!("\u{feff}{}", OK_BODY);
+        let bom_dir = cache_dir("pin_bom");
+        let (bom_url, _) = serve(vec![ok_response(&bom_body, "\"v3\"")]).await;
+        let response = request(&bom_url, &bom_dir, Some(sha256_hex(bom_body.as_bytes())))
+            .fetch()
+            .await;
+        assert!(response.is_successful);
+        assert!(!response.is_pin_mismatch);
+        assert_eq!(response.content, OK_BODY);
+        let _ = std::fs::remove_dir_all(&bom_dir);
+
+        // 没有可用缓存时直接失败
+        let empty_dir = cache_dir("pin_empty");
+        let (url, _) = serve(vec![ok_response(changed_body, "\"v2\"")]).await;
+        let response = request(&url, &empty_dir, Some(pin)).fetch().await;
+        assert!(!response.is_successful);
+        assert!(response.is_pin_mismatch);
+        assert!(OverrideCache::new(&empty_dir, &url).load().is_none());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
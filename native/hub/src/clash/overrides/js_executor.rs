@@ -2,166 +2,142 @@
 //
 // 目的：使用 Boa 引擎执行用户的 JavaScript 覆写脚本
 
-use boa_engine::{Context, Source};
-use serde_json::Value as JsonValue;
-use serde_yaml_ng::Value as YamlValue;
+use super::js_module_loader::OverrideModuleLoader;
+use boa_engine::builtins::promise::PromiseState;
+use boa_engine::object::builtins::JsArray;
+use boa_engine::property::PropertyKey;
+use boa_engine::{Context, JsBigInt, JsObject, JsValue, JsVariant, Module, Source, js_string};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_yaml_ng::{Mapping, Number, Value as YamlValue};
+use std::collections::HashSet;
+use std::rc::Rc;
+
+// JavaScript 中可精确表示的最大整数（Number.MAX_SAFE_INTEGER，2^53 - 1）
+const MAX_SAFE_INTEGER: i64 = (1 << 53) - 1;
+
+// 形如 `key: 123e456` 或 `- 123e456` 的块样式普通标量
+static PLAIN_EXPONENT_SCALAR: Lazy<Regex> = Lazy::new(|| {
+    // 注意：此正则表达式是硬编码的字面量，编译时已验证正确性
+    #[allow(clippy::expect_used)]
+    Regex::new(r"(?m)^(\s*(?:- )*(?:\S+: )?)([+-]?\d+[eE][+-]?\d+)[ \t]*$")
+        .expect("正则表达式编译失败：这是编译时错误，不应该在运行时发生")
+});
 
 // JavaScript 执行器
 pub struct JsExecutor {
     context: Context,
+    module_loader: Rc<OverrideModuleLoader>,
 }
 
 impl JsExecutor {
     // 创建新的 JavaScript 执行器
     //
-    // 目的：初始化 Boa 上下文
+    // 目的：初始化 Boa 上下文及覆写库模块加载器
     pub fn new() -> Result<Self, String> {
-        let context = Context::default();
+        let module_loader = Rc::new(OverrideModuleLoader::new());
+        let context = Context::builder()
+            .module_loader(module_loader.clone())
+            .build()
+            .map_err(|e| format!("创建 JavaScript 上下文失败：{}", e))?;
+
+        Ok(Self {
+            context,
+            module_loader,
+        })
+    }
 
-        Ok(Self { context })
+    // 注册覆写库，供模块脚本通过 `import ... from "lib:覆写ID"` 引用
+    pub fn register_library(&mut self, name: &str, source: &str) {
+        log::info!("注册 JavaScript 覆写库：{}", name);
+        self.module_loader.register(name, source);
     }
 
     // 应用 JavaScript 覆写到基础配置
     //
     // 目的：
-    // 1. 将 YAML 配置转换为 JSON
+    // 1. 将 YAML 配置直接构建为 JS 对象
     // 2. 执行用户的 JavaScript 脚本（必须定义 main(config) 函数）
-    // 3. 将结果转换回 YAML
-    pub fn apply(&mut self, base_content: &str, js_code: &str) -> Result<String, String> {
+    // 3. 将返回的 JS 对象直接转换回 YAML
+    //
+    // is_module 由覆写格式决定：为 true 时按 ES 模块执行（可导入覆写库）
+    pub fn apply(
+        &mut self,
+        base_content: &str,
+        js_code: &str,
+        is_module: bool,
+    ) -> Result<String, String> {
         log::info!("JavaScript 覆写开始");
         log::info!("基础配置长度：{}字节", base_content.len());
         log::info!("JS 脚本长度：{}字节", js_code.len());
 
-        // 1. 解析 YAML → JSON
+        // 1. 解析 YAML
         let yaml_val: YamlValue = serde_yaml_ng::from_str(base_content).map_err(|e| {
             log::error!("✗ 解析 YAML 配置失败：{}", e);
             format!("解析配置失败：{}", e)
         })?;
 
-        let json_val: JsonValue = serde_json::to_value(&yaml_val).map_err(|e| {
-            log::error!("✗ 转换为 JSON 失败：{}", e);
-            format!("转换为 JSON 失败：{}", e)
-        })?;
-
-        let config_json = serde_json::to_string(&json_val).map_err(|e| {
-            log::error!("✗ 序列化 JSON 失败：{}", e);
-            format!("序列化 JSON 失败：{}", e)
-        })?;
-
-        log::info!(
-            "✓ YAML → JSON 转换成功，JSON 长度：{}字节",
-            config_json.len()
-        );
-
-        // 检查 proxies 字段
-        if let Some(proxies) = json_val.get("proxies") {
-            if let Some(arr) = proxies.as_array() {
-                log::info!("配置中包含{}个代理节点", arr.len());
-                if let Some(first_proxy) = arr.first() {
-                    log::info!(
-                        "  第一个代理节点：{}",
-                        serde_json::to_string(first_proxy).unwrap_or_default()
-                    );
-                }
-            }
+        if let Some(arr) = yaml_val.get("proxies").and_then(|p| p.as_sequence()) {
+            log::info!("配置中包含{}个代理节点", arr.len());
         } else {
             log::warn!("配置中未找到 proxies 字段");
         }
 
-        // 转义 JSON 字符串中的反斜杠和单引号，以便安全地嵌入 JavaScript
-        let escaped_config = config_json.replace('\\', "\\\\").replace('\'', "\\'");
-
-        // 2. 构建完整的 JavaScript 代码
-        // 用户脚本必须定义 main(config) 函数
-        let full_js_code = format!(
-            r#"
-            (function() {{
-                // 用户的覆写代码（定义 main 函数）
-                {}
-
-                // 初始化配置对象（从基础配置的 JSON）
-                var config = JSON.parse('{}');
-
-                // 调用 main 函数并传入配置
-                if (typeof main === 'function') {{
-                    config = main(config);
-                }} else {{
-                    throw new Error('覆写脚本必须定义 main(config) 函数');
-                }}
-
-                // 返回修改后的配置
-                return JSON.stringify(config);
-            }})()
-            "#,
-            js_code, escaped_config
-        );
-
-        log::info!(
-            "✓ JavaScript 代码构建完成，总长度：{}字节",
-            full_js_code.len()
-        );
+        // 2. 加载用户脚本，取得 main 函数
+        let main_fn = if is_module {
+            self.load_module_main_function(js_code)?
+        } else {
+            self.load_main_function(js_code)?
+        };
 
-        // 3. 执行 JavaScript
-        log::info!("→ 开始执行 JavaScript…");
-        let source = Source::from_bytes(&full_js_code);
-        let result = self.context.eval(source).map_err(|e| {
-            log::error!("✗ JavaScript 执行失败：{}", e);
-            format!("JavaScript 执行失败：{}", e)
+        // 3. YAML → JS 对象
+        let config = Self::yaml_to_js(&yaml_val, &mut self.context).map_err(|e| {
+            log::error!("✗ 构建 JS 配置对象失败：{}", e);
+            format!("构建 JS 配置对象失败：{}", e)
         })?;
 
-        log::info!("✓ JavaScript 执行成功");
+        log::info!("✓ YAML → JS 对象转换成功");
 
-        // 4. 提取结果字符串
-        let result_str = result.to_string(&mut self.context).map_err(|e| {
-            log::error!("✗ 提取 JavaScript 结果失败：{}", e);
-            format!("提取 JavaScript 结果失败：{}", e)
-        })?;
+        // 4. 调用 main(config)
+        log::info!("→ 开始执行 JavaScript…");
+        let result = main_fn
+            .call(&JsValue::undefined(), &[config], &mut self.context)
+            .map_err(|e| {
+                log::error!("✗ JavaScript 执行失败：{}", e);
+                format!("JavaScript 执行失败：{}", e)
+            })?;
 
-        let result_str = result_str.to_std_string().map_err(|e| {
-            log::error!("✗ 转换结果字符串失败：{}", e);
-            format!("转换结果字符串失败：{}", e)
-        })?;
+        log::info!("✓ JavaScript 执行成功");
 
-        log::info!("✓ JavaScript 结果长度：{}字节", result_str.len());
+        if result.is_null_or_undefined() {
+            log::error!("✗ main(config) 未返回配置对象");
+            return Err("main(config) 必须返回修改后的配置对象".to_string());
+        }
 
-        // 5. JSON → YAML
-        let json_result: JsonValue = serde_json::from_str(&result_str).map_err(|e| {
-            log::error!("✗ 解析 JavaScript 结果失败：{}", e);
-            log::error!("✗ 错误的 JSON 内容：{}", result_str);
-            format!("解析 JavaScript 结果失败：{}", e)
-        })?;
+        // 5. JS 对象 → YAML
+        let mut seen = HashSet::new();
+        let yaml_result =
+            Self::js_to_yaml(&result, &mut self.context, &mut seen)?.unwrap_or(YamlValue::Null);
 
-        log::info!("✓ JSON 解析成功");
-
-        // 检查返回的 proxies 字段
-        if let Some(proxies) = json_result.get("proxies") {
-            if let Some(arr) = proxies.as_array() {
-                log::info!("返回的配置中包含{}个代理节点", arr.len());
-                if let Some(first_proxy) = arr.first() {
-                    log::info!(
-                        "  返回的第一个代理节点：{}",
-                        serde_json::to_string(first_proxy).unwrap_or_default()
-                    );
-                }
-            }
+        if let Some(arr) = yaml_result.get("proxies").and_then(|p| p.as_sequence()) {
+            log::info!("返回的配置中包含{}个代理节点", arr.len());
         } else {
             log::warn!("返回的配置中未找到 proxies 字段");
         }
 
-        let yaml_result: YamlValue = serde_json::from_value(json_result).map_err(|e| {
-            log::error!("✗ 转换为 YAML 失败：{}", e);
-            format!("转换为 YAML 失败：{}", e)
-        })?;
-
         let mut final_yaml = serde_yaml_ng::to_string(&yaml_result).map_err(|e| {
             log::error!("✗ 序列化 YAML 失败：{}", e);
             format!("序列化 YAML 失败：{}", e)
         })?;
 
-        // 修复可能被误解析为科学计数法的字符串值
-        // 例如：short-id: 6314e825 会被解析为 6314 × 10^825 = Infinity
-        // 需要改为：short-id: "6314e825"
-        final_yaml = Self::fix_scientific_notation_strings(&final_yaml);
+        // serde_yaml_ng 输出形如 6314e825 的字符串时不加引号，
+        // 而 mihomo 会将其解析为科学计数法浮点数（如 Reality short-id 变为 Infinity），
+        // 为所有此类字符串值加引号；浮点数保持原样
+        let mut strings = HashSet::new();
+        Self::collect_exponent_like_strings(&yaml_result, &mut strings);
+        if !strings.is_empty() {
+            final_yaml = Self::quote_exponent_like_strings(&final_yaml, &strings);
+        }
 
         log::info!("✓ YAML 序列化成功，最终长度：{}字节", final_yaml.len());
 
@@ -169,23 +145,418 @@ impl JsExecutor {
         Ok(final_yaml)
     }
 
-    // 修复可能被误解析为科学计数法的字符串值
+    // 执行用户脚本并返回其定义的 main 函数
     //
-    // 将形如 `key: 123e456` 的值改为 `key: "123e456"`
-    fn fix_scientific_notation_strings(yaml: &str) -> String {
-        // 匹配模式：键名: 数字+字母e/E+数字（可能有+/-）
-        // 例如：short-id: 6314e825
-        // 注意：此正则表达式是硬编码的字面量，编译时已验证正确性
-        #[allow(clippy::expect_used)]
-        let re = regex::Regex::new(r"(?m)^(\s*)(\S+):\s*([+-]?\d+[eE][+-]?\d+)\s*$")
-            .expect("正则表达式编译失败：这是编译时错误，不应该在运行时发生");
-
-        re.replace_all(yaml, |caps: &regex::Captures| {
-            let indent = &caps[1];
-            let key = &caps[2];
-            let value = &caps[3];
-            format!("{}{}: \"{}\"", indent, key, value)
+    // 脚本包裹在独立函数作用域内执行，避免同一上下文中多个覆写的
+    // 顶层声明相互冲突
+    fn load_main_function(&mut self, js_code: &str) -> Result<JsObject, String> {
+        let wrapped = format!(
+            "(function() {{\n{}\n;return typeof main === 'function' ? main : undefined;\n}})()",
+            js_code
+        );
+
+        let main_fn = self
+            .context
+            .eval(Source::from_bytes(&wrapped))
+            .map_err(|e| {
+                log::error!("✗ JavaScript 脚本加载失败：{}", e);
+                format!("JavaScript 执行失败：{}", e)
+            })?;
+
+        main_fn.as_callable().ok_or_else(|| {
+            log::error!("✗ 覆写脚本未定义 main(config) 函数");
+            "覆写脚本必须定义 main(config) 函数".to_string()
         })
-        .to_string()
+    }
+
+    // 以 ES 模块方式加载脚本，取得导出的 main 或默认导出函数
+    fn load_module_main_function(&mut self, js_code: &str) -> Result<JsObject, String> {
+        let module =
+            Module::parse(Source::from_bytes(js_code), None, &mut self.context).map_err(|e| {
+                log::error!("✗ JavaScript 模块解析失败：{}", e);
+                format!("JavaScript 模块解析失败：{}", e)
+            })?;
+
+        let promise = module.load_link_evaluate(&mut self.context);
+        self.context
+            .run_jobs()
+            .map_err(|e| format!("JavaScript 模块执行失败：{}", e))?;
+
+        match promise.state() {
+            PromiseState::Fulfilled(_) => {}
+            PromiseState::Rejected(e) => {
+                let message = e.display().to_string();
+                log::error!("✗ JavaScript 模块加载失败：{}", message);
+                return Err(format!("JavaScript 执行失败：{}", message));
+            }
+            PromiseState::Pending => {
+                return Err("JavaScript 模块未能完成加载".to_string());
+            }
+        }
+
+        let namespace = module.namespace(&mut self.context);
+        for export_name in ["main", "default"] {
+            let export = namespace
+                .get(js_string!(export_name), &mut self.context)
+                .map_err(|e| format!("读取模块导出失败：{}", e))?;
+            if let Some(main_fn) = export.as_callable() {
+                return Ok(main_fn);
+            }
+        }
+
+        log::error!("✗ 覆写模块未导出 main(config) 函数");
+        Err("覆写模块必须导出 main(config) 函数".to_string())
+    }
+
+    // 将 YAML 值直接构建为 JS 值
+    //
+    // 整数的绝对值不超过 Number.MAX_SAFE_INTEGER 时转换为 Number，超出时转换为
+    // BigInt 以避免精度丢失（脚本中 typeof 为 'bigint'，不能与 Number 直接混合运算），
+    // 写回 YAML 时还原为整数；浮点数始终转换为 Number
+    fn yaml_to_js(value: &YamlValue, context: &mut Context) -> Result<JsValue, String> {
+        let js_value = match value {
+            YamlValue::Null => JsValue::null(),
+            YamlValue::Bool(b) => JsValue::new(*b),
+            YamlValue::Number(n) => Self::number_to_js(n),
+            YamlValue::String(s) => JsValue::new(js_string!(s.as_str())),
+            YamlValue::Sequence(seq) => {
+                let mut items = Vec::with_capacity(seq.len());
+                for item in seq {
+                    items.push(Self::yaml_to_js(item, context)?);
+                }
+                JsArray::from_iter(items, context).into()
+            }
+            YamlValue::Mapping(map) => {
+                let obj = JsObject::with_object_proto(context.intrinsics());
+                for (key, val) in map {
+                    let key = Self::yaml_key_to_string(key)?;
+                    let val = Self::yaml_to_js(val, context)?;
+                    obj.create_data_property_or_throw(js_string!(key.as_str()), val, context)
+                        .map_err(|e| format!("设置属性 {} 失败：{}", key, e))?;
+                }
+                obj.into()
+            }
+            YamlValue::Tagged(tagged) => Self::yaml_to_js(&tagged.value, context)?,
+        };
+
+        Ok(js_value)
+    }
+
+    fn number_to_js(n: &Number) -> JsValue {
+        if let Some(i) = n.as_i64() {
+            if !(-MAX_SAFE_INTEGER..=MAX_SAFE_INTEGER).contains(&i) {
+                return JsValue::new(JsBigInt::from(i));
+            }
+            return match i32::try_from(i) {
+                Ok(small) => JsValue::new(small),
+                Err(_) => JsValue::new(i as f64),
+            };
+        }
+
+        // 无法表示为 i64 的整数大于 i64::MAX，必然超出安全范围
+        if let Some(u) = n.as_u64() {
+            return JsValue::new(JsBigInt::from(u));
+        }
+
+        JsValue::new(n.as_f64().unwrap_or(f64::NAN))
+    }
+
+    fn yaml_key_to_string(key: &YamlValue) -> Result<String, String> {
+        match key {
+            YamlValue::String(s) => Ok(s.clone()),
+            YamlValue::Number(n) => Ok(n.to_string()),
+            YamlValue::Bool(b) => Ok(b.to_string()),
+            YamlValue::Null => Ok("null".to_string()),
+            _ => Err(format!("不支持的配置键类型：{:?}", key)),
+        }
+    }
+
+    // 将 JS 值直接转换为 YAML 值
+    //
+    // 语义与 JSON.stringify 保持一致：undefined、函数和 Symbol 在对象中被忽略，
+    // 在数组中转换为 null；对象上的 toJSON 方法会被调用。
+    // 返回 None 表示该值应被忽略
+    fn js_to_yaml(
+        value: &JsValue,
+        context: &mut Context,
+        seen: &mut HashSet<JsObject>,
+    ) -> Result<Option<YamlValue>, String> {
+        let yaml_value = match value.variant() {
+            JsVariant::Undefined | JsVariant::Symbol(_) => return Ok(None),
+            JsVariant::Null => YamlValue::Null,
+            JsVariant::Boolean(b) => YamlValue::Bool(b),
+            JsVariant::String(s) => YamlValue::String(s.to_std_string_escaped()),
+            JsVariant::Integer32(i) => YamlValue::Number(i64::from(i).into()),
+            JsVariant::Float64(f) => YamlValue::Number(Self::float_to_number(f)),
+            JsVariant::BigInt(b) => {
+                let digits = b.to_string_radix(10);
+                let number = digits
+                    .parse::<i64>()
+                    .map(Number::from)
+                    .or_else(|_| digits.parse::<u64>().map(Number::from))
+                    .map_err(|_| format!("BigInt 数值超出范围：{}", digits))?;
+                YamlValue::Number(number)
+            }
+            JsVariant::Object(obj) => {
+                if obj.is_callable() {
+                    return Ok(None);
+                }
+
+                let to_json = obj
+                    .get(js_string!("toJSON"), context)
+                    .map_err(|e| format!("读取 toJSON 失败：{}", e))?;
+                if let Some(to_json) = to_json.as_callable() {
+                    let converted = to_json
+                        .call(value, &[], context)
+                        .map_err(|e| format!("调用 toJSON 失败：{}", e))?;
+                    if !converted.is_object() {
+                        return Self::js_to_yaml(&converted, context, seen);
+                    }
+                }
+
+                if !seen.insert(obj.clone()) {
+                    return Err("返回的配置存在循环引用".to_string());
+                }
+                let result = if obj.is_array() {
+                    Self::js_array_to_yaml(&obj, context, seen)
+                } else {
+                    Self::js_object_to_yaml(&obj, context, seen)
+                };
+                seen.remove(&obj);
+                result?
+            }
+        };
+
+        Ok(Some(yaml_value))
+    }
+
+    fn js_array_to_yaml(
+        obj: &JsObject,
+        context: &mut Context,
+        seen: &mut HashSet<JsObject>,
+    ) -> Result<YamlValue, String> {
+        let array =
+            JsArray::from_object(obj.clone()).map_err(|e| format!("读取数组失败：{}", e))?;
+        let len = array
+            .length(context)
+            .map_err(|e| format!("读取数组长度失败：{}", e))?;
+
+        let mut seq = Vec::with_capacity(len as usize);
+        for i in 0..len {
+            let item = array
+                .get(i, context)
+                .map_err(|e| format!("读取数组元素失败：{}", e))?;
+            seq.push(Self::js_to_yaml(&item, context, seen)?.unwrap_or(YamlValue::Null));
+        }
+
+        Ok(YamlValue::Sequence(seq))
+    }
+
+    fn js_object_to_yaml(
+        obj: &JsObject,
+        context: &mut Context,
+        seen: &mut HashSet<JsObject>,
+    ) -> Result<YamlValue, String> {
+        let keys = obj
+            .own_property_keys(context)
+            .map_err(|e| format!("读取对象属性失败：{}", e))?;
+
+        let mut map = Mapping::with_capacity(keys.len());
+        for key in keys {
+            let key_str = match &key {
+                PropertyKey::String(s) => s.to_std_string_escaped(),
+                PropertyKey::Index(i) => i.get().to_string(),
+                PropertyKey::Symbol(_) => continue,
+            };
+
+            let is_enumerable = obj
+                .borrow()
+                .properties()
+                .get(&key)
+                .and_then(|desc| desc.enumerable())
+                .unwrap_or(true);
+            if !is_enumerable {
+                continue;
+            }
+
+            let item = obj
+                .get(key, context)
+                .map_err(|e| format!("读取属性 {} 失败：{}", key_str, e))?;
+            if let Some(item) = Self::js_to_yaml(&item, context, seen)? {
+                map.insert(YamlValue::String(key_str), item);
+            }
+        }
+
+        Ok(YamlValue::Mapping(map))
+    }
+
+    // 整数值的浮点数（如 1.0）还原为整数，保持原配置中的整数类型
+    fn float_to_number(f: f64) -> Number {
+        if f.is_finite() && f.fract() == 0.0 && f.abs() <= MAX_SAFE_INTEGER as f64 {
+            Number::from(f as i64)
+        } else {
+            Number::from(f)
+        }
+    }
+
+    fn is_exponent_like(s: &str) -> bool {
+        let s = s.strip_prefix(['+', '-']).unwrap_or(s);
+        let Some((mantissa, exponent)) = s.split_once(['e', 'E']) else {
+            return false;
+        };
+        let exponent = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+        !mantissa.is_empty()
+            && mantissa.bytes().all(|b| b.is_ascii_digit())
+            && !exponent.is_empty()
+            && exponent.bytes().all(|b| b.is_ascii_digit())
+    }
+
+    // 收集配置中形如科学计数法的字符串值
+    fn collect_exponent_like_strings(value: &YamlValue, strings: &mut HashSet<String>) {
+        match value {
+            YamlValue::String(s) if Self::is_exponent_like(s) => {
+                strings.insert(s.clone());
+            }
+            YamlValue::Sequence(seq) => seq
+                .iter()
+                .for_each(|item| Self::collect_exponent_like_strings(item, strings)),
+            YamlValue::Mapping(map) => map
+                .values()
+                .for_each(|item| Self::collect_exponent_like_strings(item, strings)),
+            YamlValue::Tagged(tagged) => {
+                Self::collect_exponent_like_strings(&tagged.value, strings);
+            }
+            _ => {}
+        }
+    }
+
+    // 为形如 `key: 123e456` 或 `- 123e456` 且属于 strings 的值加上引号
+    //
+    // 序列化后的浮点数（如 1e300）与未加引号的字符串形式相同，只能按值区分：
+    // serde_yaml_ng 仅对无法解析为数值的字符串不加引号，因此两者不会重合
+    fn quote_exponent_like_strings(yaml: &str, strings: &HashSet<String>) -> String {
+        PLAIN_EXPONENT_SCALAR
+            .replace_all(yaml, |caps: &regex::Captures| {
+                if strings.contains(&caps[2]) {
+                    format!("{}\"{}\"", &caps[1], &caps[2])
+                } else {
+                    caps[0].to_string()
+                }
+            })
+            .to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(base: &str, js: &str) -> YamlValue {
+        let mut executor = JsExecutor::new().unwrap_or_else(|e| panic!("{}", e));
+        let output = executor
+            .apply(base, js, false)
+            .unwrap_or_else(|e| panic!("{}", e));
+        serde_yaml_ng::from_str(&output).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    #[test]
+    fn test_large_integers_and_numeric_strings_survive() {
+        let base =
+            "big: 12345678901234567890\nneg: -9007199254740993\nshort-id: '6314e825'\nport: 7890\n";
+        let result = run(base, "function main(config) { return config; }");
+
+        assert_eq!(result["big"].as_u64(), Some(12345678901234567890));
+        assert_eq!(result["neg"].as_i64(), Some(-9007199254740993));
+        assert_eq!(result["short-id"].as_str(), Some("6314e825"));
+        assert_eq!(result["port"].as_i64(), Some(7890));
+    }
+
+    #[test]
+    fn test_script_modifications_are_applied() {
+        let base = "proxies:\n  - name: a\n    ids: ['1e5']\n";
+        let js = "const tag = 'b';\nfunction main(config) {\n  config.proxies.push({ name: tag, skip: undefined });\n  config.mode = 'rule';\n  return config;\n}";
+        let result = run(base, js);
+
+        assert_eq!(result["mode"].as_str(), Some("rule"));
+        assert_eq!(result["proxies"][1]["name"].as_str(), Some("b"));
+        assert!(result["proxies"][1].get("skip").is_none());
+        assert_eq!(result["proxies"][0]["ids"][0].as_str(), Some("1e5"));
+    }
+
+    #[test]
+    fn test_exponent_like_strings_are_quoted() {
+        let yaml = "short-id: 6314e825\nids:\n- 1e5\nname: a1e5\nratio: 1e300\n";
+        let strings = HashSet::from(["6314e825".to_string(), "1e5".to_string()]);
+        let quoted = JsExecutor::quote_exponent_like_strings(yaml, &strings);
+
+        assert_eq!(
+            quoted,
+            "short-id: \"6314e825\"\nids:\n- \"1e5\"\nname: a1e5\nratio: 1e300\n"
+        );
+
+        // 字符串值（无论原配置中是否带引号）均加引号，浮点数保持原样
+        let base = "short-id: '6314e825'\nplain-id: 7314e825\nratio: 1e300\n";
+        let mut executor = JsExecutor::new().unwrap_or_else(|e| panic!("{}", e));
+        let output = executor
+            .apply(
+                base,
+                "function main(config) { config.added = '8314e825'; return config; }",
+                false,
+            )
+            .unwrap_or_else(|e| panic!("{}", e));
+        assert!(output.contains("short-id: \"6314e825\"\n"));
+        assert!(output.contains("plain-id: \"7314e825\"\n"));
+        assert!(output.contains("ratio: 1e300\n"));
+        assert!(output.contains("added: \"8314e825\"\n"));
+    }
+
+    #[test]
+    fn test_bigint_only_above_max_safe_integer() {
+        let base = "safe: 9007199254740991\nunsafe: 9007199254740992\nsmall: -7\n";
+        let js = "function main(config) {\n  config.types = [typeof config.safe, typeof config.unsafe, typeof config.small];\n  config.next = config.safe - 1;\n  return config;\n}";
+        let result = run(base, js);
+
+        assert_eq!(result["types"][0].as_str(), Some("number"));
+        assert_eq!(result["types"][1].as_str(), Some("bigint"));
+        assert_eq!(result["types"][2].as_str(), Some("number"));
+        assert_eq!(result["next"].as_i64(), Some(9007199254740990));
+        assert_eq!(result["unsafe"].as_u64(), Some(9007199254740992));
+    }
+
+    #[test]
+    fn test_module_script_imports_library() {
+        let mut executor = JsExecutor::new().unwrap_or_else(|e| panic!("{}", e));
+        executor.register_library(
+            "groups",
+            "export function regionGroups(names) { return names.map((n) => ({ name: n })); }",
+        );
+        let js = "import { regionGroups } from \"lib:groups\";\nexport function main(config) {\n  config['proxy-groups'] = regionGroups(['HK', 'JP']);\n  return config;\n}";
+
+        let output = executor
+            .apply("mode: rule\n", js, true)
+            .unwrap_or_else(|e| panic!("{}", e));
+        assert!(output.contains("name: JP"));
+
+        assert!(
+            executor
+                .apply(
+                    "a: 1\n",
+                    "import { x } from \"lib:missing\";\nexport function main(c) { return c; }",
+                    true
+                )
+                .is_err()
+        );
+
+        // 普通脚本格式不按模块执行，import 语句视为语法错误
+        assert!(executor.apply("mode: rule\n", js, false).is_err());
+    }
+
+    #[test]
+    fn test_top_level_declarations_do_not_leak_between_runs() {
+        let mut executor = JsExecutor::new().unwrap_or_else(|e| panic!("{}", e));
+        let js = "const value = 1;\nfunction main(config) { config.value = value; return config; }";
+
+        assert!(executor.apply("a: 1\n", js, false).is_ok());
+        assert!(executor.apply("a: 1\n", js, false).is_ok());
     }
 }
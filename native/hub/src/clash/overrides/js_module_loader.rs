@@ -0,0 +1,71 @@
+// JavaScript 覆写库模块加载器
+//
+// 目的：让模块格式的覆写脚本通过 `import { ... } from "lib:覆写ID"` 引用共享的库覆写，
+// 库源码来自本地覆写列表，首次导入时解析并在执行器内缓存
+
+use boa_engine::module::{ModuleLoader, Referrer};
+use boa_engine::{Context, JsNativeError, JsResult, JsString, Module, Source};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+// 库模块说明符前缀
+pub const LIBRARY_PREFIX: &str = "lib:";
+
+// 覆写库模块加载器
+#[derive(Default)]
+pub struct OverrideModuleLoader {
+    sources: RefCell<HashMap<String, String>>,
+    modules: RefCell<HashMap<String, Module>>,
+}
+
+impl OverrideModuleLoader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // 注册库源码，替换同名库时清除已解析的模块
+    pub fn register(&self, name: &str, source: &str) {
+        self.sources
+            .borrow_mut()
+            .insert(name.to_string(), source.to_string());
+        self.modules.borrow_mut().remove(name);
+    }
+
+    fn resolve(&self, specifier: &str, context: &mut Context) -> JsResult<Module> {
+        let name = specifier.strip_prefix(LIBRARY_PREFIX).ok_or_else(|| {
+            JsNativeError::typ().with_message(format!(
+                "仅支持导入覆写库（{}覆写ID）：{}",
+                LIBRARY_PREFIX, specifier
+            ))
+        })?;
+
+        if let Some(module) = self.modules.borrow().get(name) {
+            return Ok(module.clone());
+        }
+
+        let source = self.sources.borrow().get(name).cloned().ok_or_else(|| {
+            JsNativeError::typ().with_message(format!("未注册的覆写库：{}", name))
+        })?;
+
+        log::debug!("解析覆写库模块：{}", name);
+        let module = Module::parse(Source::from_bytes(&source), None, context)?;
+        self.modules
+            .borrow_mut()
+            .insert(name.to_string(), module.clone());
+
+        Ok(module)
+    }
+}
+
+impl ModuleLoader for OverrideModuleLoader {
+    async fn load_imported_module(
+        self: Rc<Self>,
+        _referrer: Referrer,
+        specifier: JsString,
+        context: &RefCell<&mut Context>,
+    ) -> JsResult<Module> {
+        let specifier = specifier.to_std_string_escaped();
+        self.resolve(&specifier, &mut context.borrow_mut())
+    }
+}
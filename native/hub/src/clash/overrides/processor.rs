@@ -1,17 +1,32 @@
 // 覆写处理器
 // 处理配置覆写（YAML 合并 + JavaScript 执行）
 
+use super::diff::OverrideStepDiff;
 use super::js_executor::JsExecutor;
+use super::scope::{OverrideMatchCondition, OverrideMatchContext};
+use super::template::TemplateVariables;
 use super::yaml_merger::YamlMerger;
+use crate::clash::config::runtime_params::RuntimeConfigParams;
 use crate::clash::subscription::ProxyParser;
 use rinf::{DartSignal, RustSignal, SignalPiece};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 // 覆写格式
 #[derive(Deserialize, Serialize, SignalPiece, Clone, Copy, Debug)]
 pub enum OverrideFormat {
     Yaml = 0,
     Javascript = 1,
+    JavascriptLibrary = 2, // 共享库，不直接应用，供模块脚本通过 `lib:覆写ID` 导入
+    JavascriptModule = 3,  // ES 模块脚本，可导入共享库，需导出 main 或默认导出函数
+}
+
+// 覆写失败时的处理策略
+#[derive(Deserialize, Serialize, SignalPiece, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverrideErrorPolicy {
+    Abort = 0, // 中止整个覆写链
+    Skip = 1,  // 跳过该覆写，继续后续覆写
+    Warn = 2,  // 跳过该覆写，并在响应日志中给出警告
 }
 
 // 覆写配置
@@ -21,6 +36,27 @@ pub struct OverrideConfig {
     pub name: String,
     pub format: OverrideFormat,
     pub content: String,
+    pub error_policy: OverrideErrorPolicy,
+    pub match_condition: Option<OverrideMatchCondition>, // 为空时对所有配置生效
+}
+
+// 覆写步骤状态
+#[derive(Deserialize, Serialize, SignalPiece, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverrideStepStatus {
+    Applied = 0,
+    Skipped = 1,
+    Failed = 2,
+    Unmatched = 3, // 不满足生效条件，未应用
+}
+
+// 单个覆写步骤的执行结果
+#[derive(Debug, Deserialize, Serialize, SignalPiece, Clone)]
+pub struct OverrideStepResult {
+    pub step_index: u32,
+    pub override_id: String,
+    pub override_name: String,
+    pub status: OverrideStepStatus,
+    pub error_message: String,
 }
 
 // Dart → Rust：应用覆写请求
@@ -28,6 +64,11 @@ pub struct OverrideConfig {
 pub struct ApplyOverridesRequest {
     pub base_config_content: String,
     pub overrides: Vec<OverrideConfig>,
+    pub match_context: OverrideMatchContext,
+    pub template_variables: HashMap<String, String>, // YAML 覆写中 `${name}` 的取值
+    pub allowed_env_variables: Vec<String>,          // 允许 `${env.NAME}` 读取的环境变量
+    pub runtime_params: Option<RuntimeConfigParams>, // `${runtime.*}` 的取值，未提供时按原文保留
+    pub is_diff_enabled: bool,                       // 是否记录每个覆写步骤的差异
 }
 
 // Rust → Dart：应用覆写响应
@@ -37,6 +78,8 @@ pub struct ApplyOverridesResponse {
     pub result_config: String,
     pub error_message: String,
     pub logs: Vec<String>,
+    pub step_diffs: Vec<OverrideStepDiff>,
+    pub step_results: Vec<OverrideStepResult>,
 }
 
 // Dart → Rust：解析订阅请求
@@ -68,12 +111,22 @@ impl ApplyOverridesRequest {
                     result_config: String::new(),
                     error_message: format!("初始化处理器失败：{}", e),
                     logs: vec![],
+                    step_diffs: vec![],
+                    step_results: vec![],
                 };
                 response.send_signal_to_dart();
                 return;
             }
         };
 
+        let mut template_variables = TemplateVariables::new()
+            .with_user_variables(&self.template_variables)
+            .with_allowed_env_variables(&self.allowed_env_variables);
+        if let Some(params) = &self.runtime_params {
+            template_variables = template_variables.with_runtime_params(params);
+        }
+        processor.set_template_variables(template_variables);
+
         // 先解析订阅内容为标准 Clash 配置
         let parsed_config = match ProxyParser::parse_subscription(&self.base_config_content) {
             Ok(config) => config,
@@ -84,6 +137,8 @@ impl ApplyOverridesRequest {
                     result_config: String::new(),
                     error_message: format!("订阅解析失败：{}", e),
                     logs: vec![],
+                    step_diffs: vec![],
+                    step_results: vec![],
                 };
                 response.send_signal_to_dart();
                 return;
@@ -92,24 +147,35 @@ impl ApplyOverridesRequest {
 
         log::info!("订阅解析成功，配置长度：{}字节", parsed_config.len());
 
-        match processor.apply_overrides(&parsed_config, self.overrides) {
+        match processor.apply_overrides(
+            &parsed_config,
+            self.overrides,
+            &self.match_context,
+            self.is_diff_enabled,
+        ) {
             Ok(result) => {
                 log::info!("覆写处理成功");
+                let mut logs = result.warnings;
+                logs.push("处理成功".to_string());
                 let response = ApplyOverridesResponse {
                     is_successful: true,
-                    result_config: result,
+                    result_config: result.config,
                     error_message: String::new(),
-                    logs: vec!["处理成功".to_string()],
+                    logs,
+                    step_diffs: result.step_diffs,
+                    step_results: result.step_results,
                 };
                 response.send_signal_to_dart();
             }
             Err(e) => {
-                log::error!("覆写处理失败：{}", e);
+                log::error!("覆写处理失败：{}", e.message);
                 let response = ApplyOverridesResponse {
                     is_successful: false,
                     result_config: String::new(),
-                    error_message: e,
+                    error_message: e.message,
                     logs: vec![],
+                    step_diffs: vec![],
+                    step_results: e.step_results,
                 };
                 response.send_signal_to_dart();
             }
@@ -155,10 +221,27 @@ impl ParseSubscriptionRequest {
     }
 }
 
+// 覆写处理结果
+pub struct OverrideApplyResult {
+    pub config: String,
+    pub step_diffs: Vec<OverrideStepDiff>,
+    pub step_results: Vec<OverrideStepResult>,
+    pub warnings: Vec<String>,
+}
+
+// 覆写链中止错误
+//
+// 携带中止前已执行步骤的结果，便于定位失败的覆写
+pub struct OverrideChainError {
+    pub message: String,
+    pub step_results: Vec<OverrideStepResult>,
+}
+
 // 覆写处理器
 pub struct OverrideProcessor {
     yaml_merger: YamlMerger,
     js_executor: JsExecutor,
+    template_variables: TemplateVariables,
 }
 
 impl OverrideProcessor {
@@ -173,41 +256,275 @@ impl OverrideProcessor {
         Ok(Self {
             yaml_merger,
             js_executor,
+            template_variables: TemplateVariables::new(),
         })
     }
 
+    // 设置 YAML 覆写的模板变量
+    pub fn set_template_variables(&mut self, variables: TemplateVariables) {
+        self.template_variables = variables;
+    }
+
     // 应用所有覆写到基础配置
     //
     // 目的：按顺序应用每个覆写，返回最终配置
+    // 不满足 match_condition 的覆写不会被应用
+    // 覆写失败时按其 error_policy 决定中止或跳过
+    // 启用 is_diff_enabled 时记录每个成功步骤前后的配置差异
     pub fn apply_overrides(
         &mut self,
         base_config: &str,
         overrides: Vec<OverrideConfig>,
-    ) -> Result<String, String> {
+        context: &OverrideMatchContext,
+        is_diff_enabled: bool,
+    ) -> Result<OverrideApplyResult, OverrideChainError> {
         let mut current_config = base_config.to_string();
+        let mut step_diffs = Vec::new();
+        let mut step_results = Vec::with_capacity(overrides.len());
+        let mut warnings = Vec::new();
+
+        // 先注册所有库覆写，使其在整个覆写链中可被导入
+        // 以覆写 ID 作为库名称，名称仅用于显示，可能重名或被修改
+        for library in overrides
+            .iter()
+            .filter(|o| matches!(o.format, OverrideFormat::JavascriptLibrary))
+        {
+            self.js_executor
+                .register_library(&library.id, &library.content);
+        }
 
         for (i, override_cfg) in overrides.iter().enumerate() {
-            log::info!(
-                "[{}] 应用覆写：{}（{:?}）",
-                i,
-                override_cfg.name,
-                override_cfg.format
-            );
-
-            current_config = match override_cfg.format {
-                OverrideFormat::Yaml => self
-                    .yaml_merger
-                    .apply(&current_config, &override_cfg.content)
-                    .map_err(|e| format!("YAML 覆写失败：{}", e))?,
-                OverrideFormat::Javascript => self
-                    .js_executor
-                    .apply(&current_config, &override_cfg.content)
-                    .map_err(|e| format!("JavaScript 覆写失败：{}", e))?,
+            if matches!(override_cfg.format, OverrideFormat::JavascriptLibrary) {
+                continue;
+            }
+
+            let mut step_result = OverrideStepResult {
+                step_index: i as u32,
+                override_id: override_cfg.id.clone(),
+                override_name: override_cfg.name.clone(),
+                status: OverrideStepStatus::Applied,
+                error_message: String::new(),
             };
 
+            let next_config =
+                match self.try_apply(&current_config, override_cfg, context, &mut warnings) {
+                    Ok(Some(config)) => config,
+                    Ok(None) => {
+                        log::info!("[{}] 覆写不满足生效条件，跳过：{}", i, override_cfg.name);
+                        step_result.status = OverrideStepStatus::Unmatched;
+                        step_results.push(step_result);
+                        continue;
+                    }
+                    Err(e) => {
+                        step_result.error_message = e.clone();
+                        match override_cfg.error_policy {
+                            OverrideErrorPolicy::Abort => {
+                                log::error!("[{}] 覆写失败，中止覆写链：{}", i, e);
+                                step_result.status = OverrideStepStatus::Failed;
+                                step_results.push(step_result);
+                                return Err(OverrideChainError {
+                                    message: e,
+                                    step_results,
+                                });
+                            }
+                            OverrideErrorPolicy::Skip => {
+                                log::info!("[{}] 覆写失败，已跳过：{}", i, e);
+                            }
+                            OverrideErrorPolicy::Warn => {
+                                log::warn!("[{}] 覆写失败，已跳过：{}", i, e);
+                                warnings.push(format!("覆写 {} 已跳过：{}", override_cfg.name, e));
+                            }
+                        }
+                        step_result.status = OverrideStepStatus::Skipped;
+                        step_results.push(step_result);
+                        continue;
+                    }
+                };
+
+            if is_diff_enabled {
+                let mut diff =
+                    OverrideStepDiff::compute(&current_config, &next_config).map_err(|e| {
+                        OverrideChainError {
+                            message: e,
+                            step_results: step_results.clone(),
+                        }
+                    })?;
+                diff.step_index = i as u32;
+                diff.override_id = override_cfg.id.clone();
+                diff.override_name = override_cfg.name.clone();
+                step_diffs.push(diff);
+            }
+
+            current_config = next_config;
+            step_results.push(step_result);
             log::info!("[{}] 覆写应用成功", i);
         }
 
-        Ok(current_config)
+        Ok(OverrideApplyResult {
+            config: current_config,
+            step_diffs,
+            step_results,
+            warnings,
+        })
+    }
+
+    // 在满足生效条件时应用单个覆写，不满足时返回 None
+    fn try_apply(
+        &mut self,
+        current_config: &str,
+        override_cfg: &OverrideConfig,
+        context: &OverrideMatchContext,
+        warnings: &mut Vec<String>,
+    ) -> Result<Option<String>, String> {
+        if let Some(condition) = &override_cfg.match_condition
+            && !condition.matches(context)?
+        {
+            return Ok(None);
+        }
+
+        log::info!(
+            "应用覆写：{}（{:?}）",
+            override_cfg.name,
+            override_cfg.format
+        );
+
+        self.apply_single(current_config, override_cfg, warnings)
+            .map(Some)
+    }
+
+    // 应用单个覆写
+    //
+    // YAML 覆写中无法解析的模板占位符按原文保留，并记入 warnings
+    fn apply_single(
+        &mut self,
+        current_config: &str,
+        override_cfg: &OverrideConfig,
+        warnings: &mut Vec<String>,
+    ) -> Result<String, String> {
+        match override_cfg.format {
+            OverrideFormat::Yaml => {
+                let (content, unresolved) = self
+                    .template_variables
+                    .render_yaml(&override_cfg.content)
+                    .map_err(|e| format!("YAML 覆写模板替换失败：{}", e))?;
+                if !unresolved.is_empty() {
+                    log::warn!(
+                        "覆写 {} 中的模板占位符无法解析，已按原文保留：{}",
+                        override_cfg.name,
+                        unresolved.join("、")
+                    );
+                    warnings.push(format!(
+                        "覆写 {} 中的模板占位符无法解析，已按原文保留：{}",
+                        override_cfg.name,
+                        unresolved.join("、")
+                    ));
+                }
+                self.yaml_merger
+                    .apply(current_config, &content)
+                    .map_err(|e| format!("YAML 覆写失败：{}", e))
+            }
+            OverrideFormat::Javascript => self
+                .js_executor
+                .apply(current_config, &override_cfg.content, false)
+                .map_err(|e| format!("JavaScript 覆写失败：{}", e)),
+            OverrideFormat::JavascriptModule => self
+                .js_executor
+                .apply(current_config, &override_cfg.content, true)
+                .map_err(|e| format!("JavaScript 覆写失败：{}", e)),
+            OverrideFormat::JavascriptLibrary => Ok(current_config.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn yaml_override(
+        name: &str,
+        content: &str,
+        error_policy: OverrideErrorPolicy,
+    ) -> OverrideConfig {
+        OverrideConfig {
+            id: name.to_string(),
+            name: name.to_string(),
+            format: OverrideFormat::Yaml,
+            content: content.to_string(),
+            error_policy,
+            match_condition: None,
+        }
+    }
+
+    #[test]
+    fn test_error_policy_controls_chain() {
+        let Ok(mut processor) = OverrideProcessor::new() else {
+            panic!("初始化覆写处理器失败");
+        };
+        let context = OverrideMatchContext::default();
+        let overrides = vec![
+            yaml_override("broken", "mode: [", OverrideErrorPolicy::Warn),
+            yaml_override("mode", "mode: global", OverrideErrorPolicy::Abort),
+        ];
+
+        let Ok(result) = processor.apply_overrides("mode: rule\n", overrides, &context, false)
+        else {
+            panic!("Warn 策略不应中止覆写链");
+        };
+        assert!(result.config.contains("mode: global"));
+        assert_eq!(result.warnings.len(), 1);
+        assert_eq!(result.step_results[0].status, OverrideStepStatus::Skipped);
+        assert_eq!(result.step_results[1].status, OverrideStepStatus::Applied);
+
+        let overrides = vec![yaml_override(
+            "broken",
+            "mode: [",
+            OverrideErrorPolicy::Abort,
+        )];
+        let Err(error) = processor.apply_overrides("mode: rule\n", overrides, &context, false)
+        else {
+            panic!("Abort 策略应中止覆写链");
+        };
+        assert_eq!(error.step_results[0].status, OverrideStepStatus::Failed);
+    }
+
+    #[test]
+    fn test_libraries_keyed_by_id() {
+        let Ok(mut processor) = OverrideProcessor::new() else {
+            panic!("初始化覆写处理器失败");
+        };
+        let context = OverrideMatchContext::default();
+        let script = |id: &str, format: OverrideFormat, content: &str| OverrideConfig {
+            id: id.to_string(),
+            name: "common.js".to_string(),
+            format,
+            content: content.to_string(),
+            error_policy: OverrideErrorPolicy::Abort,
+            match_condition: None,
+        };
+        // 两个库显示名称相同，按 ID 区分
+        let overrides = vec![
+            script(
+                "lib-a",
+                OverrideFormat::JavascriptLibrary,
+                "export const mode = 'global';",
+            ),
+            script(
+                "lib-b",
+                OverrideFormat::JavascriptLibrary,
+                "export const mode = 'direct';",
+            ),
+            script(
+                "main",
+                OverrideFormat::JavascriptModule,
+                "import { mode } from \"lib:lib-b\";\nexport function main(config) { config.mode = mode; return config; }",
+            ),
+        ];
+
+        let Ok(result) = processor.apply_overrides("mode: rule\n", overrides, &context, false)
+        else {
+            panic!("模块脚本应能导入库");
+        };
+        assert!(result.config.contains("mode: direct"));
+        assert_eq!(result.step_results.len(), 1);
     }
 }
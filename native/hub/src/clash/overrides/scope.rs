@@ -0,0 +1,113 @@
+// 覆写作用域
+//
+// 目的：根据订阅、平台和 TUN 状态决定覆写是否生效，
+// 使全局覆写与特定订阅的覆写可以放在同一列表中
+
+use regex::Regex;
+use rinf::SignalPiece;
+use serde::{Deserialize, Serialize};
+
+// 覆写生效条件
+//
+// 所有已设置的条件都满足时覆写才会生效；未设置的条件不做限制
+#[derive(Debug, Default, Deserialize, Serialize, SignalPiece, Clone)]
+pub struct OverrideMatchCondition {
+    pub subscription_name_pattern: Option<String>, // 订阅名称正则
+    pub subscription_url_pattern: Option<String>,  // 订阅 URL 正则
+    pub platforms: Vec<String>,                    // "windows" | "linux" | "macos" 等，空表示全部
+    pub is_tun_enabled: Option<bool>,              // 仅在 TUN 开启/关闭时生效
+}
+
+// 覆写匹配上下文（当前正在生成配置的订阅及运行状态）
+#[derive(Debug, Default, Deserialize, Serialize, SignalPiece, Clone)]
+pub struct OverrideMatchContext {
+    pub subscription_name: String,
+    pub subscription_url: String,
+    pub is_tun_enabled: bool,
+}
+
+impl OverrideMatchCondition {
+    // 判断条件是否满足
+    //
+    // 正则表达式无效时返回错误，由调用方按覆写的错误策略处理
+    pub fn matches(&self, context: &OverrideMatchContext) -> Result<bool, String> {
+        if let Some(pattern) = &self.subscription_name_pattern
+            && !Self::is_match(pattern, &context.subscription_name)?
+        {
+            return Ok(false);
+        }
+
+        if let Some(pattern) = &self.subscription_url_pattern
+            && !Self::is_match(pattern, &context.subscription_url)?
+        {
+            return Ok(false);
+        }
+
+        if !self.platforms.is_empty()
+            && !self
+                .platforms
+                .iter()
+                .any(|p| p.eq_ignore_ascii_case(std::env::consts::OS))
+        {
+            return Ok(false);
+        }
+
+        if let Some(is_tun_enabled) = self.is_tun_enabled
+            && is_tun_enabled != context.is_tun_enabled
+        {
+            return Ok(false);
+        }
+
+        Ok(true)
+    }
+
+    fn is_match(pattern: &str, text: &str) -> Result<bool, String> {
+        let re =
+            Regex::new(pattern).map_err(|e| format!("匹配条件正则无效（{}）：{}", pattern, e))?;
+        Ok(re.is_match(text))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_condition_matching() {
+        let context = OverrideMatchContext {
+            subscription_name: "Provider A".to_string(),
+            subscription_url: "https://a.example.com/sub".to_string(),
+            is_tun_enabled: true,
+        };
+
+        assert_eq!(
+            OverrideMatchCondition::default().matches(&context),
+            Ok(true)
+        );
+
+        let condition = OverrideMatchCondition {
+            subscription_url_pattern: Some(r"a\.example\.com".to_string()),
+            is_tun_enabled: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(condition.matches(&context), Ok(true));
+
+        let condition = OverrideMatchCondition {
+            subscription_name_pattern: Some("^Provider B".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(condition.matches(&context), Ok(false));
+
+        let condition = OverrideMatchCondition {
+            platforms: vec!["no-such-os".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(condition.matches(&context), Ok(false));
+
+        let condition = OverrideMatchCondition {
+            subscription_name_pattern: Some("(".to_string()),
+            ..Default::default()
+        };
+        assert!(condition.matches(&context).is_err());
+    }
+}
@@ -0,0 +1,288 @@
+// YAML 覆写模板变量
+//
+// 目的：在合并前替换覆写内容中的 `${...}` 占位符
+//
+// 支持的占位符：
+// - `${runtime.mixed_port}` 等：来自运行时参数
+// - `${env.NAME}`：来自环境变量，仅允许读取请求中列出的变量名
+//   （远程下载的覆写同样会被渲染，不能任意读取环境变量写入配置）
+// - `${name}`：来自用户自定义变量
+// - `${name:-默认值}`：变量不存在时使用默认值
+// - `$${`：输出字面量 `${`
+//
+// 替换在解析后的 YAML 标量上进行，变量值中的换行、`: ` 等不会注入新的键；
+// 占位符需位于块样式的标量（或带引号的字符串）中
+//
+// 无法解析的占位符按原文保留，仅作为警告返回，不会使覆写失败
+
+use crate::clash::config::runtime_params::RuntimeConfigParams;
+use serde_yaml_ng::Value as YamlValue;
+use std::collections::{HashMap, HashSet};
+
+const ENV_PREFIX: &str = "env.";
+
+// 模板变量集合
+#[derive(Debug, Default, Clone)]
+pub struct TemplateVariables {
+    values: HashMap<String, String>,
+    allowed_env_variables: HashSet<String>,
+}
+
+impl TemplateVariables {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // 添加用户自定义变量
+    pub fn with_user_variables(mut self, variables: &HashMap<String, String>) -> Self {
+        for (key, value) in variables {
+            self.values.insert(key.clone(), value.clone());
+        }
+        self
+    }
+
+    // 允许通过 `${env.NAME}` 读取的环境变量
+    pub fn with_allowed_env_variables(mut self, names: &[String]) -> Self {
+        self.allowed_env_variables.extend(names.iter().cloned());
+        self
+    }
+
+    // 添加运行时参数变量（以 `runtime.` 为前缀）
+    pub fn with_runtime_params(mut self, params: &RuntimeConfigParams) -> Self {
+        let runtime_values = [
+            ("mixed_port", params.http_port.to_string()),
+            ("tun_device", params.tun_device.clone()),
+            ("tun_stack", params.tun_stack.clone()),
+            ("is_tun_enabled", params.is_tun_enabled.to_string()),
+            ("is_ipv6_enabled", params.is_ipv6_enabled.to_string()),
+            (
+                "is_allow_lan_enabled",
+                params.is_allow_lan_enabled.to_string(),
+            ),
+            ("outbound_mode", params.outbound_mode.clone()),
+            ("log_level", params.clash_core_log_level.clone()),
+        ];
+
+        for (key, value) in runtime_values {
+            self.values.insert(format!("runtime.{}", key), value);
+        }
+        self
+    }
+
+    // 替换 YAML 覆写中的占位符，返回序列化后的 YAML 和未能解析的占位符
+    pub fn render_yaml(&self, content: &str) -> Result<(String, Vec<String>), String> {
+        if !content.contains("${") {
+            return Ok((content.to_string(), Vec::new()));
+        }
+
+        let mut value: YamlValue =
+            serde_yaml_ng::from_str(content).map_err(|e| format!("解析覆写内容失败：{}", e))?;
+        let mut unresolved = Vec::new();
+        self.render_value(&mut value, &mut unresolved);
+        let rendered =
+            serde_yaml_ng::to_string(&value).map_err(|e| format!("序列化覆写内容失败：{}", e))?;
+        Ok((rendered, unresolved))
+    }
+
+    fn render_value(&self, value: &mut YamlValue, unresolved: &mut Vec<String>) {
+        match value {
+            YamlValue::String(text) => *value = self.render_scalar(text, unresolved),
+            YamlValue::Sequence(items) => {
+                for item in items {
+                    self.render_value(item, unresolved);
+                }
+            }
+            YamlValue::Mapping(mapping) => {
+                for (key, mut item) in std::mem::take(mapping) {
+                    let key = match key {
+                        YamlValue::String(text) => {
+                            YamlValue::String(self.render(&text, unresolved))
+                        }
+                        key => key,
+                    };
+                    self.render_value(&mut item, unresolved);
+                    mapping.insert(key, item);
+                }
+            }
+            YamlValue::Tagged(tagged) => self.render_value(&mut tagged.value, unresolved),
+            _ => {}
+        }
+    }
+
+    // 标量恰好是单个占位符且取值为数字或布尔值时按原类型输出（如 `port: ${runtime.mixed_port}`），
+    // 其余情况一律输出为字符串
+    fn render_scalar(&self, text: &str, unresolved: &mut Vec<String>) -> YamlValue {
+        let rendered = self.render(text, unresolved);
+
+        let is_single_placeholder = text.starts_with("${")
+            && text.find('}') == Some(text.len() - 1)
+            && text.matches("${").count() == 1;
+        if is_single_placeholder
+            && let Ok(typed @ (YamlValue::Bool(_) | YamlValue::Number(_))) =
+                serde_yaml_ng::from_str::<YamlValue>(&rendered)
+            && serde_yaml_ng::to_string(&typed).is_ok_and(|s| s.trim_end() == rendered)
+        {
+            return typed;
+        }
+
+        YamlValue::String(rendered)
+    }
+
+    // 替换文本中的占位符
+    //
+    // 无法解析的占位符（未定义、不在环境变量允许列表中或未闭合）按原文保留，
+    // 并记录到 unresolved 中，由调用方作为警告返回
+    pub fn render(&self, text: &str, unresolved: &mut Vec<String>) -> String {
+        if !text.contains("${") {
+            return text.to_string();
+        }
+
+        let mut output = String::with_capacity(text.len());
+        let mut rest = text;
+
+        while let Some(pos) = rest.find("${") {
+            // `$${` 转义为字面量 `${`
+            if rest[..pos].ends_with('$') {
+                output.push_str(&rest[..pos - 1]);
+                output.push_str("${");
+                rest = &rest[pos + 2..];
+                continue;
+            }
+
+            output.push_str(&rest[..pos]);
+            let after = &rest[pos + 2..];
+            let Some(end) = after.find('}') else {
+                record_unresolved(unresolved, &rest[pos..]);
+                output.push_str(&rest[pos..]);
+                return output;
+            };
+
+            let placeholder = &rest[pos..pos + 2 + end + 1];
+            match self.resolve(after[..end].trim()) {
+                Some(value) => output.push_str(&value),
+                None => {
+                    record_unresolved(unresolved, placeholder);
+                    output.push_str(placeholder);
+                }
+            }
+            rest = &after[end + 1..];
+        }
+
+        output.push_str(rest);
+        output
+    }
+
+    fn resolve(&self, expression: &str) -> Option<String> {
+        let (name, default) = match expression.split_once(":-") {
+            Some((name, default)) => (name.trim(), Some(default)),
+            None => (expression, None),
+        };
+
+        if name.is_empty() {
+            return None;
+        }
+
+        let value = match name.strip_prefix(ENV_PREFIX) {
+            Some(env_name) if !self.allowed_env_variables.contains(env_name) => None,
+            Some(env_name) => std::env::var(env_name).ok(),
+            None => self.values.get(name).cloned(),
+        };
+
+        value.or_else(|| default.map(str::to_string))
+    }
+}
+
+fn record_unresolved(unresolved: &mut Vec<String>, placeholder: &str) {
+    if !unresolved.iter().any(|p| p == placeholder) {
+        unresolved.push(placeholder.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_placeholders() {
+        let mut user = HashMap::new();
+        user.insert("iface".to_string(), "eth0".to_string());
+        let variables = TemplateVariables::new().with_user_variables(&user);
+        let mut unresolved = Vec::new();
+
+        assert_eq!(
+            variables.render(
+                "interface-name: ${iface}\nport: ${port:-7890}\n",
+                &mut unresolved
+            ),
+            "interface-name: eth0\nport: 7890\n"
+        );
+        assert_eq!(
+            variables.render("literal: $${iface}", &mut unresolved),
+            "literal: ${iface}"
+        );
+        assert!(unresolved.is_empty());
+
+        // 无法解析的占位符按原文保留并记录
+        assert_eq!(
+            variables.render("missing: ${nope} ${nope}", &mut unresolved),
+            "missing: ${nope} ${nope}"
+        );
+        assert_eq!(
+            variables.render("broken: ${iface", &mut unresolved),
+            "broken: ${iface"
+        );
+        assert_eq!(unresolved, vec!["${nope}", "${iface"]);
+    }
+
+    #[test]
+    fn test_env_allow_list() {
+        let Some((name, _)) = std::env::vars().next() else {
+            return;
+        };
+        let expression = format!("${{env.{}}}", name);
+        let mut unresolved = Vec::new();
+
+        let variables = TemplateVariables::new();
+        assert_eq!(variables.render(&expression, &mut unresolved), expression);
+        assert_eq!(unresolved, vec![expression.clone()]);
+        assert_eq!(
+            variables.render(&format!("${{env.{}:-none}}", name), &mut unresolved),
+            "none"
+        );
+
+        let variables =
+            TemplateVariables::new().with_allowed_env_variables(std::slice::from_ref(&name));
+        assert_eq!(
+            variables.render(&expression, &mut unresolved),
+            std::env::var(&name).unwrap_or_default()
+        );
+    }
+
+    #[test]
+    fn test_render_yaml_scalars() {
+        let mut user = HashMap::new();
+        user.insert("port".to_string(), "7891".to_string());
+        user.insert("code".to_string(), "0123".to_string());
+        user.insert("evil".to_string(), "x\nsecret: leaked\ny: z".to_string());
+        let variables = TemplateVariables::new().with_user_variables(&user);
+
+        let Ok((rendered, unresolved)) = variables.render_yaml(
+            "mixed-port: ${port}\nname: node-${port}\npassword: ${code}\ninterface-name: ${evil}\n",
+        ) else {
+            panic!("渲染失败");
+        };
+        let Ok(value) = serde_yaml_ng::from_str::<YamlValue>(&rendered) else {
+            panic!("渲染结果不是有效的 YAML：{}", rendered);
+        };
+
+        assert_eq!(value["mixed-port"].as_u64(), Some(7891));
+        assert_eq!(value["name"].as_str(), Some("node-7891"));
+        assert_eq!(value["password"].as_str(), Some("0123"));
+        assert_eq!(
+            value["interface-name"].as_str(),
+            Some("x\nsecret: leaked\ny: z")
+        );
+        assert!(value.get("secret").is_none());
+        assert!(unresolved.is_empty());
+    }
+}
@@ -279,6 +279,16 @@ impl StartClashProcess {
                 let pid = process.pid();
                 *manager = Some(process);
 
+                // 记录运行配置的端口，重新生成配置时不将其视为冲突
+                if let Some(config_path) = self
+                    .args
+                    .iter()
+                    .position(|arg| arg == "-f")
+                    .and_then(|index| self.args.get(index + 1))
+                {
+                    super::config::port_probe::record_running_core_config(config_path);
+                }
+
                 log::info!("Clash 进程启动成功，PID：{}", pid);
                 ClashProcessResult {
                     is_successful: true,
@@ -314,6 +324,7 @@ impl StopClashProcess {
             Some(process) => match process.stop() {
                 Ok(()) => {
                     log::info!("Clash 进程已停止");
+                    super::config::port_probe::clear_running_core_ports();
 
                     // 异步清理网络资源（IPC 连接池和 WebSocket）
                     tokio::spawn(async {
@@ -1002,6 +1002,9 @@ impl StartClash {
         {
             Ok(pid) => {
                 log::info!("通过服务启动 Clash 成功，PID：{:?}", pid);
+
+                // 核心以 root 运行，无法识别其端口的所属进程，按运行配置记录
+                super::config::port_probe::record_running_core_config(&self.config_path);
                 ClashProcessResult {
                     is_successful: true,
                     error_message: None,
@@ -1041,6 +1044,7 @@ impl StopClash {
         match service_manager.stop_clash().await {
             Ok(()) => {
                 log::info!("通过服务停止 Clash 成功");
+                super::config::port_probe::clear_running_core_ports();
 
                 // 异步清理网络资源（IPC 连接池和 WebSocket）
                 tokio::spawn(async {
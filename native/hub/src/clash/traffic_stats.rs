@@ -0,0 +1,619 @@
+// 流量统计
+//
+// 目的：将核心推送的实时流量累计为持久化的小时、天、月总量，
+// 并在可能时按代理节点和进程细分，方便用户核对按流量计费的套餐
+//
+// - 总量：累加 /traffic 每秒推送的上下行字节数
+// - 细分：对比 /connections 快照中各连接的字节增量，按出口节点（chains 首项）和进程归类
+//   （连接在两次快照之间关闭时，最后一段流量无法计入细分，因此细分之和可能略小于总量）
+// - 数据保存在应用数据目录的 traffic_stats.json，定期及停止统计时写入
+
+use once_cell::sync::Lazy;
+use rinf::{DartSignal, RustSignal, SignalPiece};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::spawn;
+use tokio::task::JoinHandle;
+
+use chrono::{DateTime, Local};
+
+use crate::clash::network::api_client::ConnectionsSnapshot;
+use crate::clash::network::connection_tracker::ConnectionTracker;
+use crate::clash::network::{subscribe_ws, unsubscribe_ws};
+
+const STATS_FILE_NAME: &str = "traffic_stats.json";
+
+// 写入间隔
+const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+// 保留的统计条数（月统计全部保留）
+const HOURLY_RETENTION: usize = 24 * 31;
+const DAILY_RETENTION: usize = 366 * 2;
+
+// 统计周期
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, SignalPiece, PartialEq, Eq)]
+pub enum TrafficPeriod {
+    Hour = 0,  // 键格式 "2026-01-31 08"
+    Day = 1,   // 键格式 "2026-01-31"
+    Month = 2, // 键格式 "2026-01"
+}
+
+// 单项流量用量（节点或进程）
+#[derive(Debug, Clone, Serialize, Deserialize, SignalPiece, PartialEq, Eq)]
+pub struct TrafficUsage {
+    pub name: String,
+    pub upload: u64,
+    pub download: u64,
+}
+
+// 单个统计周期的流量
+#[derive(Debug, Clone, Serialize, Deserialize, SignalPiece, PartialEq, Eq)]
+pub struct TrafficStatsBucket {
+    pub key: String,
+    pub upload: u64,
+    pub download: u64,
+    pub proxies: Vec<TrafficUsage>,   // 按总流量降序
+    pub processes: Vec<TrafficUsage>, // 按总流量降序
+}
+
+// Dart → Rust：开始流量统计（核心启动后发送）
+#[derive(Deserialize, DartSignal)]
+pub struct StartTrafficAccounting {
+    pub data_dir: String, // 应用数据目录
+}
+
+// Dart → Rust：停止流量统计
+#[derive(Deserialize, DartSignal)]
+pub struct StopTrafficAccounting;
+
+// Rust → Dart：流量统计启停结果
+#[derive(Serialize, RustSignal)]
+pub struct TrafficAccountingResult {
+    pub is_successful: bool,
+    pub error_message: Option<String>,
+}
+
+// Dart → Rust：查询流量统计
+#[derive(Deserialize, DartSignal)]
+pub struct GetTrafficStatsRequest {
+    pub data_dir: String, // 统计未运行时从该目录读取
+    pub period: TrafficPeriod,
+    pub start: String, // 起始键（含），为空表示不限
+    pub end: String,   // 结束键（含），为空表示不限
+}
+
+// Rust → Dart：流量统计查询结果（按时间升序）
+#[derive(Serialize, RustSignal)]
+pub struct GetTrafficStatsResponse {
+    pub period: TrafficPeriod,
+    pub buckets: Vec<TrafficStatsBucket>,
+    pub error_message: Option<String>,
+}
+
+// 持久化的上下行总量
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+struct TrafficTotals {
+    upload: u64,
+    download: u64,
+}
+
+impl TrafficTotals {
+    fn add(&mut self, upload: u64, download: u64) {
+        self.upload = self.upload.saturating_add(upload);
+        self.download = self.download.saturating_add(download);
+    }
+}
+
+// 持久化的单个周期统计
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct TrafficBucket {
+    #[serde(default)]
+    totals: TrafficTotals,
+    #[serde(default)]
+    proxies: BTreeMap<String, TrafficTotals>,
+    #[serde(default)]
+    processes: BTreeMap<String, TrafficTotals>,
+}
+
+// 流量账本（键按时间顺序排列）
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct TrafficLedger {
+    #[serde(default)]
+    hourly: BTreeMap<String, TrafficBucket>,
+    #[serde(default)]
+    daily: BTreeMap<String, TrafficBucket>,
+    #[serde(default)]
+    monthly: BTreeMap<String, TrafficBucket>,
+}
+
+impl TrafficLedger {
+    // 获取某一时刻所在的小时、天、月统计
+    fn buckets_at(&mut self, time: DateTime<Local>) -> [&mut TrafficBucket; 3] {
+        [
+            self.hourly
+                .entry(time.format("%Y-%m-%d %H").to_string())
+                .or_default(),
+            self.daily
+                .entry(time.format("%Y-%m-%d").to_string())
+                .or_default(),
+            self.monthly
+                .entry(time.format("%Y-%m").to_string())
+                .or_default(),
+        ]
+    }
+
+    fn record_total(&mut self, time: DateTime<Local>, upload: u64, download: u64) {
+        for bucket in self.buckets_at(time) {
+            bucket.totals.add(upload, download);
+        }
+        self.prune();
+    }
+
+    fn record_usage(
+        &mut self,
+        time: DateTime<Local>,
+        proxy: &str,
+        process: &str,
+        upload: u64,
+        download: u64,
+    ) {
+        for bucket in self.buckets_at(time) {
+            if !proxy.is_empty() {
+                bucket
+                    .proxies
+                    .entry(proxy.to_string())
+                    .or_default()
+                    .add(upload, download);
+            }
+            if !process.is_empty() {
+                bucket
+                    .processes
+                    .entry(process.to_string())
+                    .or_default()
+                    .add(upload, download);
+            }
+        }
+    }
+
+    // 删除超出保留条数的最早统计
+    fn prune(&mut self) {
+        while self.hourly.len() > HOURLY_RETENTION {
+            self.hourly.pop_first();
+        }
+        while self.daily.len() > DAILY_RETENTION {
+            self.daily.pop_first();
+        }
+    }
+
+    fn query(&self, period: TrafficPeriod, start: &str, end: &str) -> Vec<TrafficStatsBucket> {
+        let buckets = match period {
+            TrafficPeriod::Hour => &self.hourly,
+            TrafficPeriod::Day => &self.daily,
+            TrafficPeriod::Month => &self.monthly,
+        };
+
+        buckets
+            .iter()
+            .filter(|(key, _)| start.is_empty() || key.as_str() >= start)
+            .filter(|(key, _)| end.is_empty() || key.as_str() <= end)
+            .map(|(key, bucket)| TrafficStatsBucket {
+                key: key.clone(),
+                upload: bucket.totals.upload,
+                download: bucket.totals.download,
+                proxies: usage_list(&bucket.proxies),
+                processes: usage_list(&bucket.processes),
+            })
+            .collect()
+    }
+}
+
+fn usage_list(usage: &BTreeMap<String, TrafficTotals>) -> Vec<TrafficUsage> {
+    let mut list: Vec<TrafficUsage> = usage
+        .iter()
+        .map(|(name, totals)| TrafficUsage {
+            name: name.clone(),
+            upload: totals.upload,
+            download: totals.download,
+        })
+        .collect();
+    list.sort_by_key(|usage| std::cmp::Reverse(usage.upload.saturating_add(usage.download)));
+    list
+}
+
+// 流量统计器：账本及连接快照状态
+struct TrafficAccountant {
+    path: PathBuf,
+    ledger: TrafficLedger,
+    tracker: ConnectionTracker,
+    labels: HashMap<String, (String, String)>, // 连接 ID → (出口节点, 进程)
+    is_baseline_pending: bool,                 // 首个快照仅作为基准，不计入细分
+    is_dirty: bool,
+}
+
+impl TrafficAccountant {
+    // 读取账本，文件损坏时另存后重新开始统计
+    fn load(data_dir: &Path) -> Result<Self, String> {
+        let path = data_dir.join(STATS_FILE_NAME);
+        let ledger = match fs::read_to_string(&path) {
+            Ok(content) => match serde_json::from_str(&content) {
+                Ok(ledger) => ledger,
+                Err(e) => {
+                    log::warn!("流量统计文件损坏，已另存并重新开始统计：{}", e);
+                    let _ = fs::rename(&path, path.with_extension("json.corrupt"));
+                    TrafficLedger::default()
+                }
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => TrafficLedger::default(),
+            Err(e) => return Err(format!("读取流量统计失败：{}", e)),
+        };
+
+        Ok(Self {
+            path,
+            ledger,
+            tracker: ConnectionTracker::new(),
+            labels: HashMap::new(),
+            is_baseline_pending: true,
+            is_dirty: false,
+        })
+    }
+
+    fn record_traffic(&mut self, time: DateTime<Local>, upload: u64, download: u64) {
+        if upload == 0 && download == 0 {
+            return;
+        }
+        self.ledger.record_total(time, upload, download);
+        self.is_dirty = true;
+    }
+
+    fn record_connections(&mut self, time: DateTime<Local>, snapshot: ConnectionsSnapshot) {
+        let diff = self.tracker.update(snapshot, Instant::now());
+        let is_baseline = std::mem::take(&mut self.is_baseline_pending);
+
+        for entry in diff.added {
+            let proxy = entry.chains.first().cloned().unwrap_or_default();
+            // 统计开始前已存在的连接，其累计流量可能已在上次统计中计入
+            if !is_baseline {
+                self.ledger.record_usage(
+                    time,
+                    &proxy,
+                    &entry.process,
+                    entry.upload,
+                    entry.download,
+                );
+                self.is_dirty = true;
+            }
+            self.labels.insert(entry.id, (proxy, entry.process));
+        }
+
+        for update in diff.updated {
+            if let Some((proxy, process)) = self.labels.get(&update.id) {
+                self.ledger.record_usage(
+                    time,
+                    proxy,
+                    process,
+                    update.upload_delta,
+                    update.download_delta,
+                );
+                self.is_dirty = true;
+            }
+        }
+
+        for id in diff.closed {
+            self.labels.remove(&id);
+        }
+    }
+
+    // 先写入临时文件再替换，避免写入中断导致账本损坏
+    fn flush(&mut self) -> Result<(), String> {
+        if !self.is_dirty {
+            return Ok(());
+        }
+
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("创建流量统计目录失败：{}", e))?;
+        }
+
+        let content = serde_json::to_string(&self.ledger)
+            .map_err(|e| format!("序列化流量统计失败：{}", e))?;
+        let temp_path = self.path.with_extension("json.tmp");
+        fs::write(&temp_path, content).map_err(|e| format!("写入流量统计失败：{}", e))?;
+        fs::rename(&temp_path, &self.path).map_err(|e| format!("替换流量统计文件失败：{}", e))?;
+
+        self.is_dirty = false;
+        Ok(())
+    }
+}
+
+// 当前的流量统计器（停止统计后保留，用于查询）
+static ACCOUNTANT: Lazy<Mutex<Option<TrafficAccountant>>> = Lazy::new(|| Mutex::new(None));
+
+// 统计运行时的 WebSocket 订阅及定时写入任务
+struct AccountingTasks {
+    subscription_ids: Vec<u32>,
+    flush_task: JoinHandle<()>,
+}
+
+static ACCOUNTING_TASKS: Lazy<tokio::sync::Mutex<Option<AccountingTasks>>> =
+    Lazy::new(|| tokio::sync::Mutex::new(None));
+
+// 在统计器上执行操作（锁失效时记录错误并跳过）
+fn with_accountant<R>(action: impl FnOnce(&mut TrafficAccountant) -> R) -> Option<R> {
+    match ACCOUNTANT.lock() {
+        Ok(mut guard) => guard.as_mut().map(action),
+        Err(e) => {
+            log::error!("流量统计状态不可用：{}", e);
+            None
+        }
+    }
+}
+
+// 写入流量统计（应用退出时调用）
+pub fn flush() {
+    if let Some(Err(e)) = with_accountant(TrafficAccountant::flush) {
+        log::error!("{}", e);
+    }
+}
+
+impl StartTrafficAccounting {
+    async fn handle(self) {
+        let result = self.start().await;
+        if let Err(e) = &result {
+            log::error!("启动流量统计失败：{}", e);
+        }
+
+        TrafficAccountingResult {
+            is_successful: result.is_ok(),
+            error_message: result.err(),
+        }
+        .send_signal_to_dart();
+    }
+
+    async fn start(self) -> Result<(), String> {
+        log::info!("开始流量统计");
+
+        // 重复开始时先结束旧的统计
+        stop_accounting().await;
+
+        let accountant = TrafficAccountant::load(Path::new(&self.data_dir))?;
+        match ACCOUNTANT.lock() {
+            Ok(mut guard) => {
+                if let Some(previous) = guard.as_mut()
+                    && let Err(e) = previous.flush()
+                {
+                    log::error!("{}", e);
+                }
+                *guard = Some(accountant);
+            }
+            Err(e) => return Err(format!("流量统计状态不可用：{}", e)),
+        }
+
+        let traffic_id = subscribe_ws("/traffic", |json_value| {
+            if let Some(obj) = json_value.as_object() {
+                let upload = obj.get("up").and_then(|v| v.as_u64()).unwrap_or(0);
+                let download = obj.get("down").and_then(|v| v.as_u64()).unwrap_or(0);
+                with_accountant(|accountant| {
+                    accountant.record_traffic(Local::now(), upload, download)
+                });
+            }
+        })
+        .await?;
+
+        let connections_id = match subscribe_ws("/connections", |json_value| {
+            match serde_json::from_value::<ConnectionsSnapshot>(json_value) {
+                Ok(snapshot) => {
+                    with_accountant(|accountant| {
+                        accountant.record_connections(Local::now(), snapshot)
+                    });
+                }
+                Err(e) => log::error!("连接快照解析失败：{}", e),
+            }
+        })
+        .await
+        {
+            Ok(id) => id,
+            Err(e) => {
+                unsubscribe_ws(traffic_id).await;
+                return Err(e);
+            }
+        };
+
+        let flush_task = spawn(async {
+            let mut interval = tokio::time::interval(FLUSH_INTERVAL);
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                flush();
+            }
+        });
+
+        *ACCOUNTING_TASKS.lock().await = Some(AccountingTasks {
+            subscription_ids: vec![traffic_id, connections_id],
+            flush_task,
+        });
+
+        Ok(())
+    }
+}
+
+// 断开订阅并写入账本
+async fn stop_accounting() {
+    let Some(tasks) = ACCOUNTING_TASKS.lock().await.take() else {
+        return;
+    };
+
+    tasks.flush_task.abort();
+    for id in tasks.subscription_ids {
+        unsubscribe_ws(id).await;
+    }
+
+    // 下次开始时重新建立连接基准
+    with_accountant(|accountant| {
+        accountant.tracker = ConnectionTracker::new();
+        accountant.labels.clear();
+        accountant.is_baseline_pending = true;
+    });
+    flush();
+}
+
+impl StopTrafficAccounting {
+    async fn handle() {
+        log::info!("停止流量统计");
+        stop_accounting().await;
+
+        TrafficAccountingResult {
+            is_successful: true,
+            error_message: None,
+        }
+        .send_signal_to_dart();
+    }
+}
+
+impl GetTrafficStatsRequest {
+    fn handle(self) {
+        let result = with_accountant(|accountant| {
+            accountant.ledger.query(self.period, &self.start, &self.end)
+        })
+        .map(Ok)
+        .unwrap_or_else(|| {
+            TrafficAccountant::load(Path::new(&self.data_dir))
+                .map(|accountant| accountant.ledger.query(self.period, &self.start, &self.end))
+        });
+
+        let response = match result {
+            Ok(buckets) => GetTrafficStatsResponse {
+                period: self.period,
+                buckets,
+                error_message: None,
+            },
+            Err(e) => {
+                log::error!("查询流量统计失败：{}", e);
+                GetTrafficStatsResponse {
+                    period: self.period,
+                    buckets: Vec::new(),
+                    error_message: Some(e),
+                }
+            }
+        };
+        response.send_signal_to_dart();
+    }
+}
+
+// 初始化流量统计消息监听器
+pub fn init_message_listeners() {
+    spawn(async {
+        let receiver = StartTrafficAccounting::get_dart_signal_receiver();
+        while let Some(dart_signal) = receiver.recv().await {
+            dart_signal.message.handle().await;
+        }
+        log::info!("流量统计启动消息通道已关闭，退出监听器");
+    });
+
+    spawn(async {
+        let receiver = StopTrafficAccounting::get_dart_signal_receiver();
+        while let Some(_dart_signal) = receiver.recv().await {
+            StopTrafficAccounting::handle().await;
+        }
+        log::info!("流量统计停止消息通道已关闭，退出监听器");
+    });
+
+    spawn(async {
+        let receiver = GetTrafficStatsRequest::get_dart_signal_receiver();
+        while let Some(dart_signal) = receiver.recv().await {
+            dart_signal.message.handle();
+        }
+        log::info!("流量统计查询消息通道已关闭，退出监听器");
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clash::network::api_client::{ConnectionInfo, ConnectionMetadata};
+    use chrono::TimeZone;
+
+    fn time(hour: u32) -> DateTime<Local> {
+        Local
+            .with_ymd_and_hms(2026, 1, 31, hour, 30, 0)
+            .single()
+            .unwrap_or_else(|| panic!("测试时间无效"))
+    }
+
+    fn connection(id: &str, proxy: &str, upload: u64, download: u64) -> ConnectionInfo {
+        ConnectionInfo {
+            id: id.to_string(),
+            chains: vec![proxy.to_string(), "节点选择".to_string()],
+            upload,
+            download,
+            metadata: ConnectionMetadata {
+                process: "chrome".to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn snapshot(connections: Vec<ConnectionInfo>) -> ConnectionsSnapshot {
+        ConnectionsSnapshot {
+            connections,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_traffic_accounting() {
+        let Ok(mut accountant) = TrafficAccountant::load(Path::new("/nonexistent")) else {
+            panic!("加载空账本失败");
+        };
+
+        accountant.record_traffic(time(8), 100, 1000);
+        accountant.record_traffic(time(9), 10, 10);
+
+        let hours = accountant.ledger.query(TrafficPeriod::Hour, "", "");
+        assert_eq!(hours.len(), 2);
+        assert_eq!(hours[0].key, "2026-01-31 08");
+        assert_eq!((hours[0].upload, hours[0].download), (100, 1000));
+
+        let days = accountant
+            .ledger
+            .query(TrafficPeriod::Day, "2026-01-31", "2026-01-31");
+        assert_eq!((days[0].upload, days[0].download), (110, 1010));
+        assert!(
+            accountant
+                .ledger
+                .query(TrafficPeriod::Month, "2026-02", "")
+                .is_empty()
+        );
+
+        // 首个快照仅作为基准
+        accountant.record_connections(time(9), snapshot(vec![connection("a", "香港 01", 50, 50)]));
+        accountant.record_connections(
+            time(9),
+            snapshot(vec![
+                connection("a", "香港 01", 60, 150),
+                connection("b", "日本 01", 5, 5),
+            ]),
+        );
+
+        let months = accountant.ledger.query(TrafficPeriod::Month, "", "");
+        assert_eq!(
+            months[0].proxies,
+            vec![
+                TrafficUsage {
+                    name: "香港 01".to_string(),
+                    upload: 10,
+                    download: 100,
+                },
+                TrafficUsage {
+                    name: "日本 01".to_string(),
+                    upload: 5,
+                    download: 5,
+                },
+            ]
+        );
+        assert_eq!(months[0].processes[0].upload, 15);
+    }
+}
@@ -18,4 +18,5 @@ async fn main() {
 
     dart_shutdown().await;
     clash::process::cleanup();
+    clash::traffic_stats::flush();
 }